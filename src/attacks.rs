@@ -0,0 +1,59 @@
+// Attack maps and square-control queries
+//
+// A square counts as attacked by a piece if the piece could capture an enemy piece standing
+// there, whether or not the square is empty or holds a piece of the same color. Pins are
+// ignored and sliders stop at the first piece in their way, so x-rays are not counted.
+
+use alloc::vec::Vec;
+
+use crate::bitboard;
+use crate::{Board, Color, Piece, Square};
+
+impl Board {
+    // pieces of the given color attacking the square
+    pub fn attackers_to(&self, square: Square, color: Color) -> Vec<&Piece> {
+        let occupied = self.color_bb(Color::White) | self.color_bb(Color::Black);
+        let attackers = self.attackers_bb(square, color, occupied);
+
+        self.pieces_of(color)
+            .iter()
+            .flatten()
+            .filter(|p| attackers & bitboard::bit(p.square) != 0)
+            .collect()
+    }
+
+    // returns true if any piece of the given color attacks the square
+    pub fn is_square_attacked(&self, square: Square, by: Color) -> bool {
+        let occupied = self.color_bb(Color::White) | self.color_bb(Color::Black);
+        self.is_attacked(square, by, occupied, 0)
+    }
+
+    // number of pieces of the given color attacking each square, indexed by Square::index
+    pub fn get_attack_map(&self, color: Color) -> [u8; 64] {
+        let occupied = self.color_bb(Color::White) | self.color_bb(Color::Black);
+        let mut map = [0u8; 64];
+
+        for piece in self.pieces_of(color).iter().flatten() {
+            for sq in bitboard::squares(bitboard::attacks(piece.kind, color, piece.square, occupied)) {
+                map[sq.index() as usize] += 1;
+            }
+        }
+
+        map
+    }
+
+    // number of pieces of the given color defending each of its own pieces, indexed like
+    // get_attack_map; squares without a piece of that color are 0
+    pub fn get_defense_map(&self, color: Color) -> [u8; 64] {
+        let mut map = self.get_attack_map(color);
+        let own = self.color_bb(color);
+
+        for sq in Square::all() {
+            if own & bitboard::bit(sq) == 0 {
+                map[sq.index() as usize] = 0;
+            }
+        }
+
+        map
+    }
+}
@@ -0,0 +1,486 @@
+// Command-line interface for inspecting positions and playing through games
+//
+// Reads one command per line, with line editing and a history kept in ~/.alpha-rust-history.
+// Anything that is not a command is taken as a move in SAN or UCI notation. Type help for the
+// list of commands. A command can also be given on the command line, e.g. alpha-rust-cli bench,
+// to run just that command and exit.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod cli {
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::time::Instant;
+
+    use alpha_rust::mcts::{HeuristicEvaluator, MctsConfig};
+    use alpha_rust::pgn::Game;
+    use alpha_rust::search::{self, SearchInfo, SearchLimits, SearchOptions, BENCH_POSITIONS, DEFAULT_HASH};
+    use alpha_rust::selfplay::{SelfPlay, SelfPlayConfig, SelfPlayGame};
+    use alpha_rust::tt::TranspositionTable;
+    use alpha_rust::{Board, Color, GameStatus, Move, Stipulation};
+
+    use rustyline::error::ReadlineError;
+    use rustyline::DefaultEditor;
+
+    const HELP: &str = "\
+commands:
+  show, d               print the board
+  ascii, unicode        print pieces as letters or as chess symbols
+  flip                  turn the board around
+  new                   start a new game
+  fen [FEN]             print the position as FEN, or set up a new game from one
+  moves                 list the legal moves
+  <move>, move <move>   play a move in SAN (Nf3, exd5, O-O, e8=Q) or UCI (g1f3)
+  undo                  take back the last move
+  eval                  static evaluation of the position
+  go [depth N] [movetime MS] [nodes N] [multipv N]
+                        search the position, by default to depth 6, for the best N moves
+  mate N                find a forced mate in at most N moves and check the key is unique
+  helpmate N, selfmate N
+                        solve a helpmate or selfmate in N for the side to move
+  selfplay N FILE [mcts] [depth N] [movetime MS] [nodes N]
+                        play N games against itself from this position, with the alpha-beta or
+                        the tree search, and add their positions to FILE as JSON lines
+  perft N               count the positions N plies deep, split by the first move
+  bench [N]             search a fixed set of positions to depth N (default 5) and count the nodes
+  pgn                   print the game as PGN
+  pgn save FILE         save the game to a PGN file
+  load FILE             load the first game in a PGN file
+  help                  show this list
+  quit, exit            leave";
+
+    // deep enough for the selective search to matter, a few seconds in a release build
+    const BENCH_DEPTH: u32 = 5;
+
+    struct Cli {
+        game: Game,
+        board: Board,
+        unicode: bool,
+        flipped: bool
+    }
+
+    impl Cli {
+        fn new() -> Cli {
+            Cli {
+                game: Game::new(),
+                board: Board::new("".to_string()),
+                unicode: true,
+                flipped: false
+            }
+        }
+
+        // runs a line of input, returns false when it is time to quit
+        fn execute(&mut self, line: &str) -> bool {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let rest = |n: usize| words[n.min(words.len())..].join(" ");
+
+            match words.as_slice() {
+                [] => {},
+                ["quit"] | ["exit"] => return false,
+                ["help"] => println!("{}", HELP),
+                ["show"] | ["d"] => self.show(),
+                ["ascii"] => {
+                    self.unicode = false;
+                    self.show();
+                },
+                ["unicode"] => {
+                    self.unicode = true;
+                    self.show();
+                },
+                ["flip"] => {
+                    self.flipped = !self.flipped;
+                    self.show();
+                },
+                ["new"] => self.set_game(Game::new()),
+                ["fen"] => println!("{}", self.board.get_fen()),
+                ["fen", ..] => match Board::from_fen(&rest(1)) {
+                    Ok(board) => self.set_game(Game::from_fen(&board.get_fen())),
+                    Err(e) => println!("{}", e)
+                },
+                ["moves"] => self.list_moves(),
+                ["move", mv] => self.play(mv),
+                ["undo"] => match self.game.undo() {
+                    Some(_) => {
+                        self.board = self.game.board();
+                        self.show();
+                    },
+                    None => println!("no moves to take back")
+                },
+                ["eval"] => {
+                    let eval = self.board.evaluate();
+                    let white = if self.board.get_side_to_move() == Color::White { eval } else { -eval };
+                    println!("{} (from white's point of view)", format_score(white));
+                },
+                ["go", args @ ..] => match parse_limits(args) {
+                    Some((limits, options)) => self.go(&limits, &options),
+                    None => println!("usage: go [depth N] [movetime MS] [nodes N] [multipv N]")
+                },
+                [kind @ ("mate" | "helpmate" | "selfmate"), moves] => {
+                    let stipulation = match *kind {
+                        "mate" => Stipulation::Mate,
+                        "helpmate" => Stipulation::Helpmate,
+                        _ => Stipulation::Selfmate
+                    };
+
+                    match moves.parse::<u32>() {
+                        Ok(moves) if moves > 0 => self.solve(stipulation, moves),
+                        _ => println!("usage: {} N", kind)
+                    }
+                },
+                ["selfplay", games, path, args @ ..] => match (games.parse::<u32>(), parse_selfplay(args)) {
+                    (Ok(games), Some((tree_search, limits))) if games > 0 => self.selfplay(games, path, tree_search, limits),
+                    _ => println!("usage: selfplay N FILE [mcts] [depth N] [movetime MS] [nodes N]")
+                },
+                ["perft", depth] => match depth.parse::<u32>() {
+                    Ok(depth) => self.perft(depth),
+                    Err(_) => println!("usage: perft N")
+                },
+                ["bench"] => bench(BENCH_DEPTH),
+                ["bench", depth] => match depth.parse::<u32>() {
+                    Ok(depth) if depth > 0 => bench(depth),
+                    _ => println!("usage: bench [N]")
+                },
+                ["pgn"] => print!("{}", self.game_with_result().to_pgn()),
+                ["pgn", "save", ..] if words.len() > 2 => {
+                    let path = rest(2);
+                    match fs::write(&path, self.game_with_result().to_pgn()) {
+                        Ok(()) => println!("saved to {}", path),
+                        Err(e) => println!("could not save {}: {}", path, e)
+                    }
+                },
+                ["load", ..] if words.len() > 1 => {
+                    let path = rest(1);
+                    match fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|text| text.parse::<Game>().map_err(|e| e.to_string())) {
+                        Ok(game) => self.set_game(game),
+                        Err(e) => println!("could not load {}: {}", path, e)
+                    }
+                },
+                [mv] => self.play(mv),
+                _ => println!("unknown command, type help for a list")
+            }
+
+            true
+        }
+
+        fn set_game(&mut self, game: Game) {
+            self.board = game.board();
+            self.game = game;
+            self.show();
+        }
+
+        // the game with its Result tag filled in when the game is over on the board
+        fn game_with_result(&self) -> Game {
+            let mut game = self.game.clone();
+            let result = game.result();
+            game.set_tag("Result", &result);
+            game
+        }
+
+        fn show(&self) {
+            let board = &self.board;
+            let display = board.display().flipped(self.flipped);
+
+            if self.unicode {
+                println!("{:#}", display);
+            } else {
+                println!("{}", display);
+            }
+
+            let winner = if board.get_side_to_move() == Color::White { "Black" } else { "White" };
+            match board.status() {
+                GameStatus::Checkmate => println!("checkmate, {} wins", winner),
+                GameStatus::Stalemate => println!("draw by stalemate"),
+                GameStatus::FiftyMoveRule => println!("draw by the fifty-move rule"),
+                GameStatus::InsufficientMaterial => println!("draw by insufficient material"),
+                _ => {}
+            }
+        }
+
+        fn list_moves(&self) {
+            let mut moves: Vec<String> = self.board.get_legal_moves().into_iter().map(|mv| self.board.to_san(mv)).collect();
+            moves.sort();
+
+            println!("{} legal moves: {}", moves.len(), moves.join(" "));
+        }
+
+        // plays a move written in SAN or UCI
+        fn play(&mut self, text: &str) {
+            match self.board.parse_move(text) {
+                Ok(mv) => {
+                    self.game.play(mv);
+                    self.board.play_move(mv);
+                    self.show();
+                },
+                Err(e) => println!("{}: {}", e, text)
+            }
+        }
+
+        // the hashes of the game's positions before the current one, for the search to tell
+        // repetitions
+        fn history(&self) -> Vec<u64> {
+            let mut board = self.game.start();
+
+            self.game.moves().iter().map(|mv| {
+                let hash = board.hash();
+                board.play_move(*mv);
+                hash
+            }).collect()
+        }
+
+        fn go(&self, limits: &SearchLimits, options: &SearchOptions) {
+            let board = &self.board;
+
+            // with more than one line, each is numbered and the depth only shown on the first
+            let mut report = |info: &SearchInfo| {
+                if options.multipv > 1 {
+                    let depth = if info.multipv == 1 { format!("depth {:2}", info.depth) } else { String::new() };
+                    println!("{:8}  {:2}.  {:>7}  {}", depth, info.multipv, format_search_score(board, info.score), pv_san(board, &info.pv));
+                } else {
+                    println!("depth {:2}  {:>7}  {:>9} nodes  {:>6} ms  {:>8} nps  {}",
+                        info.depth, format_search_score(board, info.score), info.nodes, info.time, info.nps(), pv_san(board, &info.pv));
+                }
+            };
+
+            let tt = TranspositionTable::new(DEFAULT_HASH);
+
+            match search::search_with(board, &self.history(), limits, &tt, options, &|| false, &mut report) {
+                Some(result) if options.multipv > 1 => {
+                    let moves: Vec<String> = result.lines.iter().map(|line| board.to_san(line.pv[0])).collect();
+                    println!("best moves: {}  ({} nodes, {} ms)", moves.join(" "), result.nodes, result.time);
+                },
+                Some(result) => println!("best move: {}", board.to_san(result.best_move)),
+                None => println!("no legal moves")
+            }
+        }
+
+        // the shortest solution, and every key that solves it in that many moves, more than one
+        // being a cook
+        fn solve(&self, stipulation: Stipulation, moves: u32) {
+            let board = &self.board;
+
+            let line = match board.solve(stipulation, moves) {
+                Some(line) => line,
+                None => return println!("no solution in {}", moves)
+            };
+
+            // the moves of the side to move, the key included
+            let needed = line.len().div_ceil(2) as u32;
+            println!("solution in {}: {}", needed, pv_san(board, &line));
+
+            let keys = board.key_moves(stipulation, needed);
+            if keys.len() > 1 {
+                let keys: Vec<String> = keys.iter().map(|mv| board.to_san(*mv)).collect();
+                println!("cooked, every key: {}", keys.join(" "));
+            } else {
+                println!("the key is unique");
+            }
+        }
+
+        // plays the games one after the other, writing each one's positions as soon as it is over
+        fn selfplay(&self, games: u32, path: &str, tree_search: bool, limits: SearchLimits) {
+            let mut file = match OpenOptions::new().create(true).append(true).open(path) {
+                Ok(file) => file,
+                Err(e) => return println!("could not open {}: {}", path, e)
+            };
+
+            let config = SelfPlayConfig { limits, ..SelfPlayConfig::default() };
+            let mut play = |selfplay: &mut dyn FnMut() -> Option<SelfPlayGame>| {
+                for i in 1..=games {
+                    let game = match selfplay() {
+                        Some(game) => game,
+                        None => return println!("no legal moves")
+                    };
+
+                    if let Err(e) = file.write_all(game.to_jsonl().as_bytes()) {
+                        return println!("could not write to {}: {}", path, e);
+                    }
+                    println!("game {}: {} in {} plies, {:?}", i, game.game.result(), game.game.moves().len(), game.ending);
+                }
+            };
+
+            // the tree search adds noise at the root, as AlphaZero's self-play does
+            if tree_search {
+                let noise = MctsConfig { noise_fraction: 0.25, ..MctsConfig::default() };
+                let mut selfplay = SelfPlay::tree_search(config, HeuristicEvaluator, noise);
+                play(&mut || selfplay.play_game(&self.board, &|| false));
+            } else {
+                let mut selfplay = SelfPlay::alpha_beta(config);
+                play(&mut || selfplay.play_game(&self.board, &|| false));
+            }
+        }
+
+        fn perft(&self, depth: u32) {
+            let mut total = 0;
+            let mut divide: Vec<(String, u64)> = self.board.divide(depth).into_iter().map(|(mv, count)| (mv.to_string(), count)).collect();
+            divide.sort();
+
+            for (mv, count) in divide {
+                println!("{}: {}", mv, count);
+                total += count;
+            }
+
+            println!();
+            println!("total: {}", if depth == 0 { 1 } else { total });
+        }
+    }
+
+    // searches every bench position to the same depth, so node counts can be compared between
+    // versions of the search and the speed between builds
+    fn bench(depth: u32) {
+        let limits = SearchLimits { depth: Some(depth), ..SearchLimits::default() };
+        let start = Instant::now();
+        let mut nodes = 0;
+
+        for (i, fen) in BENCH_POSITIONS.iter().enumerate() {
+            let board = Board::new(fen.to_string());
+
+            match search::search(&board, &[], &limits, &|| false, &mut |_| {}) {
+                Some(result) => {
+                    println!("position {:2}  depth {:2}  {:>9} nodes  {:>6} ms  {:>7}  {}",
+                        i + 1, result.depth, result.nodes, result.time, format_search_score(&board, result.score), board.to_san(result.best_move));
+                    nodes += result.nodes;
+                },
+                None => println!("position {:2}  no legal moves", i + 1)
+            }
+        }
+
+        let time = start.elapsed().as_millis() as u64;
+        println!();
+        println!("{} nodes  {} ms  {} nps", nodes, time, nodes * 1000 / time.max(1));
+    }
+
+    // centipawns as pawns with a sign, e.g. +0.35
+    fn format_score(score: i32) -> String {
+        format!("{}{:.2}", if score >= 0 { "+" } else { "-" }, (score.abs() as f64) / 100.0)
+    }
+
+    // the search score from white's point of view, or the moves to mate
+    fn format_search_score(board: &Board, score: i32) -> String {
+        let sign = if board.get_side_to_move() == Color::White { 1 } else { -1 };
+
+        match search::mate_in(score) {
+            Some(moves) => format!("#{}", sign * moves),
+            None => format_score(sign * score)
+        }
+    }
+
+    // the principal variation in SAN with move numbers, e.g. 1. e4 e5 2. Nf3
+    fn pv_san(board: &Board, pv: &[Move]) -> String {
+        let mut board = board.clone();
+        let mut number = board.get_fen().split_whitespace().nth(5).and_then(|n| n.parse::<u32>().ok()).unwrap_or(1);
+        let mut text = Vec::new();
+
+        for (ply, mv) in pv.iter().enumerate() {
+            match board.get_side_to_move() {
+                Color::White => text.push(format!("{}.", number)),
+                Color::Black if ply == 0 => text.push(format!("{}...", number)),
+                Color::Black => {}
+            }
+
+            text.push(board.to_san(*mv));
+
+            if board.get_side_to_move() == Color::Black { number += 1; }
+            board.play_move(*mv);
+        }
+
+        text.join(" ")
+    }
+
+    // search limits and the number of lines from the words after go; a search without any
+    // limit goes to depth 6
+    fn parse_limits(args: &[&str]) -> Option<(SearchLimits, SearchOptions)> {
+        let mut limits = SearchLimits::default();
+        let mut options = SearchOptions::default();
+
+        for pair in args.chunks(2) {
+            match pair {
+                ["depth", n] => limits.depth = Some(n.parse().ok()?),
+                ["movetime", n] => limits.movetime = Some(n.parse().ok()?),
+                ["nodes", n] => limits.nodes = Some(n.parse().ok()?),
+                ["multipv", n] => options.multipv = n.parse().ok().filter(|n| *n > 0)?,
+                _ => return None
+            }
+        }
+
+        if limits == SearchLimits::default() {
+            limits.depth = Some(6);
+        }
+
+        Some((limits, options))
+    }
+
+    // whether to use the tree search and the limits for every move, from the words after
+    // selfplay N FILE; without limits the tree search makes 200 playouts and the alpha-beta
+    // search goes to depth 4
+    fn parse_selfplay(args: &[&str]) -> Option<(bool, SearchLimits)> {
+        let tree_search = args.first() == Some(&"mcts");
+        let args = if tree_search { &args[1..] } else { args };
+        let (mut limits, _) = parse_limits(args)?;
+
+        if args.is_empty() {
+            limits = if tree_search {
+                SearchLimits { nodes: Some(200), ..SearchLimits::default() }
+            } else {
+                SelfPlayConfig::default().limits
+            };
+        }
+
+        Some((tree_search, limits))
+    }
+
+    fn history_path() -> Option<PathBuf> {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".alpha-rust-history"))
+    }
+
+    pub fn run() {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        if !args.is_empty() {
+            Cli::new().execute(&args.join(" "));
+            return;
+        }
+
+        let mut editor = match DefaultEditor::new() {
+            Ok(editor) => editor,
+            Err(e) => {
+                eprintln!("could not start the line editor: {}", e);
+                return;
+            }
+        };
+
+        let history = history_path();
+        if let Some(path) = history.as_ref() {
+            // there is no history yet the first time round
+            let _ = editor.load_history(path);
+        }
+
+        let mut cli = Cli::new();
+        println!("alpha-rust {}, type help for a list of commands", env!("CARGO_PKG_VERSION"));
+        cli.show();
+
+        loop {
+            match editor.readline("> ") {
+                Ok(line) => {
+                    if !line.trim().is_empty() {
+                        let _ = editor.add_history_entry(line.as_str());
+                    }
+                    if !cli.execute(line.trim()) { break; }
+                },
+                Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => break,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    break;
+                }
+            }
+        }
+
+        if let Some(path) = history.as_ref() {
+            let _ = editor.save_history(path);
+        }
+    }
+}
+
+fn main() {
+    // there is no terminal to talk to on wasm, the binary only does anything in native builds
+    #[cfg(not(target_arch = "wasm32"))]
+    cli::run();
+}
@@ -0,0 +1,7 @@
+// UCI engine for chess GUIs, see alpha_rust::uci for the commands it understands
+
+fn main() {
+    // GUIs run engines as native processes, the binary only does anything in native builds
+    #[cfg(not(target_arch = "wasm32"))]
+    alpha_rust::uci::run();
+}
@@ -0,0 +1,159 @@
+// Bitboard helpers used for move generation and attack detection
+//
+// bit n of a bitboard is the square with index n, from 0 (a1) to 63 (h8)
+
+use crate::{Color, PieceType, Square};
+
+pub(crate) fn bit(sq: Square) -> u64 {
+    1u64 << sq.index()
+}
+
+// iterates over the squares of the set bits, lowest first
+pub(crate) fn squares(mut bb: u64) -> impl Iterator<Item = Square> {
+    core::iter::from_fn(move || {
+        if bb == 0 {
+            None
+        } else {
+            let sq = Square::from_bit_index(bb.trailing_zeros());
+            bb &= bb - 1;
+            Some(sq)
+        }
+    })
+}
+
+// the lowest square in the bitboard, None if it is empty
+pub(crate) fn first(bb: u64) -> Option<Square> {
+    squares(bb).next()
+}
+
+// builds the attack set for a non-sliding piece from a list of (rank, file) offsets
+const fn step_attacks(offsets: [(i32, i32); 8]) -> [u64; 64] {
+    let mut table = [0u64; 64];
+    let mut sq = 0;
+
+    while sq < 64 {
+        let rank = sq as i32 / 8;
+        let file = sq as i32 % 8;
+        let mut i = 0;
+
+        while i < 8 {
+            let r = rank + offsets[i].0;
+            let f = file + offsets[i].1;
+
+            if r >= 0 && r < 8 && f >= 0 && f < 8 {
+                table[sq] |= 1u64 << (r * 8 + f);
+            }
+
+            i += 1;
+        }
+
+        sq += 1;
+    }
+
+    table
+}
+
+const KNIGHT_ATTACKS: [u64; 64] = step_attacks([(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)]);
+const KING_ATTACKS: [u64; 64] = step_attacks([(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)]);
+
+const ROOK_DIRECTIONS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+pub(crate) fn knight_attacks(sq: Square) -> u64 {
+    KNIGHT_ATTACKS[sq.index() as usize]
+}
+
+pub(crate) fn king_attacks(sq: Square) -> u64 {
+    KING_ATTACKS[sq.index() as usize]
+}
+
+// squares attacked by a pawn of the given color standing on sq
+pub(crate) fn pawn_attacks(color: Color, sq: Square) -> u64 {
+    let bb = bit(sq);
+    let not_a_file = !0x0101_0101_0101_0101u64;
+    let not_h_file = !0x8080_8080_8080_8080u64;
+
+    match color {
+        Color::White => ((bb << 7) & not_h_file) | ((bb << 9) & not_a_file),
+        Color::Black => ((bb >> 9) & not_h_file) | ((bb >> 7) & not_a_file),
+    }
+}
+
+// walks each direction until it leaves the board or hits an occupied square (which is included)
+fn slider_attacks(sq: Square, occupied: u64, directions: &[(i32, i32); 4]) -> u64 {
+    let mut attacks = 0;
+
+    for (dr, df) in directions.iter() {
+        let mut next = sq.offset(*df, *dr);
+
+        while let Some(target) = next {
+            attacks |= bit(target);
+
+            if occupied & bit(target) != 0 {
+                break;
+            }
+
+            next = target.offset(*df, *dr);
+        }
+    }
+
+    attacks
+}
+
+pub(crate) fn bishop_attacks(sq: Square, occupied: u64) -> u64 {
+    slider_attacks(sq, occupied, &BISHOP_DIRECTIONS)
+}
+
+pub(crate) fn rook_attacks(sq: Square, occupied: u64) -> u64 {
+    slider_attacks(sq, occupied, &ROOK_DIRECTIONS)
+}
+
+pub(crate) fn queen_attacks(sq: Square, occupied: u64) -> u64 {
+    bishop_attacks(sq, occupied) | rook_attacks(sq, occupied)
+}
+
+// squares attacked by a piece of the given kind and color standing on sq
+pub(crate) fn attacks(kind: PieceType, color: Color, sq: Square, occupied: u64) -> u64 {
+    match kind {
+        PieceType::Pawn => pawn_attacks(color, sq),
+        PieceType::Knight => knight_attacks(sq),
+        PieceType::Bishop => bishop_attacks(sq, occupied),
+        PieceType::Rook => rook_attacks(sq, occupied),
+        PieceType::Queen => queen_attacks(sq, occupied),
+        PieceType::King => king_attacks(sq)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sq(index: u8) -> Square {
+        Square::from_index(index).unwrap()
+    }
+
+    #[test]
+    fn test_step_attacks() {
+        // knight on a1 reaches b3 and c2
+        assert_eq!(knight_attacks(sq(0)), bit(sq(17)) | bit(sq(10)));
+        // king in the corner has three neighbours, in the middle eight
+        assert_eq!(king_attacks(sq(63)).count_ones(), 3);
+        assert_eq!(king_attacks(sq(27)).count_ones(), 8);
+    }
+
+    #[test]
+    fn test_pawn_attacks_do_not_wrap() {
+        // white pawn on a2 only attacks b3, black pawn on h7 only attacks g6
+        assert_eq!(pawn_attacks(Color::White, sq(8)), bit(sq(17)));
+        assert_eq!(pawn_attacks(Color::Black, sq(55)), bit(sq(46)));
+    }
+
+    #[test]
+    fn test_slider_attacks_stop_at_blockers() {
+        // rook on a1 with a blocker on a4: a2, a3, a4 plus the whole first rank
+        let attacks = rook_attacks(sq(0), bit(sq(24)));
+        assert_eq!(attacks, bit(sq(8)) | bit(sq(16)) | bit(sq(24)) | 0xfe);
+        // bishop on d4 on an empty board covers 13 squares
+        assert_eq!(bishop_attacks(sq(27), 0).count_ones(), 13);
+    }
+}
@@ -0,0 +1,110 @@
+// Text diagrams of the board
+//
+// {} draws the board with FEN letters and {:#} with unicode chess symbols, both with rank and
+// file labels and a line saying whose move it is and whether they are in check:
+//
+//   8  r n b q k b n r
+//   7  p p p p p p p p
+//   6  . . . . . . . .
+//   5  . . . . . . . .
+//   4  . . . . P . . .
+//   3  . . . . . . . .
+//   2  P P P P . P P P
+//   1  R N B Q K B N R
+//      a b c d e f g h
+//   Black to move
+//
+// Board::display gives the same diagram with the option of black at the bottom.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{piece_char, Board, Color, File, PieceType, Rank, Square};
+
+// a diagram of the board, from Board::display
+#[derive(Clone, Copy)]
+pub struct BoardDisplay<'a> {
+    board: &'a Board,
+    flipped: bool
+}
+
+impl BoardDisplay<'_> {
+    // shows the board from black's side, with rank 1 at the top and the h file on the left
+    pub fn flipped(mut self, flipped: bool) -> Self {
+        self.flipped = flipped;
+        self
+    }
+}
+
+fn glyph(kind: PieceType, color: Color) -> char {
+    let glyphs = match color {
+        Color::White => ['♙', '♘', '♗', '♖', '♕', '♔'],
+        Color::Black => ['♟', '♞', '♝', '♜', '♛', '♚']
+    };
+
+    glyphs[kind as usize]
+}
+
+impl fmt::Display for BoardDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let unicode = f.alternate();
+        let empty = if unicode { '·' } else { '.' };
+
+        let ranks: Vec<Rank> = if self.flipped { Rank::all().collect() } else { Rank::all().rev().collect() };
+        let files: Vec<File> = if self.flipped { File::all().rev().collect() } else { File::all().collect() };
+
+        for rank in ranks.iter() {
+            write!(f, "{} ", rank)?;
+
+            for file in files.iter() {
+                let symbol = match self.board.find_piece(Square::new(*file, *rank)) {
+                    Some(piece) if unicode => glyph(piece.kind, piece.color),
+                    Some(piece) => piece_char(piece.kind, piece.color),
+                    None => empty
+                };
+
+                write!(f, " {}", symbol)?;
+            }
+
+            writeln!(f)?;
+        }
+
+        write!(f, "  ")?;
+        for file in files.iter() {
+            write!(f, " {}", file)?;
+        }
+        writeln!(f)?;
+
+        let side = match self.board.get_side_to_move() {
+            Color::White => "White",
+            Color::Black => "Black"
+        };
+
+        if self.board.in_check() {
+            write!(f, "{} to move, in check", side)
+        } else {
+            write!(f, "{} to move", side)
+        }
+    }
+}
+
+impl Board {
+    // a diagram of the board that can be flipped, see BoardDisplay
+    pub fn display(&self) -> BoardDisplay<'_> {
+        BoardDisplay { board: self, flipped: false }
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.display(), f)
+    }
+}
+
+// the diagram and the FEN, so failing assertions show the position
+impl fmt::Debug for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.fen)?;
+        fmt::Display::fmt(&self.display(), f)
+    }
+}
@@ -0,0 +1,160 @@
+// FEN checking
+//
+// Board::new takes any string and makes what it can of it. Board::from_fen checks the FEN first,
+// for anything that takes positions from outside: the board has to have eight ranks of eight
+// squares, one king of each color, and every field has to be well formed. The move counters can
+// be left out and default to 0 and 1, as in EPD.
+//
+// Castling rights and en passant squares that do not fit the position are dropped rather than
+// refused, since plenty of FENs in the wild carry stale ones: a right needs the king and that
+// rook still on their starting squares, and an en passant square needs the pawn that just
+// moved two squares past it, with the squares it came through empty.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::{Board, Square};
+
+// returned when a string is not a FEN the board can be set up from
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ParseFenError {
+    // not four or six fields
+    Fields,
+    // a rank that is not eight squares, not eight ranks, or a character that is not a piece
+    Placement,
+    // not exactly one king of each color
+    Kings,
+    SideToMove,
+    Castling,
+    EnPassant,
+    MoveCounters
+}
+
+impl fmt::Display for ParseFenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let field = match self {
+            ParseFenError::Fields => "number of fields",
+            ParseFenError::Placement => "piece placement",
+            ParseFenError::Kings => "number of kings",
+            ParseFenError::SideToMove => "side to move",
+            ParseFenError::Castling => "castling rights",
+            ParseFenError::EnPassant => "en passant square",
+            ParseFenError::MoveCounters => "move counters"
+        };
+
+        write!(f, "invalid FEN: bad {}", field)
+    }
+}
+
+impl Error for ParseFenError {}
+
+// the piece letter on each square of a checked placement, a1 first
+fn pieces(placement: &str) -> [Option<char>; 64] {
+    let mut board = [None; 64];
+
+    for (rank, row) in placement.split('/').enumerate() {
+        let mut file = 0;
+        for c in row.chars() {
+            match c.to_digit(10) {
+                Some(empty) => file += empty as usize,
+                None => {
+                    board[(7 - rank) * 8 + file] = Some(c);
+                    file += 1;
+                }
+            }
+        }
+    }
+
+    board
+}
+
+fn at(board: &[Option<char>; 64], square: &str) -> Option<char> {
+    square.parse::<Square>().ok().and_then(|square| board[square.index() as usize])
+}
+
+// the FEN with single spaces between fields, the move counters filled in and castling rights and
+// an en passant square that do not fit the position dropped
+fn normalize(fen: &str) -> Result<String, ParseFenError> {
+    let mut fields: Vec<String> = fen.split_whitespace().map(|field| field.to_string()).collect();
+
+    if fields.len() == 4 {
+        fields.extend(["0", "1"].iter().map(|field| field.to_string()));
+    }
+    if fields.len() != 6 { return Err(ParseFenError::Fields); }
+
+    let ranks: Vec<&str> = fields[0].split('/').collect();
+    let squares = |rank: &&str| rank.chars().map(|c| match c {
+        '1'..='8' => c.to_digit(10),
+        'p' | 'n' | 'b' | 'r' | 'q' | 'k' | 'P' | 'N' | 'B' | 'R' | 'Q' | 'K' => Some(1),
+        _ => None
+    }).sum::<Option<u32>>();
+
+    if ranks.len() != 8 || !ranks.iter().all(|rank| squares(rank) == Some(8)) {
+        return Err(ParseFenError::Placement);
+    }
+    let board = pieces(&fields[0]);
+
+    let kings = |king: char| fields[0].chars().filter(|c| *c == king).count();
+    if kings('K') != 1 || kings('k') != 1 {
+        return Err(ParseFenError::Kings);
+    }
+
+    if fields[1] != "w" && fields[1] != "b" {
+        return Err(ParseFenError::SideToMove);
+    }
+
+    if fields[2] != "-" && !fields[2].chars().all(|c| "KQkq".contains(c)) {
+        return Err(ParseFenError::Castling);
+    }
+
+    let castling: String = fields[2].chars().filter(|right| {
+        let (king, home, rook, corner) = match right {
+            'K' => ('K', "e1", 'R', "h1"),
+            'Q' => ('K', "e1", 'R', "a1"),
+            'k' => ('k', "e8", 'r', "h8"),
+            _ => ('k', "e8", 'r', "a8")
+        };
+
+        at(&board, home) == Some(king) && at(&board, corner) == Some(rook)
+    }).collect();
+    fields[2] = if castling.is_empty() { "-".to_string() } else { castling };
+
+    if fields[3] != "-" {
+        let square = fields[3].parse::<Square>().map_err(|_| ParseFenError::EnPassant)?;
+
+        // the pawn stands one square beyond it, seen from the side that moved it, and came from
+        // one square before it
+        let (pawn, forward) = if fields[1] == "w" { ('p', -1) } else { ('P', 1) };
+        let passed = square.rank().index() == if fields[1] == "w" { 5 } else { 2 };
+        let pawn_at = square.offset(0, forward).map(|sq| board[sq.index() as usize]);
+        let from = square.offset(0, -forward).map(|sq| board[sq.index() as usize]);
+
+        if !passed || board[square.index() as usize].is_some() || pawn_at != Some(Some(pawn)) || from != Some(None) {
+            fields[3] = "-".to_string();
+        }
+    }
+
+    if fields[4].parse::<u32>().is_err() || fields[5].parse::<u32>().is_err() {
+        return Err(ParseFenError::MoveCounters);
+    }
+
+    Ok(fields.join(" "))
+}
+
+impl Board {
+    // sets up a board from a FEN after checking it
+    pub fn from_fen(fen: &str) -> Result<Board, ParseFenError> {
+        normalize(fen).map(Board::new)
+    }
+}
+
+impl FromStr for Board {
+    type Err = ParseFenError;
+
+    fn from_str(fen: &str) -> Result<Board, ParseFenError> {
+        Board::from_fen(fen)
+    }
+}
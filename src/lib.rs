@@ -1,6 +1,60 @@
+// The core of the crate only needs alloc. The std feature adds what needs an operating system
+// (the search clock, tablebase files) and the wasm feature the JavaScript bindings.
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use core::str;
 
-use wasm_bindgen::prelude::*;
+mod attacks;
+mod bitboard;
+mod display;
+mod eval;
+mod fen;
+mod mate;
+mod material;
+mod moves;
+mod perft;
+mod rng;
+mod san;
+mod see;
+#[cfg(feature = "serde")]
+mod serialize;
+mod square;
+mod status;
+mod zobrist;
+
+pub mod mcts;
+pub mod nn;
+pub mod nnue;
+pub mod pgn;
+pub mod search;
+pub mod selfplay;
+pub mod svg;
+pub mod time;
+pub mod tt;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use display::BoardDisplay;
+pub use fen::ParseFenError;
+pub use mate::{PuzzleError, Stipulation};
+pub use moves::{Move, ParseMoveError, PromotionError};
+pub use san::ParseSanError;
+pub use square::{File, ParseSquareError, Rank, Square};
+pub use status::GameStatus;
+
+use moves::Special;
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub mod syzygy;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub mod uci;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
@@ -8,8 +62,8 @@ use wasm_bindgen::prelude::*;
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-#[wasm_bindgen]
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PieceType {
     Pawn,
     Knight,
@@ -19,119 +73,98 @@ pub enum PieceType {
     King
 }
 
-#[wasm_bindgen]
+impl PieceType {
+    // material value in centipawns, the king is priced above everything else so it is never traded
+    pub fn value(&self) -> i32 {
+        match self {
+            PieceType::Pawn => 100,
+            PieceType::Knight => 320,
+            PieceType::Bishop => 330,
+            PieceType::Rook => 500,
+            PieceType::Queen => 900,
+            PieceType::King => 20000
+        }
+    }
+
+    // the usual 1/3/3/5/9 count used to show who is ahead in material, the king counts for nothing
+    pub fn points(&self) -> i32 {
+        match self {
+            PieceType::Pawn => 1,
+            PieceType::Knight | PieceType::Bishop => 3,
+            PieceType::Rook => 5,
+            PieceType::Queen => 9,
+            PieceType::King => 0
+        }
+    }
+}
+
+// piece kinds in the same order as the indexes of the piece arrays
+const PIECE_KINDS: [PieceType; 6] = [
+    PieceType::Pawn,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+    PieceType::King
+];
+
 #[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Color {
     White,
     Black
 }
 
-#[wasm_bindgen]
+impl Color {
+    pub fn opposite(&self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Piece {
     kind: PieceType,
     color: Color,
-    rank: i32,
-    file: i32
+    square: Square
 }
 
-#[wasm_bindgen]
 impl Piece {
-    // gets tuple of (rank, file)
-    pub fn get_position(&self) -> Coordinates {
-        Coordinates {
-            rank: self.rank,
-            file: self.file
-        }
+    pub fn get_position(&self) -> Square {
+        self.square
     }
-}
-
-#[wasm_bindgen]
-#[derive(PartialEq, Debug)]
-pub struct Coordinates {
-    pub rank: i32,
-    pub file: i32
-}
 
-#[wasm_bindgen]
-pub struct BoardSingleton {
-    board: Board
-}
-
-// mostly exists as a wrapper class so Javascript can interact with the board
-//
-// complex values (vec, arr, etc.) cannot be passed directly to javascript, 
-// so no methods in this struct can use them directly as params or return values
-#[wasm_bindgen]
-impl BoardSingleton {
-    #[wasm_bindgen(constructor)]
-    pub fn new() -> BoardSingleton {
-        let singleton = BoardSingleton {
-            board: Board::new("".to_string())
-        };
-
-        return singleton;
+    pub fn get_kind(&self) -> PieceType {
+        self.kind
     }
 
-    pub fn set_fen(&mut self, fen: &str) {
-        self.board.set_fen(fen.to_string());
+    pub fn get_color(&self) -> Color {
+        self.color
     }
 
-    pub fn get_board_string(&self) -> String {
-        let mut string = "<table><tbody>".to_string();
-        let mut char:char;
-    
-        for i in (1..9).rev() {
-            string += "<tr>";
-    
-            for j in 1..9 {
-                match self.board.find_piece_by_coords(i, j) {
-                    Some(x) => {
-                        match x.kind {
-                            PieceType::Pawn => {
-                                char = 'p';
-                            },
-                            PieceType::Rook => {
-                                char = 'r';
-                            },
-                            PieceType::Knight => {
-                                char = 'n';
-                            },
-                            PieceType::Bishop => {
-                                char = 'b';
-                            }
-                            PieceType::Queen => {
-                                char = 'q';
-                            },
-                            PieceType::King => {
-                                char = 'k';
-                            },
-                        }
-    
-                        if x.color == Color::White {
-                            char = char.to_ascii_uppercase();
-                        }
-                    },
-                    None => {
-                        char = '+';
-                    }
-                }
-    
-                string += "<td>";
-                string.push(char);
-                string += "</td>";
-            }
-    
-            string += "</tr>";
-        }
-    
-        string += "</tbody></table>";
-    
-        return string;
+    // FEN character for the piece, uppercase for white
+    fn to_char(&self) -> char {
+        piece_char(self.kind, self.color)
     }
 }
 
-#[wasm_bindgen]
+// FEN character for a piece of the given kind and color
+fn piece_char(kind: PieceType, color: Color) -> char {
+    let ch = match kind {
+        PieceType::Pawn => 'p',
+        PieceType::Knight => 'n',
+        PieceType::Bishop => 'b',
+        PieceType::Rook => 'r',
+        PieceType::Queen => 'q',
+        PieceType::King => 'k'
+    };
+
+    if color == Color::White { ch.to_ascii_uppercase() } else { ch }
+}
+
 #[derive(Clone)]
 pub struct Board {
     fen: String,
@@ -140,7 +173,11 @@ pub struct Board {
     white_pieces: [Vec<Piece>; 6],
 
     // Black pieces
-    black_pieces: [Vec<Piece>; 6]
+    black_pieces: [Vec<Piece>; 6],
+
+    // pieces of each color that have been captured, in the order they were taken
+    white_captured: Vec<PieceType>,
+    black_captured: Vec<PieceType>
 }
 
 impl Board {
@@ -163,6 +200,11 @@ impl Board {
         self.black_pieces.clone()
     }
 
+    // every piece of the given color on the board, pawns first and the king last
+    pub fn pieces(&self, color: Color) -> impl Iterator<Item = &Piece> + '_ {
+        self.pieces_of(color).iter().flatten()
+    }
+
     pub fn new(fen_in: String) -> Board {
         let mut fen = fen_in;
 
@@ -173,17 +215,18 @@ impl Board {
         // generate pieces
         let mut ret = Board {
             fen: String::new(),
-            white_pieces: Board::init_white_pieces(),
-            black_pieces: Board::init_black_pieces()
+            white_pieces: Board::empty_pieces(),
+            black_pieces: Board::empty_pieces(),
+            white_captured: Vec::new(),
+            black_captured: Vec::new()
         };
 
         ret.set_fen(fen);
-        ret.set_piece_coords();
 
-        return ret;
+        ret
     }
 
-    // Fills 2d array of white pieces
+    // 2d array of pieces with room for every piece of one color
     //
     // 0: pawns
     // 1: knights
@@ -191,271 +234,32 @@ impl Board {
     // 3: rooks
     // 4: queens
     // 5: kings
-    fn init_white_pieces() -> [Vec<Piece>; 6] {
-        let mut white_pieces = [
+    fn empty_pieces() -> [Vec<Piece>; 6] {
+        [
             Vec::with_capacity(8), // max number of pawns is 8
             Vec::with_capacity(10), // max number of knights, bishops, and rooks is 10 (2 on board + 8 promotions)
             Vec::with_capacity(10),
             Vec::with_capacity(10),
             Vec::with_capacity(9), // max number of queens on the board (1 on board + 8 promotions)
             Vec::with_capacity(1), // max number of kings on the board
-        ]; 
-
-        white_pieces[0].push(Piece {
-            kind: PieceType::Pawn,
-            color: Color::White,
-            rank: 0,
-            file: 0
-        });
-        white_pieces[0].push(Piece {
-            kind: PieceType::Pawn,
-            color: Color::White,
-            rank: 0,
-            file: 0
-        });
-        white_pieces[0].push(Piece {
-            kind: PieceType::Pawn,
-            color: Color::White,
-            rank: 0,
-            file: 0
-        });
-        white_pieces[0].push(Piece {
-            kind: PieceType::Pawn,
-            color: Color::White,
-            rank: 0,
-            file: 0
-        });
-        white_pieces[0].push(Piece {
-            kind: PieceType::Pawn,
-            color: Color::White,
-            rank: 0,
-            file: 0
-        });
-        white_pieces[0].push(Piece {
-            kind: PieceType::Pawn,
-            color: Color::White,
-            rank: 0,
-            file: 0
-        });
-        white_pieces[0].push(Piece {
-            kind: PieceType::Pawn,
-            color: Color::White,
-            rank: 0,
-            file: 0
-        });
-        white_pieces[0].push(Piece {
-            kind: PieceType::Pawn,
-            color: Color::White,
-            rank: 0,
-            file: 0
-        });
-        white_pieces[3].push(Piece {
-            kind: PieceType::Rook,
-            color: Color::White,
-            rank: 0,
-            file: 0
-        });
-        white_pieces[3].push(Piece {
-            kind: PieceType::Rook,
-            color: Color::White,
-            rank: 0,
-            file: 0
-        });
-        white_pieces[1].push(Piece {
-            kind: PieceType::Knight,
-            color: Color::White,
-            rank: 0,
-            file: 0
-        });
-        white_pieces[1].push(Piece {
-            kind: PieceType::Knight,
-            color: Color::White,
-            rank: 0,
-            file: 0
-        });
-        white_pieces[2].push(Piece {
-            kind: PieceType::Bishop,
-            color: Color::White,
-            rank: 0,
-            file: 0
-        });
-        white_pieces[2].push(Piece {
-            kind: PieceType::Bishop,
-            color: Color::White,
-            rank: 0,
-            file: 0
-        });
-        white_pieces[4].push(Piece {
-            kind: PieceType::Queen,
-            color: Color::White,
-            rank: 0,
-            file: 0
-        });
-        white_pieces[5].push(Piece {
-            kind: PieceType::King,
-            color: Color::White,
-            rank: 0,
-            file: 0
-        });
-
-        white_pieces
+        ]
     }
 
-    // Fills 2d array of black pieces
+    // Iterates through the FEN and places a piece for each one it lists
     //
-    // 0: pawns
-    // 1: knights
-    // 2: bishops
-    // 3: rooks
-    // 4: queens
-    // 5: kings
-    fn init_black_pieces() -> [Vec<Piece>; 6] {
-        let mut black_pieces = [
-            Vec::with_capacity(8), // max number of pawns is 8
-            Vec::with_capacity(10), // max number of knights, bishops, and rooks is 10 (2 on board + 8 promotions)
-            Vec::with_capacity(10),
-            Vec::with_capacity(10),
-            Vec::with_capacity(9), // max number of queens on the board (1 on board + 8 promotions)
-            Vec::with_capacity(1), // max number of kings on the board
-        ]; 
-
-        black_pieces[0].push(Piece {
-            kind: PieceType::Pawn,
-            color: Color::Black,
-            rank: 0,
-            file: 0
-        });
-        black_pieces[0].push(Piece {
-            kind: PieceType::Pawn,
-            color: Color::Black,
-            rank: 0,
-            file: 0
-        });
-        black_pieces[0].push(Piece {
-            kind: PieceType::Pawn,
-            color: Color::Black,
-            rank: 0,
-            file: 0
-        });
-        black_pieces[0].push(Piece {
-            kind: PieceType::Pawn,
-            color: Color::Black,
-            rank: 0,
-            file: 0
-        });
-        black_pieces[0].push(Piece {
-            kind: PieceType::Pawn,
-            color: Color::Black,
-            rank: 0,
-            file: 0
-        });
-        black_pieces[0].push(Piece {
-            kind: PieceType::Pawn,
-            color: Color::Black,
-            rank: 0,
-            file: 0
-        });
-        black_pieces[0].push(Piece {
-            kind: PieceType::Pawn,
-            color: Color::Black,
-            rank: 0,
-            file: 0
-        });
-        black_pieces[0].push(Piece {
-            kind: PieceType::Pawn,
-            color: Color::Black,
-            rank: 0,
-            file: 0
-        });
-        black_pieces[3].push(Piece {
-            kind: PieceType::Rook,
-            color: Color::Black,
-            rank: 0,
-            file: 0
-        });
-        black_pieces[3].push(Piece {
-            kind: PieceType::Rook,
-            color: Color::Black,
-            rank: 0,
-            file: 0
-        });
-        black_pieces[1].push(Piece {
-            kind: PieceType::Knight,
-            color: Color::Black,
-            rank: 0,
-            file: 0
-        });
-        black_pieces[1].push(Piece {
-            kind: PieceType::Knight,
-            color: Color::Black,
-            rank: 0,
-            file: 0
-        });
-        black_pieces[2].push(Piece {
-            kind: PieceType::Bishop,
-            color: Color::Black,
-            rank: 0,
-            file: 0
-        });
-        black_pieces[2].push(Piece {
-            kind: PieceType::Bishop,
-            color: Color::Black,
-            rank: 0,
-            file: 0
-        });
-        black_pieces[4].push(Piece {
-            kind: PieceType::Queen,
-            color: Color::Black,
-            rank: 0,
-            file: 0
-        });
-        black_pieces[5].push(Piece {
-            kind: PieceType::King,
-            color: Color::Black,
-            rank: 0,
-            file: 0
-        });
-
-        black_pieces
-    }
-
-    // Iterates through the FEN and properly sets the coordinates of each piece
+    // pieces are added in FEN order, from a8 to h1, and anything missing from the starting set
+    // is counted as captured
     fn set_piece_coords(&mut self) {
         let mut rank: i32 = 8;
         let mut file: i32 = 0;
-        
-        let mut white:bool;
-
-        // keeps track of the number of each piece in the array of pieces, and subtracts for this number for each one that has been seen
-        // [pawns, knights, bishops, rooks, queen]
-        let mut white_piece_counts = [0, 0, 0, 0, 0];
-        let mut black_piece_counts = [0, 0, 0, 0, 0];
-
-        // populate count arrays 
-        self.count_pieces(&self.white_pieces, &mut white_piece_counts);
-        self.count_pieces(&self.black_pieces, &mut black_piece_counts);
-
-        // indexes in 2d array of pieces
-        // 0: pawns
-        // 1: knights
-        // 2: bishops
-        // 3: rooks
-        // 4: queens
-        // 5: kings
-
-        for ch in self.fen.chars() {
-            white = ch.is_ascii_uppercase();
-            
-            let pieces;
-            let counts;
-            
-            if white {
-                pieces = &mut (self.white_pieces);
-                counts = &mut white_piece_counts;
-            } else {
-                pieces = &mut (self.black_pieces);
-                counts = &mut black_piece_counts;
-            };
+
+        let fen = self.fen.clone();
+
+        self.white_pieces = Board::empty_pieces();
+        self.black_pieces = Board::empty_pieces();
+
+        for ch in fen.chars() {
+            let color = if ch.is_ascii_uppercase() { Color::White } else { Color::Black };
 
             if ch.is_ascii_digit() {
                 // if char is a number, it represents empty spaces
@@ -467,608 +271,426 @@ impl Board {
                     Some(x) => x as i32, // x is now an integer value
                 };
             } else {
-                // index positions of pieces in arr of pieces
-        
                 file += 1; // all match arms except '/' do this
 
-                match ch.to_ascii_lowercase() {
+                let kind = match ch.to_ascii_lowercase() {
                     '/' => {
                         rank -= 1;
                         file = 0;
+                        continue;
                     },
-                    'p' => {
-                        Board::update_piece_coordinates(pieces, counts, 0, white, rank, file);
-                    },
-                    'n' => {
-                        Board::update_piece_coordinates(pieces, counts, 1, white, rank, file);
-                    },
-                    'b' => {
-                        Board::update_piece_coordinates(pieces, counts, 2, white, rank, file);
-                    },
-                    'r' => {
-                        Board::update_piece_coordinates(pieces, counts, 3, white, rank, file);
-                    },
-                    'q' => {
-                        Board::update_piece_coordinates(pieces, counts, 4, white, rank, file);
-                    },
-                    'k' => {
-                        pieces[5][0].rank = rank;
-                        pieces[5][0].file = file;
-                    }
+                    'p' => PieceType::Pawn,
+                    'n' => PieceType::Knight,
+                    'b' => PieceType::Bishop,
+                    'r' => PieceType::Rook,
+                    'q' => PieceType::Queen,
+                    'k' => PieceType::King,
                     ' ' => break, // space indicates the end of the position section
                     _ => continue
+                };
+
+                // pieces that would land off the board in a malformed FEN are skipped
+                if let (Some(f), Some(r)) = (File::new((file - 1) as u8), Rank::new((rank - 1) as u8)) {
+                    self.pieces_of_mut(color)[kind as usize].push(Piece {
+                        kind,
+                        color,
+                        square: Square::new(f, r)
+                    });
                 }
             }
         }
 
-        // index positions of pieces in arr of pieces
-        // 0: pawns
-        // 1: knights
-        // 2: bishops
-        // 3: rooks
-        // 4: queens
-        // 5: kings
-
-        // update missing pieces to have rank/file of 0 (lost)
-        
-        for i in 0..5 {
-            while black_piece_counts[i] > 0 {
-                let index = self.black_pieces[i].len() - black_piece_counts[i];
-                self.black_pieces[i][index].rank = 0;
-                self.black_pieces[i][index].file = 0;
+        self.white_captured = self.missing_material(Color::White);
+        self.black_captured = self.missing_material(Color::Black);
+    }
 
-                black_piece_counts[i] -= 1;
-            }
+    // bitboard of all pieces of the given color and kind that are still on the board
+    fn kind_bb(&self, color: Color, kind: PieceType) -> u64 {
+        self.pieces_of(color)[kind as usize]
+            .iter()
+            .fold(0, |bb, p| bb | bitboard::bit(p.square))
+    }
 
-            while white_piece_counts[i] > 0 {
-                let index = self.white_pieces[i].len() - white_piece_counts[i];
-                self.white_pieces[i][index].rank = 0;
-                self.white_pieces[i][index].file = 0;
+    // bitboard of all pieces of the given color that are still on the board
+    fn color_bb(&self, color: Color) -> u64 {
+        (0..6).fold(0, |bb, i| bb | self.kind_bb(color, PIECE_KINDS[i]))
+    }
 
-                white_piece_counts[i] -= 1;
-            }
+    fn pieces_of(&self, color: Color) -> &[Vec<Piece>; 6] {
+        match color {
+            Color::White => &self.white_pieces,
+            Color::Black => &self.black_pieces
         }
     }
 
-    // updates piece coordinates for set_piece_coords method
-    fn update_piece_coordinates(pieces:&mut [Vec<Piece>; 6], counts:&mut [usize; 5], index:usize, white:bool, rank:i32, file:i32) {
-        if counts[index] == 0 {
-            let kind = pieces[index][0].kind.clone();
+    fn pieces_of_mut(&mut self, color: Color) -> &mut [Vec<Piece>; 6] {
+        match color {
+            Color::White => &mut self.white_pieces,
+            Color::Black => &mut self.black_pieces
+        }
+    }
 
-            let mut piece = Piece {
-                kind,
-                color: Color::Black,
-                rank: 0,
-                file: 0
-            };
+    // index (kind, position in list) of the piece of the given color on the square
+    fn piece_index(&self, color: Color, square: Square) -> Option<(usize, usize)> {
+        let pieces = self.pieces_of(color);
 
-            if white { piece.color = Color::White }
+        for (i, list) in pieces.iter().enumerate() {
+            if let Some(j) = list.iter().position(|p| p.square == square) {
+                return Some((i, j));
+            }
+        }
 
+        None
+    }
 
-            pieces[index].push(piece);
-            counts[index] += 1;
+    // bitboard of the pieces of color `by` attacking sq
+    //
+    // `occupied` is the occupancy to use for sliding pieces, and only pieces on occupied squares
+    // are counted, so pieces can be taken off the board (and x-rays uncovered) by clearing their bit
+    fn attackers_bb(&self, sq: Square, by: Color, occupied: u64) -> u64 {
+        let pawns = self.kind_bb(by, PieceType::Pawn);
+        let knights = self.kind_bb(by, PieceType::Knight);
+        let bishops = self.kind_bb(by, PieceType::Bishop);
+        let rooks = self.kind_bb(by, PieceType::Rook);
+        let queens = self.kind_bb(by, PieceType::Queen);
+        let kings = self.kind_bb(by, PieceType::King);
+
+        // a pawn of color `by` attacks sq exactly when a pawn of the other color on sq would attack it
+        let attackers = (bitboard::pawn_attacks(by.opposite(), sq) & pawns)
+            | (bitboard::knight_attacks(sq) & knights)
+            | (bitboard::king_attacks(sq) & kings)
+            | (bitboard::bishop_attacks(sq, occupied) & (bishops | queens))
+            | (bitboard::rook_attacks(sq, occupied) & (rooks | queens));
+
+        attackers & occupied
+    }
 
-            println!("Added new piece of type: {:?}", kind);
-        }
+    // true if any piece of color `by` attacks sq
+    //
+    // `captured` masks out pieces that are considered removed, which together with `occupied`
+    // lets us test positions after a move without making it
+    fn is_attacked(&self, sq: Square, by: Color, occupied: u64, captured: u64) -> bool {
+        self.attackers_bb(sq, by, occupied) & !captured != 0
+    }
 
-        let index2 = pieces[index].len() - counts[index];
-        counts[index] -= 1;
-        pieces[index][index2].rank = rank;
-        pieces[index][index2].file = file;
+    // square of the given color's king, None in positions without one
+    pub fn king_square(&self, color: Color) -> Option<Square> {
+        self.pieces_of(color)[PieceType::King as usize].first().map(|p| p.square)
     }
 
-    // counts array indexes: [pawns, rooks, knights, bishops, queen]
-    fn count_pieces(&self, pieces:&[Vec<Piece>; 6], counts:&mut [usize; 5]) {
-        for i in 0..5 {
-            counts[i] = pieces[i].len();
-        }
+    // returns true if the side to move is in check
+    pub fn in_check(&self) -> bool {
+        let color = self.get_side_to_move();
+        let king = match self.king_square(color) {
+            Some(sq) => sq,
+            None => return false
+        };
+
+        let occupied = self.color_bb(Color::White) | self.color_bb(Color::Black);
+        self.is_attacked(king, color.opposite(), occupied, 0)
     }
 
-    // given a piece, return a vec of all valid squares for it
-    fn get_valid_moves(&self, piece:&Piece) -> Vec<(i32, i32)> {
-        let mut coords:Vec<(i32, i32)> = Vec::new();
+    // given a piece, return all of its legal moves, with one move per promotion piece
+    fn get_valid_moves(&self, piece:&Piece) -> Vec<Move> {
+        let mut moves:Vec<Move> = Vec::new();
 
-        // if piece is not on the board, it has no valid moves
-        if piece.rank == 0 || piece.file == 0 { return coords; }
+        let from = piece.square;
 
-        // if the king is in check, nobody else can move unless they're blocking check or attacker is eliminated
-        // also must eliminate exposing king to check
+        let own = self.color_bb(piece.color);
+        let enemy = self.color_bb(piece.color.opposite());
+        let occupied = own | enemy;
+        let en_passant = self.get_en_passant().map_or(0, bitboard::bit);
+
+        // team indicates pawn direction; white moves up the board, therefore direction is positive
+        let team = if piece.color == Color::White { 1 } else { -1 };
 
-        match piece.kind {
+        let targets = match piece.kind {
             PieceType::Pawn => {
-                // team indicates pawn direction; pawns can only move forward and therefore movement is team dependent
-                // white moves up the board, therefore direction is positive
-                let team;
-                if piece.color == Color::White {
-                    team = 1;
-                } else {
-                    team = -1;
-                }
+                let start_rank = if piece.color == Color::White { Rank::SECOND } else { Rank::SEVENTH };
+                let mut targets = bitboard::pawn_attacks(piece.color, from) & (enemy | en_passant);
 
-                // each var represents a possible movement of the pawn
-                let one_space = (piece.rank + team, piece.file);
-                let two_space = (piece.rank + 2 * team, piece.file);
-                let capture_square_1 = (piece.rank + team, piece.file - 1);
-                let capture_square_2 = (piece.rank + team, piece.file + 1);
-
-                // for pawn movements, forward squares must be clear, must be on second or seventh rank for double movement, and diagonal squares must be occupied by enemy pieces for a move to be valid
-                if Board::valid_square(one_space) && self.find_piece_by_coords(one_space.0, one_space.1).is_none() { coords.push(one_space); }
-                if Board::valid_square(two_space) && piece.rank == (4.5 - 2.5 * (team as f64)) as i32 && self.find_piece_by_coords(one_space.0, one_space.1).is_none() && self.find_piece_by_coords(two_space.0, two_space.1).is_none() { coords.push(two_space); }
-                if Board::valid_square(capture_square_1) && !self.find_piece_by_coords(capture_square_1.0, capture_square_1.1).is_none() && self.find_piece_by_coords(capture_square_1.0, capture_square_1.1).unwrap().color != piece.color { coords.push(capture_square_1); }
-                if Board::valid_square(capture_square_2) && !self.find_piece_by_coords(capture_square_2.0, capture_square_2.1).is_none() && self.find_piece_by_coords(capture_square_2.0, capture_square_2.1).unwrap().color != piece.color { coords.push(capture_square_2); }
-            },
-            PieceType::Knight => {
-                for x in -2..3 {
-                    for y in -2..3 {
-                        // when x is +/- 1       and y is +/- 2,        OR   x is +/- 2          and y is +/- 1,             and the target square is valid
-                        if (((x == 1 || x == -1) && (y == 2 || y == -2)) || ((x == 2 || x == -2) && (y == 1 || y == -1))) && Board::valid_square((piece.rank + x, piece.file + y)) {
-                            let same_team = match self.find_piece_by_coords(piece.rank + x, piece.file + y) {
-                                Some(x) => x.color == piece.color, // when the target square is not occupied by a same-color piece
-                                None => false,
-                            };
-                            if !same_team {coords.push((piece.rank + x, piece.file + y))} // add to coords
-                        }
-                    }
-                }
-            },
-            PieceType::Bishop => {
-                let mut quad_one = true;
-                let mut quad_two = true;
-                let mut quad_three = true;
-                let mut quad_four = true;
-                for x in 1..8 {
-                    if quad_one {
-                        if !Board::valid_square((piece.rank + x, piece.file + x)) {
-                            quad_one = false;
-                        }
-                        else {
-                            let same_team = match self.find_piece_by_coords(piece.rank + x, piece.file + x) {
-                                Some(y) => {
-                                    if y.color != piece.color {
-                                        coords.push((piece.rank + x, piece.file + x));
-                                    }
-                                    true
-                                },
-                                None => false,
-                            };
-                            if same_team {quad_one = false;}
-                            else {coords.push((piece.rank + x, piece.file + x))}
-                        }
-                    }
-                    if quad_two {
-                        if !Board::valid_square((piece.rank - x, piece.file + x)) {
-                            quad_two = false;
-                        }
-                        else {
-                            let same_team = match self.find_piece_by_coords(piece.rank - x, piece.file + x) {
-                                Some(y) => {
-                                    if y.color != piece.color {
-                                        coords.push((piece.rank - x, piece.file + x));
-                                    }
-                                    y.color == piece.color
-                                },
-                                None => false,
-                            };
-                            if same_team {quad_two = false;}
-                            else {coords.push((piece.rank - x, piece.file + x))}
-                        }
-                    }
-                    if quad_three {
-                        if !Board::valid_square((piece.rank - x, piece.file - x)) {
-                            quad_three = false;
-                        }
-                        else {
-                            let same_team = match self.find_piece_by_coords(piece.rank - x, piece.file - x) {
-                                Some(y) => {
-                                    if y.color != piece.color {
-                                        coords.push((piece.rank - x, piece.file - x));
-                                    }
-                                    y.color == piece.color
-                                },
-                                None => false,
-                            };
-                            if same_team {quad_three = false;}
-                            else {coords.push((piece.rank - x, piece.file - x))}
-                        }
-                    }
-                    if quad_four {
-                        if !Board::valid_square((piece.rank + x, piece.file - x)) {
-                            quad_four = false;
-                        }
-                        else {
-                            let same_team = match self.find_piece_by_coords(piece.rank + x, piece.file - x) {
-                                Some(y) => {
-                                    if y.color != piece.color {
-                                        coords.push((piece.rank + x, piece.file - x));
-                                    }
-                                    y.color == piece.color
-                                },
-                                None => false,
-                            };
-                            if same_team {quad_four = false;}
-                            else {coords.push((piece.rank + x, piece.file - x))}
-                        }
-                    }
-                }
-            },
-            PieceType::Rook => {
-                let mut r = true;
-                let mut u = true;
-                let mut l = true;
-                let mut d = true;
-                for x in 1..8 {
-                    if r {
-                        if !Board::valid_square((piece.rank, piece.file + x)) {
-                            r = false;
-                        }
-                        else {
-                            let same_team = match self.find_piece_by_coords(piece.rank, piece.file + x) {
-                                Some(y) => {
-                                    if y.color == piece.color {
-                                        coords.push((piece.rank, piece.file + x));
-                                    }
-                                    y.color == piece.color
-                                },
-                                None => false,
-                            };
-                            if same_team {r = false;}
-                            else {coords.push((piece.rank, piece.file + x))}
-                        }
-                    }
-                    if u {
-                        if !Board::valid_square((piece.rank, piece.file + x)) {
-                            u = false;
-                        }
-                        else {
-                            let same_team = match self.find_piece_by_coords(piece.rank + x, piece.file) {
-                                Some(y) => {
-                                    if y.color == piece.color {
-                                        coords.push((piece.rank + x, piece.file));
-                                    }
-                                    y.color == piece.color
-                                },
-                                None => false,
-                            };
-                            if same_team {u = false;}
-                            else {coords.push((piece.rank + x, piece.file))}
-                        }
-                    }
-                    if l {
-                        if !Board::valid_square((piece.rank + x, piece.file)) {
-                            l = false;
-                        }
-                        else {
-                            let same_team = match self.find_piece_by_coords(piece.rank, piece.file - x) {
-                                Some(y) => {
-                                    if y.color == piece.color {
-                                        coords.push((piece.rank, piece.file - x));
-                                    }
-                                    y.color == piece.color
-                                },
-                                None => false,
-                            };
-                            if same_team {l = false;}
-                            else {coords.push((piece.rank, piece.file - x))}
-                        }
-                    }
-                    if d {
-                        if !Board::valid_square((piece.rank - x, piece.file)) {
-                            d = false;
-                        }
-                        else {
-                            let same_team = match self.find_piece_by_coords(piece.rank - x, piece.file) {
-                                Some(y) => {
-                                    if y.color == piece.color {
-                                        coords.push((piece.rank - x, piece.file));
-                                    }
-                                    y.color == piece.color
-                                },
-                                None => false,
-                            };
-                            if same_team {d = false;}
-                            else {coords.push((piece.rank - x, piece.file))}
+                // forward squares must be clear, and the pawn must be on its starting rank to move two spaces
+                if let Some(one_space) = from.offset(0, team) {
+                    if occupied & bitboard::bit(one_space) == 0 {
+                        targets |= bitboard::bit(one_space);
+
+                        match one_space.offset(0, team) {
+                            Some(two_space) if from.rank() == start_rank && occupied & bitboard::bit(two_space) == 0 => {
+                                targets |= bitboard::bit(two_space);
+                            },
+                            _ => {}
                         }
                     }
                 }
+
+                targets
             },
-            PieceType::Queen => {
-                let mut r = true;
-                let mut u = true;
-                let mut l = true;
-                let mut d = true;
-                let mut quad_one = true;
-                let mut quad_two = true;
-                let mut quad_three = true;
-                let mut quad_four = true;
-                for x in 1..8 {
-                    if r {
-                        if !Board::valid_square((piece.rank, piece.file + x)) {
-                            r = false;
-                            quad_one = false;
-                            quad_four = false;
-                        }
-                        else {
-                            let same_team = match self.find_piece_by_coords(piece.rank, piece.file + x) {
-                                Some(y) => {
-                                    if y.color == piece.color {
-                                        coords.push((piece.rank, piece.file + x));
-                                    }
-                                    y.color == piece.color
-                                },
-                                None => false,
-                            };
-                            if same_team {r = false;}
-                            else {coords.push((piece.rank, piece.file + x))}
-                        }
-                    }
-                    if u {
-                        if !Board::valid_square((piece.rank, piece.file + x)) {
-                            u = false;
-                            quad_one = false;
-                            quad_two = false;
-                        }
-                        else {
-                            let same_team = match self.find_piece_by_coords(piece.rank + x, piece.file) {
-                                Some(y) => {
-                                    if y.color == piece.color {
-                                        coords.push((piece.rank + x, piece.file));
-                                    }
-                                    y.color == piece.color
-                                },
-                                None => false,
-                            };
-                            if same_team {u = false;}
-                            else {coords.push((piece.rank + x, piece.file))}
-                        }
-                    }
-                    if l {
-                        if !Board::valid_square((piece.rank + x, piece.file)) {
-                            l = false;
-                            quad_two = false;
-                            quad_three = false;
-                        }
-                        else {
-                            let same_team = match self.find_piece_by_coords(piece.rank, piece.file - x) {
-                                Some(y) => {
-                                    if y.color == piece.color {
-                                        coords.push((piece.rank, piece.file - x));
-                                    }
-                                    y.color == piece.color
-                                },
-                                None => false,
-                            };
-                            if same_team {l = false;}
-                            else {coords.push((piece.rank, piece.file - x))}
-                        }
-                    }
-                    if d {
-                        if !Board::valid_square((piece.rank - x, piece.file)) {
-                            d = false;
-                            quad_three = false;
-                            quad_four = false;
-                        }
-                        else {
-                            let same_team = match self.find_piece_by_coords(piece.rank - x, piece.file) {
-                                Some(y) => {
-                                    if y.color == piece.color {
-                                        coords.push((piece.rank - x, piece.file));
-                                    }
-                                    y.color == piece.color
-                                },
-                                None => false,
-                            };
-                            if same_team {d = false;}
-                            else {coords.push((piece.rank - x, piece.file))}
-                        }
-                    }
-                    if quad_one {
-                        let same_team = match self.find_piece_by_coords(piece.rank + x, piece.file + x) {
-                            Some(y) => {
-                                if y.color != piece.color {
-                                    coords.push((piece.rank + x, piece.file + x));
-                                }
-                                true
-                            },
-                            None => false,
-                        };
-                        if same_team {quad_one = false;}
-                        else {coords.push((piece.rank + x, piece.file + x))}
-                    }
-                    if quad_two {
-                        let same_team = match self.find_piece_by_coords(piece.rank - x, piece.file + x) {
-                            Some(y) => {
-                                if y.color != piece.color {
-                                    coords.push((piece.rank - x, piece.file + x));
-                                }
-                                y.color == piece.color
-                            },
-                            None => false,
-                        };
-                        if same_team {quad_two = false;}
-                        else {coords.push((piece.rank - x, piece.file + x))}
-                    }
-                    if quad_three {
-                        let same_team = match self.find_piece_by_coords(piece.rank - x, piece.file - x) {
-                            Some(y) => {
-                                if y.color != piece.color {
-                                    coords.push((piece.rank - x, piece.file - x));
-                                }
-                                y.color == piece.color
-                            },
-                            None => false,
-                        };
-                        if same_team {quad_three = false;}
-                        else {coords.push((piece.rank - x, piece.file - x))}
-                    }
-                    if quad_four {
-                        let same_team = match self.find_piece_by_coords(piece.rank + x, piece.file - x) {
-                            Some(y) => {
-                                if y.color != piece.color {
-                                    coords.push((piece.rank + x, piece.file - x));
-                                }
-                                y.color == piece.color
-                            },
-                            None => false,
-                        };
-                        if same_team {quad_four = false;}
-                        else {coords.push((piece.rank + x, piece.file - x))}
-                    }
-                    
-                }
+            PieceType::Knight | PieceType::Bishop | PieceType::Rook | PieceType::Queen => {
+                bitboard::attacks(piece.kind, piece.color, from, occupied) & !own
             },
             PieceType::King => {
                 let castles = self.get_castle_ability(piece.color);
-                if castles.0 {
-                    if self.find_piece_by_coords(piece.rank, piece.file + 1).is_none() && self.find_piece_by_coords(piece.rank, piece.file + 2).is_none() {
-                        coords.push((piece.rank, piece.file + 2))
+                let enemy_color = piece.color.opposite();
+                let mut targets = bitboard::king_attacks(from) & !own;
+
+                // castling is only possible from the king's own square when the king is not in check, the
+                // squares between king and rook are empty, and the king does not pass through or land on an
+                // attacked square
+                let back_rank = if piece.color == Color::White { Rank::FIRST } else { Rank::EIGHTH };
+                let home = from == Square::new(File::E, back_rank);
+
+                if home && (castles.0 || castles.1) && !self.is_attacked(from, enemy_color, occupied, 0) {
+                    let rooks = self.kind_bb(piece.color, PieceType::Rook);
+                    let on_rank = |file: File| Square::new(file, from.rank());
+                    let empty = |file: File| occupied & bitboard::bit(on_rank(file)) == 0;
+                    let safe = |file: File| !self.is_attacked(on_rank(file), enemy_color, occupied, 0);
+
+                    if castles.0 && rooks & bitboard::bit(on_rank(File::H)) != 0
+                        && empty(File::F) && empty(File::G) && safe(File::F) && safe(File::G) {
+                        targets |= bitboard::bit(on_rank(File::G));
                     }
-                }
-                if castles.1 {
-                    if self.find_piece_by_coords(piece.rank, piece.file - 1).is_none() && self.find_piece_by_coords(piece.rank, piece.file - 2).is_none() {
-                        coords.push((piece.rank, piece.file - 2))
+                    if castles.1 && rooks & bitboard::bit(on_rank(File::A)) != 0
+                        && empty(File::D) && empty(File::C) && empty(File::B) && safe(File::D) && safe(File::C) {
+                        targets |= bitboard::bit(on_rank(File::C));
                     }
                 }
-                for i in -1..2 {
-                    for j in -1..2 {
-                        if i != 0 || j != 0 {
-                            let same_team = match self.find_piece_by_coords(piece.rank + i, piece.file + j) {
-                                Some(x) => {
-                                    if x.color == piece.color {
-                                        false
-                                    }
-                                    else {
-                                        true
-                                    }
-                                },
-                                None => true,
-                            };
-                            if same_team {coords.push((piece.rank + i, piece.file + j))}
-                        }
-                    }
+
+                targets
+            }
+        };
+
+        // if the king is in check, nobody else can move unless they're blocking check or attacker is eliminated
+        // also must eliminate exposing king to check
+        let king = bitboard::first(self.kind_bb(piece.color, PieceType::King));
+
+        for to in bitboard::squares(targets) {
+            let mut captured = bitboard::bit(to) & enemy;
+            let mut special = Special::None;
+
+            if piece.kind == PieceType::Pawn && bitboard::bit(to) & en_passant != 0 {
+                // en passant removes the pawn beside the moving pawn rather than on the target square
+                captured = bitboard::bit(Square::new(to.file(), from.rank()));
+                special = Special::EnPassant;
+            } else if piece.kind == PieceType::Pawn && (to.rank().index() as i32 - from.rank().index() as i32).abs() == 2 {
+                special = Special::DoublePush;
+            } else if piece.kind == PieceType::King && to.file().offset(-2) == Some(from.file()) {
+                special = Special::KingCastle;
+            } else if piece.kind == PieceType::King && to.file().offset(2) == Some(from.file()) {
+                special = Special::QueenCastle;
+            }
+
+            let after = (occupied & !bitboard::bit(from) & !captured) | bitboard::bit(to);
+            let king_square = if piece.kind == PieceType::King { Some(to) } else { king };
+
+            if let Some(king_square) = king_square {
+                if self.is_attacked(king_square, piece.color.opposite(), after, captured) { continue; }
+            }
+
+            if piece.kind == PieceType::Pawn && (to.rank() == Rank::FIRST || to.rank() == Rank::EIGHTH) {
+                for kind in [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight].iter() {
+                    moves.push(Move::generated(from, to, Some(*kind), captured != 0, special));
                 }
-            },
+            } else {
+                moves.push(Move::generated(from, to, None, captured != 0, special));
+            }
         }
 
-        return coords;
+        moves
+    }
+
+    // returns every legal move for the side to move, with one move per promotion piece
+    pub fn get_legal_moves(&self) -> Vec<Move> {
+        let color = self.get_side_to_move();
+
+        self.pieces_of(color)
+            .iter()
+            .flatten()
+            .flat_map(|piece| self.get_valid_moves(piece))
+            .collect()
     }
 
-    fn valid_square(coord:(i32,i32)) -> bool {
-        if coord.0 > 8 || coord.0 < 1 { return false }
-        if coord.1 > 8 || coord.1 < 1 { return false }
+    // returns true if the move captures a piece, including en passant captures
+    //
+    // works from the position rather than the move's flags, so it also works for moves built with Move::new
+    pub fn is_capture(&self, mv: &Move) -> bool {
+        let color = self.get_side_to_move();
+
+        if self.piece_index(color.opposite(), mv.to()).is_some() { return true; }
 
-        return true;
+        match self.find_piece(mv.from()) {
+            Some(p) => p.kind == PieceType::Pawn && mv.from().file() != mv.to().file(),
+            None => false
+        }
     }
 
-    // // takes in rank/file coordinates, and returns the optional tuple (white:boolean, index:i32)
-    pub fn find_piece_by_coords(&self, rank:i32, file:i32) -> Option<&Piece> {
+    // returns the piece on the square, if there is one
+    pub fn find_piece(&self, square:Square) -> Option<&Piece> {
         for i in 0..6 {
             for p in self.white_pieces[i].iter() {
-                if p.rank == rank && p.file == file { return Some(p); }
+                if p.square == square { return Some(p); }
             }
     
             for p in self.black_pieces[i].iter() {
-                if p.rank == rank && p.file == file { return Some(p); }
+                if p.square == square { return Some(p); }
             }
         }
 
-        return None;
+        None
     }
 
-    // moves a given piece to the specified rank/file
+    // moves a given piece to the specified square
     // eliminates any pieces that exist there, and updates the FEN
+    // pawns reaching the last rank are promoted to a queen
     // returns true if move is successfully made
-    pub fn make_move(&mut self, piece:&mut Piece, rank:i32, file:i32) -> bool {
-        // check that given rank/file are in the list of valid moves
-        // if not, return false
-        let moves = self.get_valid_moves(piece);
-        if !moves.iter().any(|&i| i == (rank, file)) { return false; }
-
-        // separate the six whitespace-separated fields in the FEN string
-        let mut fields = self.fen.split_whitespace().collect::<Vec<&str>>();
-        
+    pub fn make_move(&mut self, piece:&mut Piece, to:Square) -> bool {
         // check if the correct side is trying to move
         // if not, return false
-        if !(self.get_side_to_move() == piece.color) {
-            return false;
-        }
-        // single out the first field, the position section
-        let mut ranks = fields[0].split('/').collect::<Vec<&str>>();
+        if self.get_side_to_move() != piece.color { return false; }
 
-        // save these to update 
-        let old_rank = piece.rank;
-        let old_file = piece.file;
-        
-        // actually make the move here. Don't forget to increment the halfmove and full move counters (fields[4] and fields[5] respectively)
-        piece.rank = rank;
-        piece.file = file;
+        // the piece must match the one actually on its square
+        let from = piece.square;
+        if self.find_piece(from) != Some(piece) { return false; }
 
-        // replaces old rank char with 1. numbers still need to be collapsed (i.e. 1,2 needs to be collapsed to 3)
-        let new_rank = &Board::replace_nth_char(ranks[old_rank as usize], (old_file - 1) as usize, '1')[..];
-        ranks[old_rank as usize] = new_rank;
+        let promotion = if piece.kind == PieceType::Pawn && (to.rank() == Rank::FIRST || to.rank() == Rank::EIGHTH) { Some(PieceType::Queen) } else { None };
 
-        let new_position = ranks[rank as usize].chars().nth((file - 1) as usize).unwrap();
+        if !Move::new(from, to, promotion).is_ok_and(|mv| self.play_move(mv)) { return false; }
 
-        if new_position.is_ascii_digit() {
-            let num = new_position.to_digit(10).unwrap();
-            if num > 1 {
-                // needs to split the int into two sides, if the piece isn't moving to the edge of the space the int covers
-            }
-        } else {
-            // capture a piece
+        piece.square = to;
+        if let Some(kind) = promotion { piece.kind = kind; }
+
+        true
+    }
+
+    // plays a move for the side to move, returns true if the move was legal and has been made
+    //
+    // the move only needs the right squares and promotion, its flags are taken from the matching legal move
+    pub fn play_move(&mut self, mv: Move) -> bool {
+        match self.get_legal_moves().into_iter().find(|m| *m == mv) {
+            Some(legal) => {
+                self.apply_move(legal);
+                true
+            },
+            None => false
         }
+    }
 
-        // also needs to handle promotion
+    // makes a generated move without checking it, updating the pieces and regenerating the FEN
+    fn apply_move(&mut self, mv: Move) {
+        let color = self.get_side_to_move();
+        let (from, to) = (mv.from(), mv.to());
+        let (kind_index, index) = match self.piece_index(color, from) {
+            Some(x) => x,
+            None => return
+        };
+        let kind = PIECE_KINDS[kind_index];
+        let mut castling = self.fen.split_whitespace().nth(2).unwrap_or("-").to_string();
+        let fields = self.fen.split_whitespace().collect::<Vec<&str>>();
+        let mut halfmoves = fields.get(4).and_then(|x| x.parse::<i32>().ok()).unwrap_or(0) + 1;
+        let mut fullmoves = fields.get(5).and_then(|x| x.parse::<i32>().ok()).unwrap_or(1);
 
-        let new_rank_2 = &Board::replace_nth_char(ranks[rank as usize], (old_file - 1) as usize, '1')[..];
-        ranks[rank as usize] = new_rank_2;
+        // en passant captures the pawn that just moved past the target square
+        let target = if mv.is_en_passant() { Square::new(to.file(), from.rank()) } else { to };
 
-        // this converts the halfmoves to an int, adds 1, parses back to a String, and then using the shorthand &(value)[..] converts to &str
-        let mut halfmoves = &((&mut fields[4]).parse::<i32>().unwrap() + 1).to_string()[..];
-        if piece.kind != PieceType::Pawn {
-            fields[4] = &mut halfmoves;
+        // captured pieces come off the board and go to their color's captured list
+        if let Some((i, j)) = self.piece_index(color.opposite(), target) {
+            let captured = self.pieces_of_mut(color.opposite())[i].remove(j);
+            self.captured_mut(color.opposite()).push(captured.kind);
+            halfmoves = 0;
         }
 
-        // this converts the fullmoves to an int, adds 1, parses back to a String, and then using the shorthand &(value)[..] converts to &str
-        let mut fullmoves = &((&mut fields[5]).parse::<i32>().unwrap() + 1).to_string()[..];
-        if piece.color != Color::Black {
-            fields[5] = &mut fullmoves;
+        if kind == PieceType::Pawn { halfmoves = 0; }
+
+        self.pieces_of_mut(color)[kind_index][index].square = to;
+
+        // castling also moves the rook to the other side of the king
+        if mv.is_castle() {
+            let (rook_from, rook_to) = if to.file() > from.file() { (File::H, File::F) } else { (File::A, File::D) };
+
+            if let Some((i, j)) = self.piece_index(color, Square::new(rook_from, from.rank())) {
+                self.pieces_of_mut(color)[i][j].square = Square::new(rook_to, from.rank());
+            }
         }
 
-        // update "to move" value
-        if piece.color == Color::White {
-            fields[2] = "b";
-        } else {
-            fields[2] = "w";
+        // a promoted pawn leaves the board and a new piece takes its place
+        if let Some(promotion) = mv.promotion() {
+            let pieces = self.pieces_of_mut(color);
+            pieces[kind_index].remove(index);
+            pieces[promotion as usize].push(Piece {
+                kind: promotion,
+                color,
+                square: to
+            });
         }
-    
-        // rejoin ranks with '/' delimeter and assign to dereferenced position_section (fields[0])
-        let new_position = ranks.join("/");
-        fields[0] = new_position.as_str();
 
+        // moving the king loses both castling rights, moving or losing a rook loses that side's right
+        if kind == PieceType::King {
+            castling.retain(|c| c.is_ascii_uppercase() != (color == Color::White));
+        }
+        let corners = [(File::H, Rank::FIRST, 'K'), (File::A, Rank::FIRST, 'Q'), (File::H, Rank::EIGHTH, 'k'), (File::A, Rank::EIGHTH, 'q')];
+        for (file, rank, right) in corners.iter() {
+            let corner = Square::new(*file, *rank);
+            if from == corner || to == corner {
+                castling.retain(|c| c != *right);
+            }
+        }
+        if castling.is_empty() { castling.push('-'); }
+
+        // a double pawn push makes the skipped square available for en passant
+        let en_passant = match from.offset(0, if color == Color::White { 1 } else { -1 }) {
+            Some(skipped) if mv.is_double_push() => skipped.to_string(),
+            _ => "-".to_string()
+        };
+
+        if color == Color::Black { fullmoves += 1; }
 
-        // rejoin FEN fields with a whitespace delimeter
-        let fen = fields.join(" ");
-        self.set_fen(fen);
+        let side = if color == Color::White { "b" } else { "w" };
 
-        return true;
+        self.fen = format!("{} {} {} {} {} {}", self.get_placement(), side, castling, en_passant, halfmoves, fullmoves);
     }
 
-    // utility to replace the nth character in a &str
-    fn replace_nth_char(s:&str, index:usize, newchar:char) -> String {
-        s.chars().enumerate().map(|(i,c)| if i == index { newchar } else { c }).collect()
+    // builds the position section of the FEN from the pieces on the board
+    fn get_placement(&self) -> String {
+        let mut placement = String::new();
+
+        for rank in Rank::all().rev() {
+            let mut empty = 0;
+
+            for file in File::all() {
+                match self.find_piece(Square::new(file, rank)) {
+                    Some(p) => {
+                        if empty > 0 {
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        placement.push(p.to_char());
+                    },
+                    None => empty += 1
+                }
+            }
+
+            if empty > 0 { placement.push_str(&empty.to_string()); }
+            if rank != Rank::FIRST { placement.push('/'); }
+        }
+
+        placement
     }
 
-    fn get_side_to_move(&self) -> Color {
+    pub fn get_side_to_move(&self) -> Color {
         // break up FEN into six whitespace-delimited sections
         let fields = self.fen.split_whitespace().collect::<Vec<&str>>();
 
         if fields[1] == "w" {
-            return Color::White;
+            Color::White
         } else {
-            return Color::Black;
+            Color::Black
         }
     }
 
+    // returns the en passant target square from the FEN, if there is one
+    fn get_en_passant(&self) -> Option<Square> {
+        self.fen.split_whitespace().nth(3)?.parse().ok()
+    }
+
     fn get_castle_ability(&self, color:Color) -> (bool, bool) {
         let fields = self.fen.split_whitespace().collect::<Vec<&str>>();
         let mut king = 'K';
@@ -1081,7 +703,14 @@ impl Board {
 
         let castling = fields[2];
         
-        return (castling.contains(king), castling.contains(queen));
+        (castling.contains(king), castling.contains(queen))
+    }
+}
+
+// the starting position
+impl Default for Board {
+    fn default() -> Self {
+        Board::new(String::new())
     }
 }
 
@@ -0,0 +1,247 @@
+// Mate solver for puzzles and composed problems
+//
+// Unlike the search, which only estimates, this proves: every line is followed to the end, so a
+// solution it finds really forces mate and one it does not find does not exist. It is a plain
+// AND/OR search, deepened one move at a time so the shortest solution comes first. The side that
+// has to find a move tries checks first, then captures, and positions already decided at a given
+// number of moves are remembered by their hash.
+//
+// Three kinds of problem are solved, all from the side to move:
+//
+//   mate       the side to move mates within n moves against any defence
+//   helpmate   both sides work together so that the side to move is mated on the other side's
+//              n-th move
+//   selfmate   the side to move forces the other side to mate it within n moves, against a
+//              defence that does all it can not to
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+
+use crate::{Board, Move};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Stipulation {
+    Mate,
+    Helpmate,
+    Selfmate
+}
+
+// why a puzzle does not check out
+#[derive(Clone, PartialEq, Debug)]
+pub enum PuzzleError {
+    // nothing solves it in the number of moves given
+    NoSolution,
+    // the key move given is not a solution, these are
+    WrongKey(Vec<Move>),
+    // the key move given solves it, but so do these (cooks)
+    Cooked(Vec<Move>)
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = |moves: &[Move]| moves.iter().map(|mv| alloc::format!("{}", mv)).collect::<Vec<_>>().join(" ");
+
+        match self {
+            PuzzleError::NoSolution => write!(f, "no solution"),
+            PuzzleError::WrongKey(keys) => write!(f, "wrong key, the solutions are {}", list(keys)),
+            PuzzleError::Cooked(cooks) => write!(f, "cooked by {}", list(cooks))
+        }
+    }
+}
+
+impl Error for PuzzleError {}
+
+// the solver remembers, for each position and number of moves left, the first key move it found
+// for the side to move there, or None when there is none
+struct Solver {
+    stipulation: Stipulation,
+    known: BTreeMap<(u64, u32), Option<Move>>
+}
+
+impl Solver {
+    fn new(stipulation: Stipulation) -> Solver {
+        Solver { stipulation, known: BTreeMap::new() }
+    }
+
+    // whether the side to move, the one the stipulation is about, succeeds within n moves
+    fn solves(&mut self, board: &Board, n: u32) -> bool {
+        self.key(board, n).is_some()
+    }
+
+    // a first move that succeeds within n moves
+    fn key(&mut self, board: &Board, n: u32) -> Option<Move> {
+        if n == 0 { return None; }
+
+        let hash = board.hash();
+        if let Some(key) = self.known.get(&(hash, n)) { return *key; }
+
+        let key = self.candidates(board, n).into_iter().find(|mv| self.key_works(&after(board, *mv), n));
+
+        self.known.insert((hash, n), key);
+        key
+    }
+
+    // every first move that succeeds within n moves
+    fn keys(&mut self, board: &Board, n: u32) -> Vec<Move> {
+        self.candidates(board, n).into_iter().filter(|mv| self.key_works(&after(board, *mv), n)).collect()
+    }
+
+    // the moves worth trying, checks first, then captures, then the rest; a move that has to
+    // mate straight away has to be a check
+    fn candidates(&self, board: &Board, n: u32) -> Vec<Move> {
+        let checks_only = self.stipulation == Stipulation::Mate && n == 1;
+
+        let mut moves: Vec<(u8, Move)> = board.get_legal_moves().into_iter()
+            .map(|mv| {
+                let check = after(board, mv).in_check();
+                (if check { 0 } else if mv.is_capture() { 1 } else { 2 }, mv)
+            })
+            .filter(|(order, _)| !checks_only || *order == 0)
+            .collect();
+
+        moves.sort_by_key(|(order, _)| *order);
+        moves.into_iter().map(|(_, mv)| mv).collect()
+    }
+
+    // whether the position after a key candidate, with the other side to move, is won for the
+    // side that played it
+    fn key_works(&mut self, board: &Board, n: u32) -> bool {
+        let replies = board.get_legal_moves();
+        let mated = replies.is_empty() && board.in_check();
+
+        match self.stipulation {
+            Stipulation::Mate => mated || (n > 1 && !replies.is_empty() && replies.iter().all(|mv| self.solves(&after(board, *mv), n - 1))),
+
+            // the other side has to mate, so any reply that does will do
+            Stipulation::Helpmate => !replies.is_empty() && replies.iter().any(|mv| {
+                let child = after(board, *mv);
+                is_mated(&child) || self.solves(&child, n - 1)
+            }),
+
+            // the other side must have a reply, and every reply has to mate or leave a selfmate
+            // in one fewer; the defence picks the reply that avoids mating the longest
+            Stipulation::Selfmate => !replies.is_empty() && replies.iter().all(|mv| {
+                let child = after(board, *mv);
+                is_mated(&child) || self.solves(&child, n - 1)
+            })
+        }
+    }
+
+    // fewest moves within max the side to move needs, if it succeeds at all
+    fn moves_needed(&mut self, board: &Board, max: u32) -> Option<u32> {
+        (1..=max).find(|n| self.solves(board, *n))
+    }
+
+    // the line of a solution within n moves from a position where it is known to succeed, with
+    // the defence that holds out longest where the other side has a choice
+    fn line(&mut self, board: &Board, n: u32) -> Vec<Move> {
+        let mut line = Vec::new();
+        let mut board = board.clone();
+        let mut n = match self.moves_needed(&board, n) {
+            Some(n) => n,
+            None => return line
+        };
+
+        loop {
+            let key = match self.key(&board, n) {
+                Some(key) => key,
+                None => return line
+            };
+            line.push(key);
+            board = after(&board, key);
+
+            let replies = board.get_legal_moves();
+            if replies.is_empty() { return line; }
+
+            // the reply that ends it straight away for a helpmate, and otherwise for a selfmate
+            // only when nothing else is left; otherwise the one that needs the most moves after
+            let finishing = replies.iter().copied().find(|mv| is_mated(&after(&board, *mv)));
+            let reply = match (self.stipulation, finishing) {
+                (Stipulation::Helpmate, Some(mv)) => mv,
+                _ => {
+                    let mut best = None;
+
+                    for mv in replies.iter().copied() {
+                        let child = after(&board, mv);
+                        if is_mated(&child) { continue; }
+
+                        let needed = self.moves_needed(&child, n - 1);
+                        let helps = self.stipulation == Stipulation::Helpmate;
+
+                        // a helping reply wants the quickest finish, a defence the slowest
+                        let better = match (needed, best) {
+                            (None, _) => false,
+                            (Some(_), None) => true,
+                            (Some(m), Some((_, b))) => if helps { m < b } else { m > b }
+                        };
+                        if better { best = Some((mv, needed.unwrap_or(0))); }
+                    }
+
+                    match (best, finishing) {
+                        (Some((mv, needed)), _) => {
+                            line.push(mv);
+                            board = after(&board, mv);
+                            n = needed;
+                            continue;
+                        },
+                        (None, Some(mv)) => mv,
+                        (None, None) => return line
+                    }
+                }
+            };
+
+            line.push(reply);
+            return line;
+        }
+    }
+}
+
+// the position after a generated legal move
+fn after(board: &Board, mv: Move) -> Board {
+    let mut child = board.clone();
+    child.apply_move(mv);
+    child
+}
+
+fn is_mated(board: &Board) -> bool {
+    board.in_check() && board.get_legal_moves().is_empty()
+}
+
+impl Board {
+    // the shortest forced mate for the side to move within max_moves of its moves, as the line
+    // with the longest defence, e.g. [key, reply, mate] for a mate in two
+    pub fn find_mate(&self, max_moves: u32) -> Option<Vec<Move>> {
+        self.solve(Stipulation::Mate, max_moves)
+    }
+
+    // the shortest solution within max_moves moves of the side to move, see the top of the file
+    pub fn solve(&self, stipulation: Stipulation, max_moves: u32) -> Option<Vec<Move>> {
+        let mut solver = Solver::new(stipulation);
+
+        solver.moves_needed(self, max_moves)?;
+        Some(solver.line(self, max_moves))
+    }
+
+    // every first move that solves the problem within the given number of moves
+    pub fn key_moves(&self, stipulation: Stipulation, moves: u32) -> Vec<Move> {
+        Solver::new(stipulation).keys(self, moves)
+    }
+
+    // checks a puzzle with its intended key move: that the key solves it and nothing else does;
+    // the key can be given without its capture or castling flags, e.g. parsed from UCI notation
+    pub fn check_puzzle(&self, stipulation: Stipulation, moves: u32, key: Move) -> Result<(), PuzzleError> {
+        let keys = self.key_moves(stipulation, moves);
+
+        if keys.is_empty() {
+            Err(PuzzleError::NoSolution)
+        } else if !keys.contains(&key) {
+            Err(PuzzleError::WrongKey(keys))
+        } else if keys.len() > 1 {
+            Err(PuzzleError::Cooked(keys.into_iter().filter(|mv| *mv != key).collect()))
+        } else {
+            Ok(())
+        }
+    }
+}
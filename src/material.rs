@@ -0,0 +1,67 @@
+// Material counts and captured pieces
+//
+// Boards set from a FEN have no history, so their captured pieces are worked out from what is
+// missing from the starting set, with extra pieces beyond it counted as promoted pawns. Captures
+// made on the board after that are recorded as they happen.
+
+use alloc::vec::Vec;
+
+use crate::{Board, Color, PieceType, PIECE_KINDS};
+
+// number of pieces of each kind in the starting position, by piece array index
+const STARTING_COUNTS: [usize; 6] = [8, 2, 2, 2, 1, 1];
+
+impl Board {
+    // pieces of the given color that have been captured
+    pub fn captured(&self, color: Color) -> Vec<PieceType> {
+        match color {
+            Color::White => self.white_captured.clone(),
+            Color::Black => self.black_captured.clone()
+        }
+    }
+
+    pub(crate) fn captured_mut(&mut self, color: Color) -> &mut Vec<PieceType> {
+        match color {
+            Color::White => &mut self.white_captured,
+            Color::Black => &mut self.black_captured
+        }
+    }
+
+    // number of pieces of the given color and kind on the board
+    pub fn piece_count(&self, color: Color, kind: PieceType) -> usize {
+        self.pieces_of(color)[kind as usize].len()
+    }
+
+    // total points of the given color's pieces on the board
+    pub fn material(&self, color: Color) -> i32 {
+        PIECE_KINDS.iter().map(|kind| kind.points() * self.piece_count(color, *kind) as i32).sum()
+    }
+
+    // white's material minus black's, positive when white is ahead
+    pub fn material_difference(&self) -> i32 {
+        self.material(Color::White) - self.material(Color::Black)
+    }
+
+    // pieces of the starting set that are no longer on the board, pawns first
+    pub(crate) fn missing_material(&self, color: Color) -> Vec<PieceType> {
+        let mut missing = Vec::new();
+        let mut promoted = 0;
+
+        for kind in PIECE_KINDS[1..].iter() {
+            let count = self.piece_count(color, *kind);
+            let start = STARTING_COUNTS[*kind as usize];
+
+            promoted += count.saturating_sub(start);
+            for _ in count..start {
+                missing.push(*kind);
+            }
+        }
+
+        let pawns = STARTING_COUNTS[0].saturating_sub(self.piece_count(color, PieceType::Pawn) + promoted);
+        for _ in 0..pawns {
+            missing.insert(0, PieceType::Pawn);
+        }
+
+        missing
+    }
+}
@@ -0,0 +1,582 @@
+// Monte Carlo tree search
+//
+// The AlphaZero way of searching: rather than looking at every move to a fixed depth, it grows a
+// tree one position at a time, further down the lines that look best so far. Each playout walks
+// down from the root taking at every node the move with the best PUCT score, the average value
+// the move has had so far plus a bonus for moves the policy likes and that have had few visits,
+// evaluates the position it ends at and adds the value to every node on the way back up. The
+// move played is the one visited the most.
+//
+// Policy and value come from an Evaluator: a neural network in AlphaZero, and by default here a
+// heuristic one built from the static evaluation and static exchange evaluation, so everything
+// runs on the CPU, wasm included.
+//
+// Playouts are gathered in batches, so that an evaluator can work on several positions at once.
+// Each playout in a batch puts a virtual loss on the nodes it went through until its value is
+// in, which sends the next ones in the same batch down other lines.
+//
+// The tree is kept from one search to the next: when the position moves on by a move or two
+// that are in the tree, the subtree under them becomes the new root. For self-play, Dirichlet
+// noise can be mixed into the root's priors so that games do not all go the same way.
+//
+// The search knows the positions of the game before the root. A position in the tree that
+// repeats one since the root, or one from before it that has been seen twice, is a draw, and
+// the evaluator is told how many times every other position has been seen before.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::rng::Rng;
+use crate::search::{now, SearchInfo, SearchLimits, SearchResult, MATE};
+use crate::time::TimeManager;
+use crate::{Board, Move};
+
+// how many centipawns a value of tanh(1) is worth, for the heuristic evaluator's values and the
+// scores reported
+const VALUE_SCALE: f32 = 400.0;
+
+// how much lower than its parent's value a move that has not been visited yet is taken to be
+const FIRST_PLAY_REDUCTION: f32 = 0.2;
+
+// the tree stops growing at this many nodes, about 128 megabytes, and the search with it
+const MAX_NODES: usize = 1 << 22;
+
+// what an evaluator says about a position
+#[derive(Clone, PartialEq, Debug)]
+pub struct Evaluation {
+    // a prior for each legal move, in the order they were given, adding up to 1
+    pub priors: Vec<f32>,
+    // from -1, lost for the side to move, through 0, a draw, to 1, won
+    pub value: f32
+}
+
+// the policy and value the search is guided by
+pub trait Evaluator {
+    // evaluates a position that is not over, given how many times it has been seen before in the
+    // game and its legal moves
+    fn evaluate(&mut self, board: &Board, repetitions: u32, moves: &[Move]) -> Evaluation;
+
+    // evaluates the positions of a batch of playouts; one at a time unless the evaluator can do
+    // better
+    fn evaluate_batch(&mut self, positions: &[(Board, u32, Vec<Move>)]) -> Vec<Evaluation> {
+        positions.iter().map(|(board, repetitions, moves)| self.evaluate(board, *repetitions, moves)).collect()
+    }
+}
+
+// an evaluator picked at run time, e.g. a network once its weights are loaded
+impl<E: Evaluator + ?Sized> Evaluator for alloc::boxed::Box<E> {
+    fn evaluate(&mut self, board: &Board, repetitions: u32, moves: &[Move]) -> Evaluation {
+        (**self).evaluate(board, repetitions, moves)
+    }
+
+    fn evaluate_batch(&mut self, positions: &[(Board, u32, Vec<Move>)]) -> Vec<Evaluation> {
+        (**self).evaluate_batch(positions)
+    }
+}
+
+// the evaluator without a network: the value is the static evaluation, counting on the best
+// winning capture being made, and the priors favour captures that win material, promotions and
+// checks; it pays no attention to repetitions
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct HeuristicEvaluator;
+
+impl Evaluator for HeuristicEvaluator {
+    fn evaluate(&mut self, board: &Board, _repetitions: u32, moves: &[Move]) -> Evaluation {
+        let mut best_gain = 0;
+
+        let logits: Vec<f32> = moves.iter().map(|mv| {
+            let mut logit = 0.0;
+
+            if mv.is_capture() {
+                let gain = board.see(*mv);
+                best_gain = best_gain.max(gain);
+                logit += 0.5 + gain as f32 / 200.0;
+            }
+            if let Some(kind) = mv.promotion() {
+                logit += kind.value() as f32 / 400.0;
+            }
+
+            let mut child = board.clone();
+            child.apply_move(*mv);
+            if child.in_check() { logit += 1.0; }
+
+            logit
+        }).collect();
+
+        let value = libm::tanhf((board.evaluate() + best_gain) as f32 / VALUE_SCALE);
+
+        Evaluation { priors: softmax(&logits), value }
+    }
+}
+
+pub(crate) fn softmax(logits: &[f32]) -> Vec<f32> {
+    let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let exps: Vec<f32> = logits.iter().map(|logit| libm::expf(logit - max)).collect();
+    let sum: f32 = exps.iter().sum();
+
+    exps.into_iter().map(|e| e / sum).collect()
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MctsConfig {
+    // how much the priors count against the values found so far
+    pub c_puct: f32,
+    // playouts evaluated together
+    pub batch_size: usize,
+    // the Dirichlet noise on the root's priors and how much of it is mixed in, 0 for none
+    pub noise_alpha: f32,
+    pub noise_fraction: f32,
+    // for the noise, the same seed gives the same search
+    pub seed: u64
+}
+
+// AlphaZero's settings for chess, without noise, which is only wanted for self-play
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig { c_puct: 1.5, batch_size: 8, noise_alpha: 0.3, noise_fraction: 0.0, seed: 1 }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum State {
+    // not evaluated yet
+    Leaf,
+    // waiting for its evaluation in the current batch
+    Pending,
+    Expanded,
+    // the game is over, with this value for the side to move
+    Over(f32)
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    // the move that leads here, None at the root
+    mv: Option<Move>,
+    prior: f32,
+    visits: u32,
+    // the values backed up through the node, from the point of view of the side that played mv
+    value: f32,
+    // playouts of the current batch that went through the node and are not back yet
+    virtual_loss: u32,
+    // the children are next to each other, from first on
+    first: u32,
+    count: u32,
+    state: State
+}
+
+impl Node {
+    fn new(mv: Option<Move>, prior: f32) -> Node {
+        Node { mv, prior, visits: 0, value: 0.0, virtual_loss: 0, first: 0, count: 0, state: State::Leaf }
+    }
+
+    // visits with the virtual losses counted as visits that were lost
+    fn visits_with_loss(&self) -> u32 {
+        self.visits + self.virtual_loss
+    }
+
+    // the average value for the side that played the move, None without visits
+    fn q(&self) -> Option<f32> {
+        match self.visits_with_loss() {
+            0 => None,
+            n => Some((self.value - self.virtual_loss as f32) / n as f32)
+        }
+    }
+}
+
+pub struct Mcts<E: Evaluator> {
+    evaluator: E,
+    config: MctsConfig,
+    // the position at the root, the tree is nodes[0] and everything under it
+    board: Board,
+    // the hashes of the game's positions before the root, oldest first
+    history: Vec<u64>,
+    nodes: Vec<Node>,
+    rng: Rng,
+    // whether the root's priors have had their noise
+    noisy: bool
+}
+
+impl<E: Evaluator> Mcts<E> {
+    pub fn new(board: &Board, evaluator: E, config: MctsConfig) -> Mcts<E> {
+        Mcts {
+            evaluator,
+            config,
+            board: board.clone(),
+            history: Vec::new(),
+            nodes: vec![Node::new(None, 1.0)],
+            rng: Rng::new(config.seed),
+            noisy: false
+        }
+    }
+
+    // the position the tree starts from
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn evaluator(&self) -> &E {
+        &self.evaluator
+    }
+
+    // playouts that have gone through the root, those of earlier searches included
+    pub fn playouts(&self) -> u32 {
+        self.nodes[0].visits
+    }
+
+    // the root's moves with their visits, the policy a network learns from in self-play
+    pub fn root_visits(&self) -> Vec<(Move, u32)> {
+        self.children(0).filter_map(|i| Some((self.nodes[i].mv?, self.nodes[i].visits))).collect()
+    }
+
+    // throws the tree away, keeping the position
+    pub fn clear(&mut self) {
+        self.reset(self.board.clone());
+    }
+
+    // moves the root on to the given position, keeping the subtree under it when it is the
+    // position after one of the root's moves or after one of those and a reply; history is the
+    // hashes of the positions before it in the game, oldest first
+    pub fn set_position(&mut self, board: &Board, history: &[u64]) {
+        let hash = board.hash();
+        self.history = history.to_vec();
+        if hash == self.board.hash() { return; }
+
+        for child in self.children(0) {
+            let after_child = after(&self.board, self.nodes[child].mv);
+            if after_child.hash() == hash { return self.reroot(child, board); }
+
+            for grandchild in self.children(child) {
+                if after(&after_child, self.nodes[grandchild].mv).hash() == hash {
+                    return self.reroot(grandchild, board);
+                }
+            }
+        }
+
+        self.reset(board.clone());
+    }
+
+    // plays a move at the root, keeping its subtree; a move that is not legal there is ignored
+    pub fn play(&mut self, mv: Move) {
+        if let Some(child) = self.children(0).find(|i| self.nodes[*i].mv == Some(mv)) {
+            let board = after(&self.board, Some(mv));
+            self.history.push(self.board.hash());
+            return self.reroot(child, &board);
+        }
+
+        if let Some(mv) = self.board.get_legal_moves().into_iter().find(|m| *m == mv) {
+            self.history.push(self.board.hash());
+            self.reset(after(&self.board, Some(mv)));
+        }
+    }
+
+    fn reset(&mut self, board: Board) {
+        self.board = board;
+        self.nodes = vec![Node::new(None, 1.0)];
+        self.noisy = false;
+    }
+
+    // makes node the root, copying its subtree to a new tree and dropping the rest
+    fn reroot(&mut self, node: usize, board: &Board) {
+        let mut nodes = vec![self.nodes[node].clone()];
+        nodes[0].mv = None;
+        nodes[0].prior = 1.0;
+
+        // the children of a copied node still point into the old tree until it is its turn
+        let mut i = 0;
+        while i < nodes.len() {
+            // a repetition since the old root may not be one since the new root, so draws are
+            // looked at again the next time a playout gets there
+            if nodes[i].state == State::Over(0.0) { nodes[i].state = State::Leaf; }
+
+            let (first, count) = (nodes[i].first as usize, nodes[i].count as usize);
+            nodes[i].first = nodes.len() as u32;
+            nodes.extend_from_slice(&self.nodes[first..first + count]);
+            i += 1;
+        }
+
+        self.board = board.clone();
+        self.nodes = nodes;
+        self.noisy = false;
+    }
+
+    fn children(&self, node: usize) -> impl Iterator<Item = usize> {
+        let first = self.nodes[node].first as usize;
+        first..first + self.nodes[node].count as usize
+    }
+
+    // searches until a limit is reached or it is stopped, calling on_info every time the average
+    // depth of the playouts goes up and once at the end, and returns the most visited move; None
+    // only when the game is over at the root
+    //
+    // nodes counts playouts, movetime and the clock limit the time, and depth the average depth
+    // of the playouts; without a limit it runs until stopped, or the tree is full
+    pub fn search(&mut self, limits: &SearchLimits, stop: &dyn Fn() -> bool, on_info: &mut dyn FnMut(&SearchInfo)) -> Option<SearchResult> {
+        let start = now();
+        let elapsed = || (now() - start).max(0.0) as u64;
+
+        // there is no iteration to finish, so the time it would like to take is the time it takes
+        let soft_limit = limits.clock.as_ref().map(|clock| TimeManager::new(clock).soft_limit());
+        let deadline = [limits.movetime, soft_limit].iter().flatten().min().copied();
+
+        let moves = self.board.get_legal_moves();
+        if moves.is_empty() { return None; }
+
+        // a root that is drawn by rule still has moves to choose from
+        if let State::Over(_) = self.nodes[0].state {
+            self.nodes[0].state = State::Leaf;
+        }
+        if self.nodes[0].state == State::Leaf {
+            let repetitions = self.history.iter().filter(|hash| **hash == self.board.hash()).count() as u32;
+            let evaluation = self.evaluator.evaluate(&self.board, repetitions, &moves);
+            self.expand(0, &moves, &evaluation.priors);
+            self.backup(&[0], evaluation.value);
+        }
+        if !self.noisy && self.config.noise_fraction > 0.0 {
+            self.add_noise();
+        }
+
+        let mut playouts = 0;
+        let mut total_depth = 0;
+        let mut seldepth = 0;
+        let mut reported = 0;
+
+        loop {
+            let out_of_time = deadline.is_some_and(|deadline| elapsed() >= deadline);
+            let enough = limits.nodes.is_some_and(|nodes| playouts >= nodes);
+            let deep_enough = limits.depth.is_some_and(|depth| playouts > 0 && total_depth / playouts >= depth as u64);
+
+            if out_of_time || enough || deep_enough || self.nodes.len() >= MAX_NODES || stop() { break; }
+
+            // a batch never goes past the node limit, and a single forced move needs no more
+            let batch = limits.nodes.map_or(self.config.batch_size, |nodes| (nodes - playouts).min(self.config.batch_size as u64) as usize);
+            for depth in self.run_batch(batch.max(1)) {
+                playouts += 1;
+                total_depth += depth as u64;
+                seldepth = seldepth.max(depth);
+            }
+
+            let depth = (total_depth / playouts.max(1)) as u32;
+            if depth > reported {
+                reported = depth;
+                on_info(&self.info(depth, seldepth, playouts, elapsed()));
+            }
+
+            if moves.len() == 1 && limits.clock.is_some() { break; }
+        }
+
+        let info = self.info(reported.max(1), seldepth, playouts, elapsed());
+        on_info(&info);
+
+        Some(SearchResult {
+            best_move: info.pv[0],
+            depth: info.depth,
+            seldepth: info.seldepth,
+            score: info.score,
+            nodes: info.nodes,
+            time: info.time,
+            pv: info.pv.clone(),
+            lines: vec![info]
+        })
+    }
+
+    // runs a batch of up to size playouts and returns the depth of each; stops gathering early
+    // when a playout runs into one still waiting for its evaluation
+    fn run_batch(&mut self, size: usize) -> Vec<u32> {
+        let mut depths = Vec::new();
+        let mut waiting: Vec<Vec<usize>> = Vec::new();
+        let mut positions: Vec<(Board, u32, Vec<Move>)> = Vec::new();
+
+        for _ in 0..size {
+            let (path, board, seen) = self.select();
+            let leaf = *path.last().unwrap_or(&0);
+
+            match self.nodes[leaf].state {
+                State::Over(value) => {
+                    self.backup(&path, value);
+                    depths.push(path.len() as u32 - 1);
+                },
+                State::Pending => {
+                    self.undo_virtual_loss(&path);
+                    break;
+                },
+                _ => {
+                    let moves = board.get_legal_moves();
+
+                    // the times the position has been seen before, since the root and in all
+                    let hash = board.hash();
+                    let since_root = seen.iter().filter(|h| **h == hash).count() as u32;
+                    let repetitions = since_root + self.history.iter().filter(|h| **h == hash).count() as u32;
+
+                    match game_over(&board, &moves, since_root >= 1 || repetitions >= 2) {
+                        Some(value) => {
+                            self.nodes[leaf].state = State::Over(value);
+                            self.backup(&path, value);
+                            depths.push(path.len() as u32 - 1);
+                        },
+                        None => {
+                            self.nodes[leaf].state = State::Pending;
+                            waiting.push(path);
+                            positions.push((board, repetitions, moves));
+                        }
+                    }
+                }
+            }
+        }
+
+        if positions.is_empty() { return depths; }
+
+        let evaluations = self.evaluator.evaluate_batch(&positions);
+        for ((path, (_, _, moves)), evaluation) in waiting.iter().zip(positions.iter()).zip(evaluations.iter()) {
+            let leaf = path[path.len() - 1];
+            self.expand(leaf, moves, &evaluation.priors);
+            self.backup(path, evaluation.value);
+            depths.push(path.len() as u32 - 1);
+        }
+
+        depths
+    }
+
+    // walks down from the root to a node that has not been expanded, putting a virtual loss on
+    // every node on the way, and returns the path, the position at its end and the hashes of the
+    // positions before it from the root on
+    fn select(&mut self) -> (Vec<usize>, Board, Vec<u64>) {
+        let mut path = vec![0];
+        let mut board = self.board.clone();
+        let mut seen = Vec::new();
+        let mut node = 0;
+
+        self.nodes[0].virtual_loss += 1;
+
+        while self.nodes[node].state == State::Expanded {
+            node = self.best_child(node);
+            seen.push(board.hash());
+            if let Some(mv) = self.nodes[node].mv { board.apply_move(mv); }
+
+            self.nodes[node].virtual_loss += 1;
+            path.push(node);
+        }
+
+        (path, board, seen)
+    }
+
+    // the child with the best PUCT score: its average value, or its parent's less a bit before
+    // it has any, plus c_puct * prior * sqrt(parent visits) / (1 + visits)
+    fn best_child(&self, node: usize) -> usize {
+        let parent = &self.nodes[node];
+        let first_play = parent.q().map_or(0.0, |q| -q) - FIRST_PLAY_REDUCTION;
+        let exploration = self.config.c_puct * libm::sqrtf(parent.visits_with_loss().max(1) as f32);
+
+        let score = |child: &Node| {
+            child.q().unwrap_or(first_play) + exploration * child.prior / (1 + child.visits_with_loss()) as f32
+        };
+
+        self.children(node)
+            .fold(None, |best: Option<(usize, f32)>, i| {
+                let s = score(&self.nodes[i]);
+                match best {
+                    Some((_, b)) if b >= s => best,
+                    _ => Some((i, s))
+                }
+            })
+            .map_or(node, |(i, _)| i)
+    }
+
+    fn expand(&mut self, node: usize, moves: &[Move], priors: &[f32]) {
+        let first = self.nodes.len() as u32;
+
+        for (i, mv) in moves.iter().enumerate() {
+            self.nodes.push(Node::new(Some(*mv), priors.get(i).copied().unwrap_or(0.0)));
+        }
+
+        let node = &mut self.nodes[node];
+        node.first = first;
+        node.count = moves.len() as u32;
+        node.state = State::Expanded;
+    }
+
+    // adds the value, for the side to move at the end of the path, to every node on it, each
+    // from the point of view of the side that moved into it, and takes the virtual losses off
+    fn backup(&mut self, path: &[usize], value: f32) {
+        let mut value = -value;
+
+        for node in path.iter().rev() {
+            let node = &mut self.nodes[*node];
+            node.visits += 1;
+            node.value += value;
+            node.virtual_loss = node.virtual_loss.saturating_sub(1);
+            value = -value;
+        }
+    }
+
+    fn undo_virtual_loss(&mut self, path: &[usize]) {
+        for node in path.iter() {
+            self.nodes[*node].virtual_loss = self.nodes[*node].virtual_loss.saturating_sub(1);
+        }
+    }
+
+    fn add_noise(&mut self) {
+        let children: Vec<usize> = self.children(0).collect();
+        let noise = self.rng.dirichlet(self.config.noise_alpha as f64, children.len());
+        let fraction = self.config.noise_fraction;
+
+        for (i, eta) in children.into_iter().zip(noise) {
+            self.nodes[i].prior = (1.0 - fraction) * self.nodes[i].prior + fraction * eta;
+        }
+
+        self.noisy = true;
+    }
+
+    // the most visited child, the one with the better value and then the better prior on a tie
+    fn most_visited(&self, node: usize) -> Option<usize> {
+        self.children(node).max_by(|a, b| {
+            let (a, b) = (&self.nodes[*a], &self.nodes[*b]);
+            a.visits.cmp(&b.visits)
+                .then(a.q().unwrap_or(-1.0).total_cmp(&b.q().unwrap_or(-1.0)))
+                .then(a.prior.total_cmp(&b.prior))
+        })
+    }
+
+    fn info(&self, depth: u32, seldepth: u32, playouts: u64, time: u64) -> SearchInfo {
+        let mut pv = Vec::new();
+        let mut node = 0;
+
+        // the root's best move even when stopped before any playout, then only moves visited
+        while let Some(child) = self.most_visited(node) {
+            if node != 0 && self.nodes[child].visits == 0 { break; }
+            pv.extend(self.nodes[child].mv);
+            node = child;
+        }
+
+        let best = self.most_visited(0).map(|i| &self.nodes[i]);
+
+        // a move that mates is the one case the tree can be sure of
+        let score = match best {
+            Some(node) if node.state == State::Over(-1.0) => MATE - 1,
+            Some(node) => {
+                let q = node.q().unwrap_or(0.0).clamp(-0.999, 0.999);
+                (libm::atanhf(q) * VALUE_SCALE) as i32
+            },
+            None => 0
+        };
+
+        SearchInfo { depth, multipv: 1, seldepth: seldepth.max(depth), score, nodes: playouts, time, pv }
+    }
+}
+
+fn after(board: &Board, mv: Option<Move>) -> Board {
+    let mut child = board.clone();
+    if let Some(mv) = mv { child.apply_move(mv); }
+    child
+}
+
+// the value for the side to move when the game is over, -1 for checkmate and 0 for a draw,
+// repeated or not
+fn game_over(board: &Board, moves: &[Move], repeated: bool) -> Option<f32> {
+    if moves.is_empty() {
+        Some(if board.in_check() { -1.0 } else { 0.0 })
+    } else if repeated || board.halfmove_clock() >= 100 || board.is_insufficient_material() {
+        Some(0.0)
+    } else {
+        None
+    }
+}
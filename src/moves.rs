@@ -0,0 +1,206 @@
+// Moves packed into 16 bits
+//
+// bits 0-5 hold the from square, bits 6-11 the to square and bits 12-15 the flags below, which
+// follow the usual layout where bit 14 marks captures and bit 15 promotions
+
+use core::error::Error;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::str::FromStr;
+
+use crate::{PieceType, Square};
+
+const QUIET: u16 = 0;
+const DOUBLE_PUSH: u16 = 1;
+const KING_CASTLE: u16 = 2;
+const QUEEN_CASTLE: u16 = 3;
+const CAPTURE: u16 = 4;
+const EN_PASSANT: u16 = 5;
+const PROMOTION: u16 = 8;
+
+// piece kinds a pawn can promote to, in the order of the low two flag bits
+const PROMOTIONS: [PieceType; 4] = [PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen];
+
+// two moves are equal when they go between the same squares with the same promotion, so a move
+// parsed from text equals the generated one whatever flags the position gives it; use
+// Move::identical to compare the flags as well
+#[derive(Clone, Copy, Eq, Debug)]
+pub struct Move(u16);
+
+impl PartialEq for Move {
+    fn eq(&self, other: &Move) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Hash for Move {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl Move {
+    // a move of the piece on `from` to `to`, with the piece to promote to when a pawn reaches the last rank
+    //
+    // the move carries no capture or castling flags, Board::play_move looks those up from the position;
+    // a promotion to a pawn or a king is an error
+    pub fn new(from: Square, to: Square, promotion: Option<PieceType>) -> Result<Move, PromotionError> {
+        let flags = match promotion {
+            Some(kind) => PROMOTION | Move::promotion_bits(kind).ok_or(PromotionError(kind))?,
+            None => QUIET
+        };
+
+        Ok(Move::with_flags(from, to, flags))
+    }
+
+    // a move as generated from a position, with its capture/castling/en passant flags set
+    pub(crate) fn generated(from: Square, to: Square, promotion: Option<PieceType>, capture: bool, special: Special) -> Move {
+        let mut flags = match special {
+            Special::None => QUIET,
+            Special::DoublePush => DOUBLE_PUSH,
+            Special::KingCastle => KING_CASTLE,
+            Special::QueenCastle => QUEEN_CASTLE,
+            Special::EnPassant => EN_PASSANT
+        };
+
+        if let Some(bits) = promotion.and_then(Move::promotion_bits) { flags = PROMOTION | bits; }
+        if capture { flags |= CAPTURE; }
+
+        Move::with_flags(from, to, flags)
+    }
+
+    fn with_flags(from: Square, to: Square, flags: u16) -> Move {
+        Move(from.index() as u16 | (to.index() as u16) << 6 | flags << 12)
+    }
+
+    // the squares and the promotion, without the capture and special move flags
+    fn key(&self) -> u16 {
+        if self.flags() & PROMOTION != 0 { self.0 & 0xbfff } else { self.0 & 0x0fff }
+    }
+
+    fn promotion_bits(kind: PieceType) -> Option<u16> {
+        PROMOTIONS.iter().position(|k| *k == kind).map(|i| i as u16)
+    }
+
+    // unpacks a move from its 16 bit form, None if the flags are not a valid combination
+    pub fn from_bits(bits: u16) -> Option<Move> {
+        match bits >> 12 {
+            6 | 7 => None,
+            _ => Some(Move(bits))
+        }
+    }
+
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
+
+    pub fn from(&self) -> Square {
+        Square::from_bit_index((self.0 & 0x3f) as u32)
+    }
+
+    pub fn to(&self) -> Square {
+        Square::from_bit_index((self.0 >> 6 & 0x3f) as u32)
+    }
+
+    fn flags(&self) -> u16 {
+        self.0 >> 12
+    }
+
+    pub fn promotion(&self) -> Option<PieceType> {
+        if self.flags() & PROMOTION != 0 { Some(PROMOTIONS[(self.flags() & 3) as usize]) } else { None }
+    }
+
+    pub fn is_capture(&self) -> bool {
+        self.flags() & CAPTURE != 0
+    }
+
+    pub fn is_en_passant(&self) -> bool {
+        self.flags() == EN_PASSANT
+    }
+
+    pub fn is_castle(&self) -> bool {
+        self.flags() == KING_CASTLE || self.flags() == QUEEN_CASTLE
+    }
+
+    pub fn is_double_push(&self) -> bool {
+        self.flags() == DOUBLE_PUSH
+    }
+
+    // true if both moves are the same down to their capture and special move flags
+    pub fn identical(&self, other: &Move) -> bool {
+        self.0 == other.0
+    }
+}
+
+// the kind of non-capture special move, used when generating moves
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum Special {
+    None,
+    DoublePush,
+    KingCastle,
+    QueenCastle,
+    EnPassant
+}
+
+// returned when a string is not a move in UCI notation
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ParseMoveError;
+
+impl fmt::Display for ParseMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid move")
+    }
+}
+
+impl Error for ParseMoveError {}
+
+// returned by Move::new for a promotion to anything but a knight, bishop, rook or queen
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PromotionError(pub PieceType);
+
+impl fmt::Display for PromotionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a pawn cannot promote to a {:?}", self.0)
+    }
+}
+
+impl Error for PromotionError {}
+
+// UCI notation, e.g. e2e4 or e7e8q
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.from(), self.to())?;
+
+        match self.promotion() {
+            Some(PieceType::Knight) => write!(f, "n"),
+            Some(PieceType::Bishop) => write!(f, "b"),
+            Some(PieceType::Rook) => write!(f, "r"),
+            Some(_) => write!(f, "q"),
+            None => Ok(())
+        }
+    }
+}
+
+impl FromStr for Move {
+    type Err = ParseMoveError;
+
+    fn from_str(s: &str) -> Result<Move, ParseMoveError> {
+        if !s.is_ascii() || (s.len() != 4 && s.len() != 5) { return Err(ParseMoveError); }
+
+        let from = s[0..2].parse().map_err(|_| ParseMoveError)?;
+        let to = s[2..4].parse().map_err(|_| ParseMoveError)?;
+        let promotion = match &s[4..] {
+            "" => None,
+            "p" => Some(PieceType::Pawn),
+            "n" => Some(PieceType::Knight),
+            "b" => Some(PieceType::Bishop),
+            "r" => Some(PieceType::Rook),
+            "q" => Some(PieceType::Queen),
+            "k" => Some(PieceType::King),
+            _ => return Err(ParseMoveError)
+        };
+
+        // Move::new has the last word on which pieces a pawn can become
+        Move::new(from, to, promotion).map_err(|_| ParseMoveError)
+    }
+}
@@ -0,0 +1,428 @@
+// Policy and value network
+//
+// A small network over the position that gives the tree search its priors and values, the way
+// AlphaZero's does, run on the CPU. It is a multilayer perceptron: a trunk of fully connected
+// layers with ReLU, where a layer as wide as the one before it adds its input back in as a
+// residual connection, then a policy head with a logit for every move index and a value head of
+// one more hidden layer and a tanh output.
+//
+// The input is a stack of 8x8 planes, seen from the side to move, whose pieces are always at the
+// bottom of the board:
+//
+//   0-5     the side to move's pawns, knights, bishops, rooks, queens and king
+//   6-11    the other side's
+//   12      all ones if the position has been seen once before, 13 if twice or more
+//   14      all ones when black is to move
+//   15-18   castling rights: the side to move's king side and queen side, then the other side's
+//   19      the halfmove clock over 100
+//
+// and a move's index is its from square, seen from the side to move, times 73 plus one of 73
+// kinds of move, as in AlphaZero: 56 queen moves (8 directions from north clockwise, 1 to 7
+// squares each), 8 knight moves and 9 underpromotions (to a knight, bishop or rook, capturing
+// left, straight on or capturing right). Promotions to a queen are queen moves and castling is
+// the king's move of two squares.
+//
+// The dense layers use SSE on x86_64 and NEON on aarch64, which every CPU of those has, and
+// plain loops elsewhere, wasm included.
+//
+// Weights are stored in a simple binary format, all numbers little endian:
+//
+//   "ARNN"                      magic
+//   u32                         version, 1
+//   u32                         inputs, 1280
+//   u32                         policy outputs, 4672
+//   u32 n, then n u32           the number of trunk layers and the width of each
+//   u32                         the width of the value head's hidden layer
+//   then for every layer, trunk layers first, then the policy layer, the value hidden layer and
+//   the value output: its weights as f32, one row of inputs per output, then an f32 bias for
+//   every output
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+
+use crate::mcts::{softmax, Evaluation, Evaluator};
+use crate::pgn::Game;
+use crate::rng::Rng;
+use crate::{Board, Color, Move, PieceType, Square};
+
+pub const PLANES: usize = 20;
+pub const INPUTS: usize = PLANES * 64;
+
+const MOVE_KINDS: usize = 73;
+pub const POLICY_SIZE: usize = 64 * MOVE_KINDS;
+
+const MAGIC: &[u8; 4] = b"ARNN";
+const VERSION: u32 = 1;
+
+// the queen move directions from north clockwise, and the knight moves in the same order
+const DIRECTIONS: [(i32, i32); 8] = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+const KNIGHT_MOVES: [(i32, i32); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+const UNDERPROMOTIONS: [PieceType; 3] = [PieceType::Knight, PieceType::Bishop, PieceType::Rook];
+
+// a square as the side to move sees it, the board turned around for black
+fn orient(square: Square, side: Color) -> usize {
+    match side {
+        Color::White => square.index() as usize,
+        Color::Black => square.index() as usize ^ 56
+    }
+}
+
+// the input planes for a position that has been seen `repetitions` times before, see the top
+// of the file
+pub fn encode(board: &Board, repetitions: u32) -> Vec<f32> {
+    let mut input = vec![0.0; INPUTS];
+    let side = board.get_side_to_move();
+    let mut fill = |plane: usize| input[plane * 64..(plane + 1) * 64].iter_mut().for_each(|x| *x = 1.0);
+
+    if repetitions >= 1 { fill(12); }
+    if repetitions >= 2 { fill(13); }
+    if side == Color::Black { fill(14); }
+
+    let castling = board.fen.split_whitespace().nth(2).unwrap_or("-");
+    let rights = match side {
+        Color::White => ['K', 'Q', 'k', 'q'],
+        Color::Black => ['k', 'q', 'K', 'Q']
+    };
+    for (i, right) in rights.iter().enumerate() {
+        if castling.contains(*right) { fill(15 + i); }
+    }
+
+    let clock = board.halfmove_clock().min(100) as f32 / 100.0;
+    input[19 * 64..].iter_mut().for_each(|x| *x = clock);
+
+    for color in [side, side.opposite()].iter() {
+        let offset = if *color == side { 0 } else { 6 };
+
+        for piece in board.pieces(*color) {
+            input[(offset + piece.kind as usize) * 64 + orient(piece.square, side)] = 1.0;
+        }
+    }
+
+    input
+}
+
+// the input planes for the position at the end of a game, with its repetitions counted
+pub fn encode_game(game: &Game) -> Vec<f32> {
+    let mut board = game.start();
+    let mut hashes = vec![board.hash()];
+
+    for mv in game.moves() {
+        board.play_move(*mv);
+        hashes.push(board.hash());
+    }
+
+    let repetitions = hashes.iter().filter(|hash| **hash == board.hash()).count() - 1;
+    encode(&board, repetitions as u32)
+}
+
+// the policy index of a move made by the given side, see the top of the file
+pub fn move_index(mv: Move, side: Color) -> usize {
+    let (from, to) = (orient(mv.from(), side), orient(mv.to(), side));
+    let files = (to % 8) as i32 - (from % 8) as i32;
+    let ranks = (to / 8) as i32 - (from / 8) as i32;
+
+    let kind = match mv.promotion() {
+        Some(kind) if kind != PieceType::Queen => {
+            let piece = UNDERPROMOTIONS.iter().position(|k| *k == kind).unwrap_or(0);
+            64 + piece * 3 + (files + 1) as usize
+        },
+        _ => match KNIGHT_MOVES.iter().position(|step| *step == (files, ranks)) {
+            Some(knight) => 56 + knight,
+            None => {
+                let distance = files.abs().max(ranks.abs());
+                let direction = DIRECTIONS.iter().position(|d| *d == (files.signum(), ranks.signum())).unwrap_or(0);
+                direction * 7 + (distance - 1) as usize
+            }
+        }
+    };
+
+    from * MOVE_KINDS + kind
+}
+
+// why weights could not be loaded
+#[derive(Clone, PartialEq, Debug)]
+pub enum LoadError {
+    // not starting with ARNN
+    Magic,
+    Version(u32),
+    // input or policy sizes that do not match the encoding, or a layer without width
+    Shape,
+    // fewer bytes than the layers need
+    Truncated,
+    // more bytes than the layers need
+    TrailingBytes
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Magic => write!(f, "invalid network: not an alpha-rust network"),
+            LoadError::Version(version) => write!(f, "invalid network: unknown version {}", version),
+            LoadError::Shape => write!(f, "invalid network: bad layer sizes"),
+            LoadError::Truncated => write!(f, "invalid network: file too short"),
+            LoadError::TrailingBytes => write!(f, "invalid network: file too long")
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Dense {
+    inputs: usize,
+    outputs: usize,
+    // outputs rows of inputs weights each
+    weights: Vec<f32>,
+    biases: Vec<f32>
+}
+
+impl Dense {
+    // He initialisation, uniform, with zero biases
+    fn random(inputs: usize, outputs: usize, rng: &mut Rng) -> Dense {
+        let limit = libm::sqrt(6.0 / inputs as f64);
+
+        Dense {
+            inputs,
+            outputs,
+            weights: (0..inputs * outputs).map(|_| ((rng.uniform() * 2.0 - 1.0) * limit) as f32).collect(),
+            biases: vec![0.0; outputs]
+        }
+    }
+
+    fn forward(&self, input: &[f32]) -> Vec<f32> {
+        self.weights.chunks_exact(self.inputs)
+            .zip(self.biases.iter())
+            .map(|(row, bias)| dot(row, input) + bias)
+            .collect()
+    }
+}
+
+fn relu(values: &mut [f32]) {
+    values.iter_mut().for_each(|x| *x = x.max(0.0));
+}
+
+#[cfg(target_arch = "x86_64")]
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    use core::arch::x86_64::*;
+
+    let n = a.len().min(b.len());
+    let mut sum = 0.0;
+
+    // SAFETY: SSE is part of x86_64, and every load is of four floats inside both slices
+    unsafe {
+        let (mut low, mut high) = (_mm_setzero_ps(), _mm_setzero_ps());
+
+        for i in (0..n - n % 8).step_by(8) {
+            low = _mm_add_ps(low, _mm_mul_ps(_mm_loadu_ps(a.as_ptr().add(i)), _mm_loadu_ps(b.as_ptr().add(i))));
+            high = _mm_add_ps(high, _mm_mul_ps(_mm_loadu_ps(a.as_ptr().add(i + 4)), _mm_loadu_ps(b.as_ptr().add(i + 4))));
+        }
+
+        let mut lanes = [0.0; 4];
+        _mm_storeu_ps(lanes.as_mut_ptr(), _mm_add_ps(low, high));
+        sum += lanes.iter().sum::<f32>();
+    }
+
+    sum + a[n - n % 8..n].iter().zip(b[n - n % 8..n].iter()).map(|(x, y)| x * y).sum::<f32>()
+}
+
+#[cfg(target_arch = "aarch64")]
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    use core::arch::aarch64::*;
+
+    let n = a.len().min(b.len());
+    let mut sum = 0.0;
+
+    // SAFETY: NEON is part of aarch64, and every load is of four floats inside both slices
+    unsafe {
+        let (mut low, mut high) = (vdupq_n_f32(0.0), vdupq_n_f32(0.0));
+
+        for i in (0..n - n % 8).step_by(8) {
+            low = vfmaq_f32(low, vld1q_f32(a.as_ptr().add(i)), vld1q_f32(b.as_ptr().add(i)));
+            high = vfmaq_f32(high, vld1q_f32(a.as_ptr().add(i + 4)), vld1q_f32(b.as_ptr().add(i + 4)));
+        }
+
+        sum += vaddvq_f32(vaddq_f32(low, high));
+    }
+
+    sum + a[n - n % 8..n].iter().zip(b[n - n % 8..n].iter()).map(|(x, y)| x * y).sum::<f32>()
+}
+
+// four sums side by side, which is as far as plain loops go
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    let mut sums = [0.0; 4];
+    let (a, b) = (a.chunks_exact(4), b.chunks_exact(4));
+    let tail: f32 = a.remainder().iter().zip(b.remainder().iter()).map(|(x, y)| x * y).sum();
+
+    for (x, y) in a.zip(b) {
+        for ((sum, x), y) in sums.iter_mut().zip(x).zip(y) {
+            *sum += x * y;
+        }
+    }
+
+    sums.iter().sum::<f32>() + tail
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Network {
+    trunk: Vec<Dense>,
+    policy: Dense,
+    value_hidden: Dense,
+    value: Dense
+}
+
+impl Network {
+    // an untrained network with trunk layers of the given widths, its weights from the seed
+    pub fn new(trunk: &[usize], value_hidden: usize, seed: u64) -> Network {
+        let mut rng = Rng::new(seed);
+        let mut network = Network::empty(trunk, value_hidden);
+
+        for layer in network.layers_mut() {
+            *layer = Dense::random(layer.inputs, layer.outputs, &mut rng);
+        }
+
+        network
+    }
+
+    // the widths of the trunk layers
+    pub fn trunk(&self) -> Vec<usize> {
+        self.trunk.iter().map(|layer| layer.outputs).collect()
+    }
+
+    // policy logits, one for each move index, and the value for the side to move
+    pub fn forward(&self, input: &[f32]) -> (Vec<f32>, f32) {
+        let mut x = input.to_vec();
+
+        for layer in self.trunk.iter() {
+            let mut y = layer.forward(&x);
+            relu(&mut y);
+
+            if y.len() == x.len() {
+                y.iter_mut().zip(x.iter()).for_each(|(y, x)| *y += x);
+            }
+            x = y;
+        }
+
+        let policy = self.policy.forward(&x);
+
+        let mut hidden = self.value_hidden.forward(&x);
+        relu(&mut hidden);
+        let value = libm::tanhf(self.value.forward(&hidden)[0]);
+
+        (policy, value)
+    }
+
+    fn layers(&self) -> impl Iterator<Item = &Dense> {
+        self.trunk.iter().chain([&self.policy, &self.value_hidden, &self.value])
+    }
+
+    fn layers_mut(&mut self) -> impl Iterator<Item = &mut Dense> {
+        self.trunk.iter_mut().chain([&mut self.policy, &mut self.value_hidden, &mut self.value])
+    }
+
+    // the network in the format at the top of the file
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        let mut put = |n: u32| bytes.extend_from_slice(&n.to_le_bytes());
+
+        put(VERSION);
+        put(INPUTS as u32);
+        put(POLICY_SIZE as u32);
+        put(self.trunk.len() as u32);
+        self.trunk.iter().for_each(|layer| put(layer.outputs as u32));
+        put(self.value_hidden.outputs as u32);
+
+        for layer in self.layers() {
+            for x in layer.weights.iter().chain(layer.biases.iter()) {
+                bytes.extend_from_slice(&x.to_le_bytes());
+            }
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Network, LoadError> {
+        if bytes.get(..4) != Some(&MAGIC[..]) { return Err(LoadError::Magic); }
+
+        let mut words = bytes[4..].chunks_exact(4).map(|word| [word[0], word[1], word[2], word[3]]);
+        let mut read = || words.next().ok_or(LoadError::Truncated);
+        let mut number = || read().map(|word| u32::from_le_bytes(word) as usize);
+
+        let version = number()? as u32;
+        if version != VERSION { return Err(LoadError::Version(version)); }
+
+        if number()? != INPUTS || number()? != POLICY_SIZE { return Err(LoadError::Shape); }
+
+        let layers = number()?;
+        // a bad count would otherwise ask for more memory than the file could ever fill
+        if layers > bytes.len() / 4 { return Err(LoadError::Truncated); }
+
+        let trunk = (0..layers).map(|_| number()).collect::<Result<Vec<_>, _>>()?;
+        let value_hidden = number()?;
+
+        if trunk.contains(&0) || value_hidden == 0 { return Err(LoadError::Shape); }
+
+        // the shapes first, to check the size before allocating anything big
+        let mut network = Network::empty(&trunk, value_hidden);
+        let needed: usize = network.layers().map(|layer| layer.outputs * (layer.inputs + 1)).sum();
+        let header = 4 * (6 + layers);
+
+        if bytes.len() < header + needed * 4 { return Err(LoadError::Truncated); }
+        if bytes.len() > header + needed * 4 { return Err(LoadError::TrailingBytes); }
+
+        let mut floats = bytes[header..].chunks_exact(4).map(|word| f32::from_le_bytes([word[0], word[1], word[2], word[3]]));
+
+        for layer in network.layers_mut() {
+            layer.weights = floats.by_ref().take(layer.inputs * layer.outputs).collect();
+            layer.biases = floats.by_ref().take(layer.outputs).collect();
+        }
+
+        Ok(network)
+    }
+
+    // a network of the given shape with nothing in its layers yet
+    fn empty(trunk: &[usize], value_hidden: usize) -> Network {
+        let layer = |inputs: usize, outputs: usize| Dense { inputs, outputs, weights: Vec::new(), biases: Vec::new() };
+        let mut inputs = INPUTS;
+        let mut layers = Vec::new();
+
+        for width in trunk.iter() {
+            layers.push(layer(inputs, *width));
+            inputs = *width;
+        }
+
+        Network {
+            trunk: layers,
+            policy: layer(inputs, POLICY_SIZE),
+            value_hidden: layer(inputs, value_hidden),
+            value: layer(value_hidden, 1)
+        }
+    }
+}
+
+// a network as the tree search's evaluator, with the repetitions the search counted in planes 12
+// and 13
+#[derive(Clone, PartialEq, Debug)]
+pub struct NetworkEvaluator {
+    pub network: Network
+}
+
+impl NetworkEvaluator {
+    pub fn new(network: Network) -> NetworkEvaluator {
+        NetworkEvaluator { network }
+    }
+}
+
+impl Evaluator for NetworkEvaluator {
+    fn evaluate(&mut self, board: &Board, repetitions: u32, moves: &[Move]) -> Evaluation {
+        let (policy, value) = self.network.forward(&encode(board, repetitions));
+        let side = board.get_side_to_move();
+
+        // the priors are the policy's softmax over the legal moves only
+        let logits: Vec<f32> = moves.iter().map(|mv| policy[move_index(*mv, side)]).collect();
+
+        Evaluation { priors: softmax(&logits), value }
+    }
+}
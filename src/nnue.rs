@@ -0,0 +1,584 @@
+// Efficiently updatable neural network evaluation (NNUE)
+//
+// An evaluation for the alpha-beta search, in place of the hand-crafted one. Its first layer, the
+// feature transformer, has an input for every piece on every square with the king of one side on
+// every square (HalfKA), seen from each side in turn with its own pieces first and the board
+// turned around for black:
+//
+//   feature = (king * 12 + piece) * 64 + square
+//
+// where piece is 0-5 for the side's own pawn to king and 6-11 for the other side's. A position
+// has only as many features as pieces, and a move changes two to four of them, so each side's
+// sums of the first layer (its accumulator) are updated from the position before rather than
+// worked out again: the search pushes an accumulator for every move it makes and pops it when it
+// takes the move back. Only a king move changes every feature of its side, which then starts
+// over from the board.
+//
+// The accumulators are int16. Both are clipped to 0-127 and put side by side, the side to move's
+// first, as the int8 input of two hidden layers and an output, with int8 weights and int32
+// biases; a hidden layer's sums are divided by 64 and clipped to 0-127 again, and the output
+// divided by 16 is the score in centipawns for the side to move. Everything is integer, so the
+// SSE2 code on x86_64, the NEON code on aarch64 and the plain loops everywhere else, wasm
+// included, give exactly the same scores.
+//
+// Networks are stored in a simple binary format, all numbers little endian:
+//
+//   "ARNU"                      magic
+//   u32                         version, 1
+//   u32                         features per side, 49152
+//   u32, u32, u32               the widths L1 of an accumulator and L2, L3 of the hidden layers
+//   L1 i16                      feature transformer biases
+//   49152 * L1 i16              feature transformer weights, L1 for each feature in turn
+//   L2 i32, L2 * 2L1 i8         first hidden layer biases, then weights, a row of inputs for
+//                               every output
+//   L3 i32, L3 * L2 i8          second hidden layer, the same way
+//   1 i32, L3 i8                output
+//
+// which loads from a file, or from bytes built into the program with include_bytes!.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+pub use crate::nn::LoadError;
+use crate::rng::Rng;
+use crate::search::{MATE, MAX_PLY};
+use crate::{Board, Color, File, Move, PieceType, Square};
+
+pub const FEATURES: usize = 64 * 12 * 64;
+
+const MAGIC: &[u8; 4] = b"ARNU";
+const VERSION: u32 = 1;
+
+// activations go from 0 to 127, a hidden layer's weights are in 64ths and the output in 16ths of
+// a centipawn
+const ACTIVATION_MAX: i32 = 127;
+const WEIGHT_SHIFT: u32 = 6;
+const OUTPUT_SCALE: i32 = 16;
+
+// scores stay clear of the mate scores
+const SCORE_LIMIT: i32 = MATE - MAX_PLY as i32 - 1;
+
+// the feature of a piece on a square, as the side `perspective` sees it with its king on `king`
+fn feature(perspective: Color, king: Square, color: Color, kind: PieceType, square: Square) -> usize {
+    let orient = |square: Square| match perspective {
+        Color::White => square.index() as usize,
+        Color::Black => square.index() as usize ^ 56
+    };
+    let piece = if color == perspective { 0 } else { 6 } + kind as usize;
+
+    (orient(king) * 12 + piece) * 64 + orient(square)
+}
+
+#[derive(Clone, PartialEq)]
+struct Layer {
+    inputs: usize,
+    outputs: usize,
+    // outputs rows of inputs weights each
+    weights: Vec<i8>,
+    biases: Vec<i32>
+}
+
+impl Layer {
+    fn forward(&self, input: &[i8]) -> Vec<i32> {
+        self.weights.chunks_exact(self.inputs)
+            .zip(self.biases.iter())
+            .map(|(row, bias)| bias + dot(row, input))
+            .collect()
+    }
+}
+
+fn activate(value: i32) -> i8 {
+    value.clamp(0, ACTIVATION_MAX) as i8
+}
+
+// plain loops, for machines without SIMD and for what is left over at the end of a SIMD loop
+
+fn dot_scalar(a: &[i8], b: &[i8]) -> i32 {
+    a.iter().zip(b.iter()).map(|(x, y)| *x as i32 * *y as i32).sum()
+}
+
+fn add_scalar(values: &mut [i16], row: &[i16]) {
+    values.iter_mut().zip(row.iter()).for_each(|(x, y)| *x = x.wrapping_add(*y));
+}
+
+fn subtract_scalar(values: &mut [i16], row: &[i16]) {
+    values.iter_mut().zip(row.iter()).for_each(|(x, y)| *x = x.wrapping_sub(*y));
+}
+
+#[cfg(target_arch = "x86_64")]
+fn dot(a: &[i8], b: &[i8]) -> i32 {
+    use core::arch::x86_64::*;
+
+    let n = a.len().min(b.len());
+    let end = n - n % 16;
+    let mut lanes = [0i32; 4];
+
+    // SAFETY: SSE2 is part of x86_64, and every load is of sixteen bytes inside both slices
+    unsafe {
+        let mut sum = _mm_setzero_si128();
+
+        for i in (0..end).step_by(16) {
+            let x = _mm_loadu_si128(a.as_ptr().add(i) as *const __m128i);
+            let y = _mm_loadu_si128(b.as_ptr().add(i) as *const __m128i);
+
+            // each byte unpacked into the high half of a 16 bit lane and shifted back down, which
+            // keeps its sign, then multiplied and added in pairs into 32 bit lanes
+            let widen = |v: __m128i| (_mm_srai_epi16(_mm_unpacklo_epi8(v, v), 8), _mm_srai_epi16(_mm_unpackhi_epi8(v, v), 8));
+            let ((x_low, x_high), (y_low, y_high)) = (widen(x), widen(y));
+
+            sum = _mm_add_epi32(sum, _mm_madd_epi16(x_low, y_low));
+            sum = _mm_add_epi32(sum, _mm_madd_epi16(x_high, y_high));
+        }
+
+        _mm_storeu_si128(lanes.as_mut_ptr() as *mut __m128i, sum);
+    }
+
+    lanes.iter().sum::<i32>() + dot_scalar(&a[end..n], &b[end..n])
+}
+
+#[cfg(target_arch = "x86_64")]
+fn add(values: &mut [i16], row: &[i16]) {
+    use core::arch::x86_64::*;
+
+    let n = values.len().min(row.len());
+    let end = n - n % 8;
+
+    // SAFETY: SSE2 is part of x86_64, and every load and store is of eight values inside both
+    // slices
+    unsafe {
+        for i in (0..end).step_by(8) {
+            let x = _mm_loadu_si128(values.as_ptr().add(i) as *const __m128i);
+            let y = _mm_loadu_si128(row.as_ptr().add(i) as *const __m128i);
+            _mm_storeu_si128(values.as_mut_ptr().add(i) as *mut __m128i, _mm_add_epi16(x, y));
+        }
+    }
+
+    add_scalar(&mut values[end..n], &row[end..n]);
+}
+
+#[cfg(target_arch = "x86_64")]
+fn subtract(values: &mut [i16], row: &[i16]) {
+    use core::arch::x86_64::*;
+
+    let n = values.len().min(row.len());
+    let end = n - n % 8;
+
+    // SAFETY: as for add
+    unsafe {
+        for i in (0..end).step_by(8) {
+            let x = _mm_loadu_si128(values.as_ptr().add(i) as *const __m128i);
+            let y = _mm_loadu_si128(row.as_ptr().add(i) as *const __m128i);
+            _mm_storeu_si128(values.as_mut_ptr().add(i) as *mut __m128i, _mm_sub_epi16(x, y));
+        }
+    }
+
+    subtract_scalar(&mut values[end..n], &row[end..n]);
+}
+
+#[cfg(target_arch = "aarch64")]
+fn dot(a: &[i8], b: &[i8]) -> i32 {
+    use core::arch::aarch64::*;
+
+    let n = a.len().min(b.len());
+    let end = n - n % 16;
+    let mut sum = 0;
+
+    // SAFETY: NEON is part of aarch64, and every load is of sixteen bytes inside both slices
+    unsafe {
+        let mut lanes = vdupq_n_s32(0);
+
+        for i in (0..end).step_by(16) {
+            let x = vld1q_s8(a.as_ptr().add(i));
+            let y = vld1q_s8(b.as_ptr().add(i));
+
+            // widening multiplies into 16 bit lanes, added in pairs into 32 bit ones
+            lanes = vpadalq_s16(lanes, vmull_s8(vget_low_s8(x), vget_low_s8(y)));
+            lanes = vpadalq_s16(lanes, vmull_high_s8(x, y));
+        }
+
+        sum += vaddvq_s32(lanes);
+    }
+
+    sum + dot_scalar(&a[end..n], &b[end..n])
+}
+
+#[cfg(target_arch = "aarch64")]
+fn add(values: &mut [i16], row: &[i16]) {
+    use core::arch::aarch64::*;
+
+    let n = values.len().min(row.len());
+    let end = n - n % 8;
+
+    // SAFETY: NEON is part of aarch64, and every load and store is of eight values inside both
+    // slices
+    unsafe {
+        for i in (0..end).step_by(8) {
+            let x = vld1q_s16(values.as_ptr().add(i));
+            let y = vld1q_s16(row.as_ptr().add(i));
+            vst1q_s16(values.as_mut_ptr().add(i), vaddq_s16(x, y));
+        }
+    }
+
+    add_scalar(&mut values[end..n], &row[end..n]);
+}
+
+#[cfg(target_arch = "aarch64")]
+fn subtract(values: &mut [i16], row: &[i16]) {
+    use core::arch::aarch64::*;
+
+    let n = values.len().min(row.len());
+    let end = n - n % 8;
+
+    // SAFETY: as for add
+    unsafe {
+        for i in (0..end).step_by(8) {
+            let x = vld1q_s16(values.as_ptr().add(i));
+            let y = vld1q_s16(row.as_ptr().add(i));
+            vst1q_s16(values.as_mut_ptr().add(i), vsubq_s16(x, y));
+        }
+    }
+
+    subtract_scalar(&mut values[end..n], &row[end..n]);
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn dot(a: &[i8], b: &[i8]) -> i32 {
+    dot_scalar(a, b)
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn add(values: &mut [i16], row: &[i16]) {
+    add_scalar(values, row);
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn subtract(values: &mut [i16], row: &[i16]) {
+    subtract_scalar(values, row);
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Network {
+    l1: usize,
+    // the feature transformer, L1 weights for every feature
+    biases: Vec<i16>,
+    weights: Vec<i16>,
+    // the two hidden layers and the output
+    layers: [Layer; 3]
+}
+
+// the weights are far too many to print
+impl fmt::Debug for Network {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (l1, l2, l3) = self.shape();
+        write!(f, "Network {{ l1: {}, l2: {}, l3: {} }}", l1, l2, l3)
+    }
+}
+
+impl Network {
+    // an untrained network of the given widths, its weights from the seed; it plays badly, but
+    // it is somewhere for training to start and something to test with
+    pub fn new(l1: usize, l2: usize, l3: usize, seed: u64) -> Network {
+        let mut rng = Rng::new(seed);
+        let mut random = |limit: i32| (rng.next() % (2 * limit as u64 + 1)) as i32 - limit;
+        let mut network = Network::empty(l1, l2, l3);
+
+        network.biases = (0..l1).map(|_| random(32) as i16).collect();
+        network.weights = (0..FEATURES * l1).map(|_| random(32) as i16).collect();
+
+        for layer in network.layers.iter_mut() {
+            layer.weights = (0..layer.inputs * layer.outputs).map(|_| random(16) as i8).collect();
+            layer.biases = (0..layer.outputs).map(|_| random(256)).collect();
+        }
+
+        network
+    }
+
+    // the widths L1, L2 and L3
+    pub fn shape(&self) -> (usize, usize, usize) {
+        (self.l1, self.layers[0].outputs, self.layers[1].outputs)
+    }
+
+    // the score of the position in centipawns for the side to move, worked out from scratch;
+    // the search keeps Accumulators instead
+    pub fn evaluate(&self, board: &Board) -> i32 {
+        Accumulators::new(self, board).evaluate(board.get_side_to_move())
+    }
+
+    // the network in the format at the top of the file
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (l1, l2, l3) = self.shape();
+        let mut bytes = MAGIC.to_vec();
+
+        for n in [VERSION, FEATURES as u32, l1 as u32, l2 as u32, l3 as u32].iter() {
+            bytes.extend_from_slice(&n.to_le_bytes());
+        }
+        for x in self.biases.iter().chain(self.weights.iter()) {
+            bytes.extend_from_slice(&x.to_le_bytes());
+        }
+        for layer in self.layers.iter() {
+            for x in layer.biases.iter() {
+                bytes.extend_from_slice(&x.to_le_bytes());
+            }
+            bytes.extend(layer.weights.iter().map(|x| *x as u8));
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Network, LoadError> {
+        if bytes.get(..4) != Some(&MAGIC[..]) { return Err(LoadError::Magic); }
+
+        let mut words = bytes[4..].chunks_exact(4).map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]) as usize);
+        let mut number = || words.next().ok_or(LoadError::Truncated);
+
+        let version = number()? as u32;
+        if version != VERSION { return Err(LoadError::Version(version)); }
+
+        let (features, l1, l2, l3) = (number()?, number()?, number()?, number()?);
+        if features != FEATURES || l1 == 0 || l2 == 0 || l3 == 0 { return Err(LoadError::Shape); }
+
+        // the size first, before allocating anything big; widths too big to add up cannot be
+        // in a file that fits in memory either
+        let sizes = [
+            (FEATURES + 1).checked_mul(l1).and_then(|n| n.checked_mul(2)),
+            l1.checked_mul(2).and_then(|n| n.checked_add(4)).and_then(|n| n.checked_mul(l2)),
+            l2.checked_add(4).and_then(|n| n.checked_mul(l3)),
+            l3.checked_add(4)
+        ];
+        let needed = sizes.iter().try_fold(0usize, |total, size| total.checked_add((*size)?)).ok_or(LoadError::Truncated)?;
+        let header = 4 * 6;
+
+        if bytes.len() - header < needed { return Err(LoadError::Truncated); }
+        if bytes.len() - header > needed { return Err(LoadError::TrailingBytes); }
+
+        let mut network = Network::empty(l1, l2, l3);
+        let mut rest = &bytes[header..];
+        let mut take = |n: usize| {
+            let (taken, left) = rest.split_at(n);
+            rest = left;
+            taken
+        };
+
+        let mut shorts = |n: usize| take(2 * n).chunks_exact(2).map(|pair| i16::from_le_bytes([pair[0], pair[1]])).collect::<Vec<_>>();
+        network.biases = shorts(l1);
+        network.weights = shorts(FEATURES * l1);
+
+        for layer in network.layers.iter_mut() {
+            layer.biases = take(4 * layer.outputs).chunks_exact(4).map(|word| i32::from_le_bytes([word[0], word[1], word[2], word[3]])).collect();
+            layer.weights = take(layer.inputs * layer.outputs).iter().map(|x| *x as i8).collect();
+        }
+
+        Ok(network)
+    }
+
+    // the network in a file
+    #[cfg(feature = "std")]
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Network> {
+        let bytes = std::fs::read(path)?;
+        Network::from_bytes(&bytes).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    // a network of the given widths with nothing in its layers yet
+    fn empty(l1: usize, l2: usize, l3: usize) -> Network {
+        let layer = |inputs: usize, outputs: usize| Layer { inputs, outputs, weights: Vec::new(), biases: Vec::new() };
+
+        Network {
+            l1,
+            biases: Vec::new(),
+            weights: Vec::new(),
+            layers: [layer(2 * l1, l2), layer(l2, l3), layer(l3, 1)]
+        }
+    }
+
+    fn row(&self, feature: usize) -> &[i16] {
+        &self.weights[feature * self.l1..(feature + 1) * self.l1]
+    }
+
+    // the score for the side to move from both sides' accumulators, white's first
+    fn output(&self, values: &[i16], side: Color) -> i32 {
+        let (white, black) = values.split_at(self.l1);
+        let (ours, theirs) = match side {
+            Color::White => (white, black),
+            Color::Black => (black, white)
+        };
+
+        let mut input: Vec<i8> = ours.iter().chain(theirs.iter()).map(|x| activate(*x as i32)).collect();
+        for layer in self.layers[..2].iter() {
+            input = layer.forward(&input).into_iter().map(|x| activate(x >> WEIGHT_SHIFT)).collect();
+        }
+
+        (self.layers[2].forward(&input)[0] / OUTPUT_SCALE).clamp(-SCORE_LIMIT, SCORE_LIMIT)
+    }
+}
+
+// a piece that a move puts on or takes off a square
+type Change = (Color, PieceType, Square);
+
+// the accumulators of the positions along the line the search is on, the root's first
+pub struct Accumulators<'a> {
+    network: &'a Network,
+    // white's L1 values then black's for each position, kept when popped so that pushing again
+    // does not allocate
+    stack: Vec<Vec<i16>>,
+    len: usize
+}
+
+impl<'a> Accumulators<'a> {
+    pub fn new(network: &'a Network, board: &Board) -> Accumulators<'a> {
+        let mut accumulators = Accumulators { network, stack: vec![vec![0; 2 * network.l1]], len: 1 };
+        accumulators.reset(board);
+        accumulators
+    }
+
+    // starts again with the board as the root
+    pub fn reset(&mut self, board: &Board) {
+        self.len = 1;
+        self.refresh_all(board);
+    }
+
+    // the accumulators after mv, a legal move in board, which leads to child
+    pub fn push(&mut self, board: &Board, mv: Move, child: &Board) {
+        self.grow();
+
+        let side = board.get_side_to_move();
+        let kind = match board.find_piece(mv.from()) {
+            Some(piece) => piece.kind,
+            None => return self.refresh_all(child)
+        };
+
+        let mut added: [Option<Change>; 2] = [Some((side, mv.promotion().unwrap_or(kind), mv.to())), None];
+        let mut removed: [Option<Change>; 2] = [Some((side, kind, mv.from())), None];
+
+        // worked out from the board rather than the move's flags, so that a move read from text
+        // will do as well
+        let captured = board.find_piece(mv.to()).map(|piece| (piece.color, piece.kind));
+        let files = mv.to().file().index() as i32 - mv.from().file().index() as i32;
+
+        if kind == PieceType::Pawn && files != 0 && captured.is_none() {
+            let square = Square::new(mv.to().file(), mv.from().rank());
+            removed[1] = Some((side.opposite(), PieceType::Pawn, square));
+        } else if kind == PieceType::King && files.abs() == 2 {
+            // the rook goes from the corner to the other side of the king
+            let (from, to) = if files > 0 { (7, 5) } else { (0, 3) };
+            let rook = |file: u8| Square::new(File::new(file).unwrap(), mv.from().rank());
+            removed[1] = Some((side, PieceType::Rook, rook(from)));
+            added[1] = Some((side, PieceType::Rook, rook(to)));
+        } else if let Some((color, kind)) = captured {
+            removed[1] = Some((color, kind, mv.to()));
+        }
+
+        for perspective in [Color::White, Color::Black].iter() {
+            // every feature of a side depends on where its king is
+            if *perspective == side && kind == PieceType::King {
+                self.refresh(child, *perspective);
+                continue;
+            }
+
+            let king = match child.king_square(*perspective) {
+                Some(king) => king,
+                None => continue
+            };
+            let network = self.network;
+            let values = self.values_mut(*perspective);
+
+            for (color, kind, square) in added.iter().flatten() {
+                add(values, network.row(feature(*perspective, king, *color, *kind, *square)));
+            }
+            for (color, kind, square) in removed.iter().flatten() {
+                subtract(values, network.row(feature(*perspective, king, *color, *kind, *square)));
+            }
+        }
+    }
+
+    // the accumulators after the side to move passes, which are the same
+    pub fn push_null(&mut self) {
+        self.grow();
+    }
+
+    // back to the position before the last push
+    pub fn pop(&mut self) {
+        if self.len > 1 { self.len -= 1; }
+    }
+
+    // the score of the current position in centipawns for the side to move
+    pub fn evaluate(&self, side: Color) -> i32 {
+        self.network.output(&self.stack[self.len - 1], side)
+    }
+
+    // the current position's accumulator for one side
+    pub fn values(&self, perspective: Color) -> &[i16] {
+        let l1 = self.network.l1;
+        &self.stack[self.len - 1][perspective as usize * l1..(perspective as usize + 1) * l1]
+    }
+
+    fn values_mut(&mut self, perspective: Color) -> &mut [i16] {
+        let l1 = self.network.l1;
+        &mut self.stack[self.len - 1][perspective as usize * l1..(perspective as usize + 1) * l1]
+    }
+
+    // a new position on top, a copy of the one below
+    fn grow(&mut self) {
+        if self.len == self.stack.len() {
+            self.stack.push(vec![0; 2 * self.network.l1]);
+        }
+
+        let (below, above) = self.stack.split_at_mut(self.len);
+        above[0].copy_from_slice(&below[self.len - 1]);
+        self.len += 1;
+    }
+
+    fn refresh_all(&mut self, board: &Board) {
+        for color in [Color::White, Color::Black].iter() {
+            self.refresh(board, *color);
+        }
+    }
+
+    // one side's accumulator from scratch; without a king it only has the biases
+    fn refresh(&mut self, board: &Board, perspective: Color) {
+        let network = self.network;
+        let values = self.values_mut(perspective);
+        values.copy_from_slice(&network.biases);
+
+        let king = match board.king_square(perspective) {
+            Some(king) => king,
+            None => return
+        };
+
+        for color in [Color::White, Color::Black].iter() {
+            for piece in board.pieces(*color) {
+                add(values, network.row(feature(perspective, king, *color, piece.kind, piece.square)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // whatever the machine, the SIMD code has to give what the plain loops give
+    #[test]
+    fn test_kernels() {
+        let mut rng = Rng::new(9);
+
+        for n in [0, 1, 15, 16, 17, 64, 100, 512].iter() {
+            let a: Vec<i8> = (0..*n).map(|_| rng.next() as i8).collect();
+            let b: Vec<i8> = (0..*n).map(|_| rng.next() as i8).collect();
+            assert_eq!(dot(&a, &b), dot_scalar(&a, &b));
+
+            let row: Vec<i16> = (0..*n).map(|_| rng.next() as i16).collect();
+            let start: Vec<i16> = (0..*n).map(|_| rng.next() as i16).collect();
+
+            let (mut simd, mut scalar) = (start.clone(), start.clone());
+            add(&mut simd, &row);
+            add_scalar(&mut scalar, &row);
+            assert_eq!(simd, scalar);
+
+            subtract(&mut simd, &row);
+            subtract_scalar(&mut scalar, &row);
+            assert_eq!(simd, scalar);
+            assert_eq!(simd, start);
+        }
+    }
+}
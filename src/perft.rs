@@ -0,0 +1,40 @@
+// Move generation counts
+//
+// perft counts the leaf nodes of the full game tree to a fixed depth, the standard way of
+// checking move generation against positions with known counts.
+
+use alloc::vec::Vec;
+
+use crate::{Board, Move};
+
+impl Board {
+    // number of positions reached after exactly depth plies
+    pub fn perft(&self, depth: u32) -> u64 {
+        if depth == 0 { return 1; }
+
+        let moves = self.get_legal_moves();
+
+        // the last ply does not need to be played out, only counted
+        if depth == 1 { return moves.len() as u64; }
+
+        moves.into_iter()
+            .map(|mv| {
+                let mut child = self.clone();
+                child.apply_move(mv);
+                child.perft(depth - 1)
+            })
+            .sum()
+    }
+
+    // perft split by the first move, to narrow down which move a wrong count comes from
+    pub fn divide(&self, depth: u32) -> Vec<(Move, u64)> {
+        self.get_legal_moves()
+            .into_iter()
+            .map(|mv| {
+                let mut child = self.clone();
+                child.apply_move(mv);
+                (mv, child.perft(depth.saturating_sub(1)))
+            })
+            .collect()
+    }
+}
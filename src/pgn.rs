@@ -0,0 +1,313 @@
+// Games and PGN import/export
+//
+// A Game is a starting position plus the moves played from it, along with the PGN tag pairs.
+// Export writes the seven tag roster first, adds SetUp and FEN tags for games that do not start
+// from the initial position, and wraps the move text at 80 columns. Import reads the first game
+// in the text and skips comments, variations and annotation glyphs, keeping only the main line.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::{Board, Color, GameStatus, Move};
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+// tags every PGN game has, in the order they are written
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Game {
+    tags: Vec<(String, String)>,
+    start: String,
+    moves: Vec<Move>
+}
+
+// returned when a PGN game cannot be read
+#[derive(Clone, PartialEq, Debug)]
+pub enum ParsePgnError {
+    // a tag pair that is not of the form [Name "value"]
+    Tag(String),
+    // a move that is not legal or not SAN, with the ply it was played on counting from 1
+    Move { ply: usize, san: String }
+}
+
+impl fmt::Display for ParsePgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParsePgnError::Tag(line) => write!(f, "invalid tag pair: {}", line),
+            ParsePgnError::Move { ply, san } => write!(f, "invalid move {} at ply {}", san, ply)
+        }
+    }
+}
+
+impl Error for ParsePgnError {}
+
+impl Game {
+    // a game from the initial position, with the seven tag roster filled with unknowns
+    pub fn new() -> Game {
+        Game::from_fen(START_FEN)
+    }
+
+    // a game starting from the given position
+    pub fn from_fen(fen: &str) -> Game {
+        let mut game = Game {
+            tags: SEVEN_TAG_ROSTER.iter().map(|name| (name.to_string(), "?".to_string())).collect(),
+            start: fen.trim().to_string(),
+            moves: Vec::new()
+        };
+
+        game.set_tag("Date", "????.??.??");
+        game.set_tag("Result", "*");
+
+        if game.start != START_FEN {
+            game.set_tag("SetUp", "1");
+            game.set_tag("FEN", fen.trim());
+        }
+
+        game
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(n, _)| n == name).map(|(_, value)| value.as_str())
+    }
+
+    // sets a tag, replacing its value if it already exists
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string()))
+        }
+    }
+
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    pub fn start(&self) -> Board {
+        Board::new(self.start.clone())
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    // the position after every move has been played
+    pub fn board(&self) -> Board {
+        let mut board = self.start();
+
+        for mv in self.moves.iter() {
+            board.apply_move(*mv);
+        }
+
+        board
+    }
+
+    // plays a move in the final position, returns true if it was legal
+    pub fn play(&mut self, mv: Move) -> bool {
+        let board = self.board();
+
+        match board.get_legal_moves().into_iter().find(|m| *m == mv) {
+            Some(legal) => {
+                self.moves.push(legal);
+                true
+            },
+            None => false
+        }
+    }
+
+    // takes back the last move
+    pub fn undo(&mut self) -> Option<Move> {
+        self.moves.pop()
+    }
+
+    // the game's result as PGN writes it, from the final position when the game is over on the
+    // board and from the Result tag otherwise, e.g. after a resignation
+    pub fn result(&self) -> String {
+        let board = self.board();
+
+        match board.status() {
+            GameStatus::Checkmate if board.get_side_to_move() == Color::White => "0-1".to_string(),
+            GameStatus::Checkmate => "1-0".to_string(),
+            GameStatus::Ongoing => self.tag("Result").unwrap_or("*").to_string(),
+            _ => "1/2-1/2".to_string()
+        }
+    }
+
+    // the game in PGN
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+        let result = self.result();
+
+        for (name, value) in self.tags.iter() {
+            let value = if name == "Result" { result.as_str() } else { value.as_str() };
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\"")));
+        }
+        pgn.push('\n');
+
+        let mut board = self.start();
+        let mut number = board.get_fen().split_whitespace().nth(5).and_then(|n| n.parse::<u32>().ok()).unwrap_or(1);
+        let mut tokens = Vec::new();
+
+        for (ply, mv) in self.moves.iter().enumerate() {
+            match board.get_side_to_move() {
+                Color::White => tokens.push(format!("{}.", number)),
+                Color::Black if ply == 0 => tokens.push(format!("{}...", number)),
+                Color::Black => {}
+            }
+
+            tokens.push(board.to_san(*mv));
+
+            if board.get_side_to_move() == Color::Black { number += 1; }
+            board.apply_move(*mv);
+        }
+
+        tokens.push(result);
+
+        // tokens are joined with spaces and wrapped before a line would go over 80 columns
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > 80 {
+                pgn.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                pgn.push(' ');
+                line_length += 1;
+            }
+
+            pgn.push_str(&token);
+            line_length += token.len();
+        }
+        pgn.push('\n');
+
+        pgn
+    }
+
+    // reads the first game in a PGN text
+    pub fn from_pgn(pgn: &str) -> Result<Game, ParsePgnError> {
+        let mut tags: Vec<(String, String)> = Vec::new();
+        let mut movetext = String::new();
+
+        for line in pgn.lines() {
+            let line = line.trim();
+
+            // a % at the start of a line escapes it from PGN
+            if line.starts_with('%') { continue; }
+
+            if line.starts_with('[') && movetext.trim().is_empty() {
+                tags.push(parse_tag(line)?);
+            } else if line.starts_with('[') {
+                // the tags of the next game
+                break;
+            } else {
+                movetext.push_str(line);
+                movetext.push('\n');
+            }
+        }
+
+        let fen = tags.iter().find(|(name, _)| name == "FEN").map_or(START_FEN, |(_, value)| value.as_str());
+        let mut game = Game::from_fen(fen);
+        for (name, value) in tags.iter() {
+            game.set_tag(name, value);
+        }
+
+        let mut board = game.start();
+
+        for san in movetext_moves(&movetext) {
+            let mv = board.parse_san(&san).map_err(|_| ParsePgnError::Move { ply: game.moves.len() + 1, san: san.clone() })?;
+
+            board.apply_move(mv);
+            game.moves.push(mv);
+        }
+
+        Ok(game)
+    }
+}
+
+impl Default for Game {
+    fn default() -> Game {
+        Game::new()
+    }
+}
+
+impl FromStr for Game {
+    type Err = ParsePgnError;
+
+    fn from_str(s: &str) -> Result<Game, ParsePgnError> {
+        Game::from_pgn(s)
+    }
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_pgn())
+    }
+}
+
+// a tag pair line like [Event "Casual game"]
+fn parse_tag(line: &str) -> Result<(String, String), ParsePgnError> {
+    let error = || ParsePgnError::Tag(line.to_string());
+
+    let inner = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')).ok_or_else(error)?;
+    let (name, value) = inner.split_once(char::is_whitespace).ok_or_else(error)?;
+    let value = value.trim().strip_prefix('"').and_then(|v| v.strip_suffix('"')).ok_or_else(error)?;
+
+    if name.is_empty() { return Err(error()); }
+
+    Ok((name.to_string(), value.replace("\\\"", "\"").replace("\\\\", "\\")))
+}
+
+// adds the token to the moves unless it is a NAG or the result
+fn finish(token: &mut String, moves: &mut Vec<String>) {
+    // move numbers can be written right against the move, as in 1.e4
+    let san = match token.rfind('.') {
+        Some(dot) => &token[dot + 1..],
+        None => token.as_str()
+    };
+
+    let is_result = matches!(san, "1-0" | "0-1" | "1/2-1/2" | "*");
+    if !san.is_empty() && !is_result && !san.starts_with('$') {
+        moves.push(san.to_string());
+    }
+
+    token.clear();
+}
+
+// the main line moves of the move text, without move numbers, comments, variations, NAGs or the result
+fn movetext_moves(movetext: &str) -> Vec<String> {
+    let mut moves = Vec::new();
+    let mut token = String::new();
+    let mut chars = movetext.chars();
+    let mut variation_depth = 0;
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' => {
+                for c in chars.by_ref() {
+                    if c == '}' { break; }
+                }
+            },
+            ';' => {
+                for c in chars.by_ref() {
+                    if c == '\n' { break; }
+                }
+            },
+            '(' => variation_depth += 1,
+            ')' => variation_depth -= 1,
+            _ if variation_depth > 0 => {},
+            c if c.is_whitespace() => finish(&mut token, &mut moves),
+            c => token.push(c)
+        }
+
+        // comments and variations also end the token before them
+        if matches!(ch, '{' | ';' | '(') && variation_depth <= 1 {
+            finish(&mut token, &mut moves);
+        }
+    }
+    finish(&mut token, &mut moves);
+
+    moves
+}
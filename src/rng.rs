@@ -0,0 +1,79 @@
+// Random numbers
+//
+// splitmix64, the same generator the Zobrist keys come from, with the few distributions the
+// tree search, the network and self-play need built on top. It is seeded, so the same seed
+// always gives the same numbers, on every platform.
+
+use alloc::vec::Vec;
+
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // uniform in (0, 1]
+    pub(crate) fn uniform(&mut self) -> f64 {
+        ((self.next() >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+
+    // standard normal, Box-Muller
+    pub(crate) fn normal(&mut self) -> f64 {
+        let (u, v) = (self.uniform(), self.uniform());
+        libm::sqrt(-2.0 * libm::log(u)) * libm::cos(2.0 * core::f64::consts::PI * v)
+    }
+
+    // gamma distributed with the given shape and scale 1, Marsaglia and Tsang's method; shapes
+    // below 1 are boosted by one and scaled back down
+    pub(crate) fn gamma(&mut self, shape: f64) -> f64 {
+        if shape < 1.0 {
+            let u = self.uniform();
+            return self.gamma(shape + 1.0) * libm::pow(u, 1.0 / shape);
+        }
+
+        let d = shape - 1.0 / 3.0;
+        let c = 1.0 / libm::sqrt(9.0 * d);
+
+        loop {
+            let x = self.normal();
+            let v = 1.0 + c * x;
+            if v <= 0.0 { continue; }
+
+            let v = v * v * v;
+            let u = self.uniform();
+            if u < 1.0 - 0.0331 * x * x * x * x || libm::log(u) < 0.5 * x * x + d * (1.0 - v + libm::log(v)) {
+                return d * v;
+            }
+        }
+    }
+
+    // a sample from a symmetric Dirichlet distribution
+    pub(crate) fn dirichlet(&mut self, alpha: f64, count: usize) -> Vec<f32> {
+        let samples: Vec<f64> = (0..count).map(|_| self.gamma(alpha)).collect();
+        let sum: f64 = samples.iter().sum();
+
+        samples.into_iter().map(|x| if sum > 0.0 { (x / sum) as f32 } else { 1.0 / count as f32 }).collect()
+    }
+
+    // an index picked with a probability that goes with its weight
+    pub(crate) fn pick(&mut self, weights: &[f64]) -> usize {
+        let mut left = self.uniform() * weights.iter().sum::<f64>();
+
+        for (i, weight) in weights.iter().enumerate() {
+            left -= weight;
+            if left <= 0.0 { return i; }
+        }
+
+        weights.len().saturating_sub(1)
+    }
+}
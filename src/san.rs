@@ -0,0 +1,191 @@
+// Standard algebraic notation
+//
+// Moves are written the way PGN expects, with the piece letter, only as much of the origin square
+// as it takes to tell apart two pieces that could both go to the same square, and a + or # when
+// the move gives check or mate. Parsing is more forgiving: the check marks, annotations like !?,
+// castling with zeros and extra disambiguation are all accepted.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+
+use crate::{Board, Move, PieceType};
+
+// returned when a move in SAN cannot be matched with exactly one legal move
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ParseSanError {
+    // not a move in SAN at all
+    Invalid,
+    // no legal move matches
+    Illegal,
+    // more than one legal move matches
+    Ambiguous
+}
+
+impl fmt::Display for ParseSanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseSanError::Invalid => write!(f, "invalid move"),
+            ParseSanError::Illegal => write!(f, "illegal move"),
+            ParseSanError::Ambiguous => write!(f, "ambiguous move")
+        }
+    }
+}
+
+impl Error for ParseSanError {}
+
+fn piece_letter(kind: PieceType) -> Option<char> {
+    match kind {
+        PieceType::Pawn => None,
+        PieceType::Knight => Some('N'),
+        PieceType::Bishop => Some('B'),
+        PieceType::Rook => Some('R'),
+        PieceType::Queen => Some('Q'),
+        PieceType::King => Some('K')
+    }
+}
+
+fn letter_piece(letter: char) -> Option<PieceType> {
+    match letter {
+        'N' => Some(PieceType::Knight),
+        'B' => Some(PieceType::Bishop),
+        'R' => Some(PieceType::Rook),
+        'Q' => Some(PieceType::Queen),
+        'K' => Some(PieceType::King),
+        _ => None
+    }
+}
+
+impl Board {
+    // the move in SAN, e.g. Nbd2, exd6, O-O or e8=Q+; the move has to be legal in this position
+    pub fn to_san(&self, mv: Move) -> String {
+        let legal = self.get_legal_moves();
+        let mv = legal.iter().copied().find(|m| *m == mv).unwrap_or(mv);
+        let kind = self.find_piece(mv.from()).map_or(PieceType::Pawn, |p| p.get_kind());
+
+        let mut san = String::new();
+
+        if mv.is_castle() {
+            san.push_str(if mv.to().file() > mv.from().file() { "O-O" } else { "O-O-O" });
+        } else {
+            if let Some(letter) = piece_letter(kind) {
+                san.push(letter);
+
+                // other pieces of the same kind that could also go to the target square
+                let rivals: Vec<Move> = legal.iter()
+                    .copied()
+                    .filter(|m| m.to() == mv.to() && m.from() != mv.from())
+                    .filter(|m| self.find_piece(m.from()).is_some_and(|p| p.get_kind() == kind))
+                    .collect();
+
+                if !rivals.is_empty() {
+                    let same_file = rivals.iter().any(|m| m.from().file() == mv.from().file());
+                    let same_rank = rivals.iter().any(|m| m.from().rank() == mv.from().rank());
+
+                    if !same_file {
+                        san.push(mv.from().file().to_char());
+                    } else if !same_rank {
+                        san.push(mv.from().rank().to_char());
+                    } else {
+                        san.push_str(&mv.from().to_string());
+                    }
+                }
+            } else if mv.is_capture() {
+                san.push(mv.from().file().to_char());
+            }
+
+            if mv.is_capture() { san.push('x'); }
+            san.push_str(&mv.to().to_string());
+
+            if let Some(promotion) = mv.promotion().and_then(piece_letter) {
+                san.push('=');
+                san.push(promotion);
+            }
+        }
+
+        let mut after = self.clone();
+        after.apply_move(mv);
+
+        if after.in_check() {
+            san.push(if after.get_legal_moves().is_empty() { '#' } else { '+' });
+        }
+
+        san
+    }
+
+    // the legal move written in either UCI or SAN, whichever the text is
+    pub fn parse_move(&self, text: &str) -> Result<Move, ParseSanError> {
+        match text.trim().parse::<Move>() {
+            Ok(mv) => self.get_legal_moves().into_iter().find(|m| *m == mv).ok_or(ParseSanError::Illegal),
+            Err(_) => self.parse_san(text)
+        }
+    }
+
+    // the legal move written in SAN
+    pub fn parse_san(&self, san: &str) -> Result<Move, ParseSanError> {
+        let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+        let legal = self.get_legal_moves();
+
+        if matches!(text, "O-O" | "0-0" | "O-O-O" | "0-0-0") {
+            let kingside = text.len() == 3;
+
+            return legal.into_iter()
+                .find(|m| m.is_castle() && (m.to().file() > m.from().file()) == kingside)
+                .ok_or(ParseSanError::Illegal);
+        }
+
+        if !text.is_ascii() || text.len() < 2 { return Err(ParseSanError::Invalid); }
+
+        let mut chars: Vec<char> = text.chars().collect();
+
+        // promotion, as e8=Q or e8Q
+        let mut promotion = None;
+        if let Some(kind) = letter_piece(chars[chars.len() - 1]).filter(|k| *k != PieceType::King) {
+            if chars.len() > 2 && (chars[chars.len() - 2] == '=' || chars[chars.len() - 2].is_ascii_digit()) {
+                promotion = Some(kind);
+                chars.pop();
+                if chars[chars.len() - 1] == '=' { chars.pop(); }
+            }
+        }
+
+        if chars.len() < 2 { return Err(ParseSanError::Invalid); }
+
+        let to = chars[chars.len() - 2..].iter().collect::<String>().parse().map_err(|_| ParseSanError::Invalid)?;
+        chars.truncate(chars.len() - 2);
+
+        let kind = match chars.first().copied().and_then(letter_piece) {
+            Some(kind) => {
+                chars.remove(0);
+                kind
+            },
+            None => PieceType::Pawn
+        };
+
+        if chars.last() == Some(&'x') { chars.pop(); }
+
+        // whatever is left is the file, rank or square the piece comes from
+        let mut from_file = None;
+        let mut from_rank = None;
+        for ch in chars {
+            match ch {
+                'a'..='h' if from_file.is_none() => from_file = Some(ch),
+                '1'..='8' if from_rank.is_none() => from_rank = Some(ch),
+                _ => return Err(ParseSanError::Invalid)
+            }
+        }
+
+        let candidates: Vec<Move> = legal.into_iter()
+            .filter(|m| m.to() == to && m.promotion() == promotion)
+            .filter(|m| self.find_piece(m.from()).is_some_and(|p| p.get_kind() == kind))
+            .filter(|m| from_file.is_none_or(|f| m.from().file().to_char() == f))
+            .filter(|m| from_rank.is_none_or(|r| m.from().rank().to_char() == r))
+            .collect();
+
+        match candidates.len() {
+            0 => Err(ParseSanError::Illegal),
+            1 => Ok(candidates[0]),
+            _ => Err(ParseSanError::Ambiguous)
+        }
+    }
+}
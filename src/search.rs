@@ -0,0 +1,899 @@
+// Iterative deepening alpha-beta search
+//
+// Searches one ply deeper each iteration, with a capture-only quiescence search at the leaves,
+// and reports each finished iteration through a callback. A search can be cut short by its
+// limits or by the stop callback, which is polled every couple of thousand nodes; an unfinished
+// iteration is thrown away unless no iteration has finished yet.
+//
+// It is a principal variation search: the first move at each node is searched with the full
+// window and the rest with a null window, only searched again properly when one of them turns
+// out better. Each iteration starts with an aspiration window around the previous score, widened
+// when the score falls outside it, and the principal variation is collected in a triangular table.
+//
+// The search is selective away from the principal variation: null-move pruning, reverse
+// futility and futility pruning cut lines that are clearly good or bad enough already, late quiet
+// moves are searched at reduced depth, and checks are extended by a ply.
+//
+// A position that comes back is scored as a draw: straight away when it was already seen since
+// the root, as the side that could have avoided it did not, and the second time for one that was
+// seen before the root, in the game history the search is given, when it would be a threefold
+// repetition.
+//
+// Positions already searched are looked up in a transposition table. With more than one thread
+// the search is a lazy SMP one: helper threads search the same position at staggered depths
+// with nothing shared but the table, and what they store there speeds up the main thread, which
+// alone decides when to stop and what to play. Without threads to spare, on wasm or without std,
+// the search runs on the calling thread only; with one thread it is deterministic.
+//
+// A MultiPV search finds several lines each iteration, each with a different first move: the
+// root is searched once for every line, leaving out the first moves of the lines before it.
+//
+// Positions are evaluated by hand-crafted terms, or by an NNUE network given in the options,
+// whose accumulators each thread keeps up to date along the line it is searching.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use crate::nnue::{Accumulators, Network};
+use crate::time::{TimeControl, TimeManager};
+use crate::tt::{Bound, Entry, TranspositionTable};
+use crate::{Board, Move, PieceType};
+
+// score for being mated right now, mates further away score closer to zero by one per ply
+pub const MATE: i32 = 32000;
+const INFINITY: i32 = MATE + 1;
+
+// deepest ply the search goes to, quiescence included
+pub const MAX_PLY: u32 = 64;
+
+// how often, in nodes, the stop callback and the clock are looked at
+const CHECK_INTERVAL: u64 = 512;
+
+// megabytes of transposition table for a search that is not given one
+pub const DEFAULT_HASH: usize = 16;
+
+// limits on a search, it runs until the first one is reached or it is stopped
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    // milliseconds
+    pub movetime: Option<u64>,
+    // the clock of the side to move, for a TimeManager to decide how long to think
+    pub clock: Option<TimeControl>,
+    // UCI's go infinite: the search may still end by itself, on a mate or at the deepest depth,
+    // but the answer has to wait until it is stopped
+    pub infinite: bool
+}
+
+// how a search runs, rather than how long for
+#[derive(Clone, PartialEq, Debug)]
+pub struct SearchOptions {
+    pub threads: usize,
+    // lines to find, best first, each starting with a different move
+    pub multipv: usize,
+    // the network to evaluate positions with, or None for the hand-crafted evaluation
+    pub nnue: Option<Arc<Network>>
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions { threads: 1, multipv: 1, nnue: None }
+    }
+}
+
+// result of one finished iteration, or of one of its lines in a MultiPV search
+#[derive(Clone, PartialEq, Debug)]
+pub struct SearchInfo {
+    pub depth: u32,
+    // rank of the line, 1 for the best
+    pub multipv: u32,
+    // deepest ply reached, quiescence included
+    pub seldepth: u32,
+    // centipawns from the point of view of the side to move, or a mate score
+    pub score: i32,
+    pub nodes: u64,
+    // milliseconds since the search started
+    pub time: u64,
+    pub pv: Vec<Move>
+}
+
+impl SearchInfo {
+    // nodes per second
+    pub fn nps(&self) -> u64 {
+        self.nodes * 1000 / self.time.max(1)
+    }
+
+    // moves until mate as UCI reports it, positive when the side to move mates
+    pub fn mate_in(&self) -> Option<i32> {
+        mate_in(self.score)
+    }
+}
+
+// the iteration as a UCI info line without the leading "info",
+// e.g. "depth 6 seldepth 11 multipv 1 score cp 35 nodes 81250 nps 70000 time 1160 pv e2e4 e7e5"
+impl fmt::Display for SearchInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "depth {} seldepth {} multipv {} ", self.depth, self.seldepth, self.multipv)?;
+
+        match self.mate_in() {
+            Some(moves) => write!(f, "score mate {}", moves)?,
+            None => write!(f, "score cp {}", self.score)?
+        }
+
+        write!(f, " nodes {} nps {} time {} pv", self.nodes, self.nps(), self.time)?;
+        for mv in self.pv.iter() {
+            write!(f, " {}", mv)?;
+        }
+
+        Ok(())
+    }
+}
+
+// what a search found: the best move and the line it expects, from the last finished iteration
+#[derive(Clone, PartialEq, Debug)]
+pub struct SearchResult {
+    pub best_move: Move,
+    pub depth: u32,
+    pub seldepth: u32,
+    // centipawns from the point of view of the side to move, or a mate score
+    pub score: i32,
+    // nodes and milliseconds for the whole search, the unfinished iteration included
+    pub nodes: u64,
+    pub time: u64,
+    // the principal variation, starting with the best move
+    pub pv: Vec<Move>,
+    // every line of the last finished iteration, best first; just the principal variation
+    // unless more lines were asked for
+    pub lines: Vec<SearchInfo>
+}
+
+impl SearchResult {
+    pub fn nps(&self) -> u64 {
+        self.nodes * 1000 / self.time.max(1)
+    }
+
+    pub fn mate_in(&self) -> Option<i32> {
+        mate_in(self.score)
+    }
+
+    // the reply the search expects, to think about on the opponent's time
+    pub fn ponder(&self) -> Option<Move> {
+        self.pv.get(1).copied()
+    }
+}
+
+// the UCI bestmove line, e.g. "bestmove e2e4 ponder e7e5"
+impl fmt::Display for SearchResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "bestmove {}", self.best_move)?;
+
+        match self.ponder() {
+            Some(mv) => write!(f, " ponder {}", mv),
+            None => Ok(())
+        }
+    }
+}
+
+// moves until mate for a search score, None if it is not a mate score
+pub fn mate_in(score: i32) -> Option<i32> {
+    if score > MATE - MAX_PLY as i32 {
+        Some((MATE - score + 1) / 2)
+    } else if score < -MATE + MAX_PLY as i32 {
+        Some(-(MATE + score) / 2)
+    } else {
+        None
+    }
+}
+
+// milliseconds from a fixed point in time, from the browser's clock on wasm where Instant does not work
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub(crate) fn now() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub(crate) fn now() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+// without std there is no clock, so time limits never run out and times are reported as 0
+#[cfg(not(any(all(feature = "wasm", target_arch = "wasm32"), all(feature = "std", not(target_arch = "wasm32")))))]
+pub(crate) fn now() -> f64 {
+    0.0
+}
+
+// positions for the bench command: the starting position, a few middlegames full of tactics,
+// and endgames where null moves and reductions matter differently
+pub const BENCH_POSITIONS: [&str; 8] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+    "r2q1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R w KQ - 0 9",
+    "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "8/8/4k3/8/2p5/8/B2K4/8 w - - 0 1",
+    "8/5k2/8/4PK2/8/8/8/8 w - - 0 1"
+];
+
+// how far reverse futility pruning and futility pruning look ahead, and the margin per ply
+const REVERSE_FUTILITY_DEPTH: u32 = 6;
+const REVERSE_FUTILITY_MARGIN: i32 = 120;
+const FUTILITY_DEPTH: u32 = 3;
+const FUTILITY_MARGIN: i32 = 150;
+
+// null-move pruning needs some depth left to save anything
+const NULL_MOVE_DEPTH: u32 = 3;
+
+// late move reductions start after this many moves and at this depth
+const LMR_MOVES: usize = 3;
+const LMR_DEPTH: u32 = 3;
+
+// aspiration windows start this far either side of the last score and are used from this depth
+const ASPIRATION_WINDOW: i32 = 25;
+const ASPIRATION_DEPTH: u32 = 4;
+
+// a ply for every ply the search can reach, and one more for the positions at MAX_PLY
+const PLIES: usize = MAX_PLY as usize + 1;
+
+// what the threads of one search share
+struct Shared<'a> {
+    tt: &'a TranspositionTable,
+    // nodes searched by all the threads, each adding its own every so often
+    nodes: AtomicU64,
+    // set once the main thread has finished, which tells the helpers to stop
+    done: AtomicBool,
+    nnue: Option<&'a Network>,
+    // the hashes of the game's positions before the root, oldest first
+    history: &'a [u64]
+}
+
+struct Searcher<'a> {
+    limits: &'a SearchLimits,
+    stop: &'a dyn Fn() -> bool,
+    shared: &'a Shared<'a>,
+    // milliseconds after which the search stops in the middle of an iteration
+    deadline: Option<u64>,
+    // nodes this thread has searched, and how many of them it has added to the shared count
+    nodes: u64,
+    counted: u64,
+    // root moves left out, as the first moves of better lines in a MultiPV search
+    excluded: Vec<Move>,
+    start: f64,
+    stopped: bool,
+    seldepth: u32,
+
+    // the network's accumulators along the line being searched, when there is a network
+    nnue: Option<Accumulators<'a>>,
+
+    // quiet moves that caused a beta cutoff, two per ply, tried right after the captures
+    killers: [[Option<Move>; 2]; MAX_PLY as usize],
+
+    // triangular principal variation table: row ply holds the best line found from that ply,
+    // in pv_table[ply][ply..pv_length[ply]], and is built from the row below it
+    pv_table: [[Option<Move>; PLIES]; PLIES],
+    pv_length: [usize; PLIES],
+
+    // the hashes of the positions on the line being searched, path[ply] for the one at ply
+    path: [u64; PLIES]
+}
+
+impl<'a> Searcher<'a> {
+    fn new(limits: &'a SearchLimits, stop: &'a dyn Fn() -> bool, shared: &'a Shared<'a>, deadline: Option<u64>, start: f64) -> Searcher<'a> {
+        Searcher {
+            limits,
+            stop,
+            shared,
+            deadline,
+            nodes: 0,
+            counted: 0,
+            excluded: Vec::new(),
+            start,
+            stopped: false,
+            seldepth: 0,
+            nnue: None,
+            killers: [[None; 2]; MAX_PLY as usize],
+            pv_table: [[None; PLIES]; PLIES],
+            pv_length: [0; PLIES],
+            path: [0; PLIES]
+        }
+    }
+
+    fn elapsed(&self) -> u64 {
+        (now() - self.start).max(0.0) as u64
+    }
+
+    // adds the nodes searched since last time to the count shared by the threads
+    fn count_nodes(&mut self) {
+        self.shared.nodes.fetch_add(self.nodes - self.counted, Ordering::Relaxed);
+        self.counted = self.nodes;
+    }
+
+    // checks the limits and the stop callback, and remembers once it is time to stop
+    fn should_stop(&mut self) -> bool {
+        if self.stopped { return true; }
+
+        if let Some(nodes) = self.limits.nodes {
+            if self.nodes >= nodes { self.stopped = true; }
+        }
+
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            self.count_nodes();
+
+            let out_of_time = self.deadline.is_some_and(|deadline| self.elapsed() >= deadline);
+            if out_of_time || (self.stop)() { self.stopped = true; }
+        }
+
+        self.stopped
+    }
+
+    // the static evaluation for the side to move
+    fn evaluate(&self, board: &Board) -> i32 {
+        match self.nnue.as_ref() {
+            Some(accumulators) => accumulators.evaluate(board.get_side_to_move()),
+            None => board.evaluate()
+        }
+    }
+
+    // whether the position at ply, with the given hash, is a draw by repetition, see the top of
+    // the file
+    fn repeated(&self, hash: u64, ply: u32) -> bool {
+        self.path[..ply as usize].contains(&hash) || self.shared.history.iter().filter(|h| **h == hash).count() >= 2
+    }
+
+    // keeps the accumulators in step with the moves searched: make before searching the
+    // position after a move, unmake once done with it
+    fn make(&mut self, board: &Board, mv: Move, child: &Board) {
+        if let Some(accumulators) = self.nnue.as_mut() { accumulators.push(board, mv, child); }
+    }
+
+    fn make_null(&mut self) {
+        if let Some(accumulators) = self.nnue.as_mut() { accumulators.push_null(); }
+    }
+
+    fn unmake(&mut self) {
+        if let Some(accumulators) = self.nnue.as_mut() { accumulators.pop(); }
+    }
+
+    // null_allowed is false right after a null move, two in a row would just hand the move back
+    fn negamax(&mut self, board: &Board, depth: u32, ply: u32, mut alpha: i32, beta: i32, null_allowed: bool) -> i32 {
+        let original_alpha = alpha;
+
+        self.pv_length[ply as usize] = ply as usize;
+
+        if self.should_stop() { return 0; }
+
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiescence(board, ply, alpha, beta);
+        }
+
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+
+        let hash = board.hash();
+        self.path[ply as usize] = hash;
+
+        if ply > 0 && (board.halfmove_clock() >= 100 || board.is_insufficient_material() || self.repeated(hash, ply)) {
+            return 0;
+        }
+
+        // the selective parts only apply away from the principal variation, where a null window
+        // is searched and a wrong guess only costs a re-search
+        let pv_node = beta - alpha > 1;
+
+        // a position already searched at least this deep is settled by an exact score, or by a
+        // bound on the right side of the window; not on the principal variation, which would
+        // lose the rest of the line
+        let entry = self.shared.tt.probe(hash);
+
+        if let Some(entry) = entry.filter(|entry| !pv_node && ply > 0 && entry.depth >= depth) {
+            let score = score_from_tt(entry.score, ply);
+
+            match entry.bound {
+                Bound::Exact => return score,
+                Bound::Lower if score >= beta => return score,
+                Bound::Upper if score <= alpha => return score,
+                _ => {}
+            }
+        }
+
+        let moves = board.get_legal_moves();
+
+        let in_check = board.in_check();
+        if moves.is_empty() {
+            return if in_check { -MATE + ply as i32 } else { 0 };
+        }
+
+        let moves = if ply == 0 && !self.excluded.is_empty() {
+            moves.into_iter().filter(|mv| !self.excluded.contains(mv)).collect()
+        } else {
+            moves
+        };
+
+        let eval = if in_check || pv_node { 0 } else { self.evaluate(board) };
+
+        if !pv_node && !in_check && ply > 0 {
+            // reverse futility: far enough above beta that the opponent is not going to catch up
+            if depth <= REVERSE_FUTILITY_DEPTH && eval - REVERSE_FUTILITY_MARGIN * depth as i32 >= beta && beta.abs() < MATE - MAX_PLY as i32 {
+                return eval;
+            }
+
+            // null move: if passing still keeps us above beta, a real move will too; in pawn
+            // endgames and with a single minor piece passing can be better than any move
+            // (zugzwang), so it is not tried there
+            if null_allowed && depth >= NULL_MOVE_DEPTH && eval >= beta && has_pieces(board) {
+                let reduction = 2 + depth / 4;
+                self.make_null();
+                let score = -self.negamax(&null_move(board), depth.saturating_sub(1 + reduction), ply + 1, -beta, -beta + 1, false);
+                self.unmake();
+
+                if self.stopped { return 0; }
+
+                // a null-move search does not prove a mate, so mate scores are not trusted
+                if score >= beta { return if score >= MATE - MAX_PLY as i32 { beta } else { score }; }
+            }
+        }
+
+        let futile = !pv_node && !in_check && depth <= FUTILITY_DEPTH && eval + FUTILITY_MARGIN * depth as i32 <= alpha;
+        let killers = self.killers[ply as usize];
+        let mut best_move = None;
+
+        for (index, mv) in ordered_moves(board, moves, entry.and_then(|entry| entry.best_move), &killers).into_iter().enumerate() {
+            let child = after(board, mv);
+            let gives_check = child.in_check();
+            let quiet = !mv.is_capture() && mv.promotion().is_none();
+
+            // futility: quiet moves cannot lift a position this far below alpha in time
+            if futile && index > 0 && quiet && !gives_check {
+                continue;
+            }
+
+            // a check is looked at one ply deeper, so forcing lines are not cut off at the horizon
+            let extension = if gives_check { 1 } else { 0 };
+            let new_depth = depth - 1 + extension;
+
+            self.make(board, mv, &child);
+            let score = if index == 0 {
+                -self.negamax(&child, new_depth, ply + 1, -beta, -alpha, true)
+            } else {
+                // late quiet moves are searched shallower first
+                let killer = killers.contains(&Some(mv));
+                let reduction = if quiet && !killer && !in_check && !gives_check && index >= LMR_MOVES && depth >= LMR_DEPTH {
+                    late_move_reduction(depth, index).min(new_depth - 1)
+                } else {
+                    0
+                };
+
+                // the later moves only have to be shown to be no better than the best so far,
+                // which a null window does cheaply; one that is better gets searched again at
+                // full depth, and with the full window if it might become the new best line
+                let mut score = -self.negamax(&child, new_depth - reduction, ply + 1, -alpha - 1, -alpha, true);
+
+                if score > alpha && reduction > 0 && !self.stopped {
+                    score = -self.negamax(&child, new_depth, ply + 1, -alpha - 1, -alpha, true);
+                }
+                if score > alpha && score < beta && !self.stopped {
+                    score = -self.negamax(&child, new_depth, ply + 1, -beta, -alpha, true);
+                }
+
+                score
+            };
+            self.unmake();
+
+            // at the root the moves searched so far are still good for a best guess
+            if self.stopped { return if ply == 0 { alpha } else { 0 }; }
+
+            if score > alpha {
+                alpha = score;
+                best_move = Some(mv);
+                self.update_pv(ply as usize, mv);
+
+                if alpha >= beta {
+                    if quiet { self.add_killer(ply, mv); }
+                    break;
+                }
+            }
+        }
+
+        // a root missing some of its moves has a score that is not the position's
+        if ply > 0 || self.excluded.is_empty() {
+            let bound = if alpha >= beta {
+                Bound::Lower
+            } else if alpha > original_alpha {
+                Bound::Exact
+            } else {
+                Bound::Upper
+            };
+            self.shared.tt.store(hash, Entry { best_move, score: score_to_tt(alpha, ply), depth, bound });
+        }
+
+        alpha
+    }
+
+    // the best line from ply is now mv followed by the best line from the next ply
+    fn update_pv(&mut self, ply: usize, mv: Move) {
+        let length = self.pv_length[ply + 1].max(ply + 1);
+
+        self.pv_table[ply][ply] = Some(mv);
+        for i in ply + 1..length {
+            self.pv_table[ply][i] = self.pv_table[ply + 1][i];
+        }
+        self.pv_length[ply] = length;
+    }
+
+    // the principal variation found from the root
+    fn root_pv(&self) -> Vec<Move> {
+        self.pv_table[0][..self.pv_length[0]].iter().flatten().copied().collect()
+    }
+
+    fn add_killer(&mut self, ply: u32, mv: Move) {
+        let killers = &mut self.killers[ply as usize];
+
+        if killers[0] != Some(mv) {
+            killers[1] = killers[0];
+            killers[0] = Some(mv);
+        }
+    }
+
+    // searches captures and promotions until the position is quiet, so the static evaluation is
+    // never taken in the middle of an exchange
+    fn quiescence(&mut self, board: &Board, ply: u32, mut alpha: i32, beta: i32) -> i32 {
+        self.pv_length[ply as usize] = ply as usize;
+
+        if self.should_stop() { return 0; }
+
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+
+        // the tables end here, and evasions from check would otherwise go on past them
+        if ply >= MAX_PLY { return self.evaluate(board); }
+
+        let in_check = board.in_check();
+        let moves = board.get_legal_moves();
+
+        if moves.is_empty() {
+            return if in_check { -MATE + ply as i32 } else { 0 };
+        }
+
+        // the side to move can usually do at least as well as the evaluation by not capturing,
+        // but in check every way out has to be looked at
+        if !in_check {
+            let stand_pat = self.evaluate(board);
+
+            if stand_pat >= beta { return stand_pat; }
+            if stand_pat > alpha { alpha = stand_pat; }
+        }
+
+        let tactical = moves.into_iter().filter(|mv| in_check || mv.is_capture() || mv.promotion().is_some()).collect();
+
+        for mv in ordered_moves(board, tactical, None, &[None, None]) {
+            let child = after(board, mv);
+            self.make(board, mv, &child);
+            let score = -self.quiescence(&child, ply + 1, -beta, -alpha);
+            self.unmake();
+
+            if self.stopped { return 0; }
+
+            if score > alpha {
+                alpha = score;
+                if alpha >= beta { break; }
+            }
+        }
+
+        alpha
+    }
+}
+
+// mate scores count plies from the root, but in the table they have to count from the position
+// itself, which can be reached at any ply
+fn score_to_tt(score: i32, ply: u32) -> i32 {
+    if score > MATE - MAX_PLY as i32 {
+        score + ply as i32
+    } else if score < -MATE + MAX_PLY as i32 {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: u32) -> i32 {
+    if score > MATE - MAX_PLY as i32 {
+        score - ply as i32
+    } else if score < -MATE + MAX_PLY as i32 {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+// plies to take off a late quiet move, growing with both the depth and how late the move comes
+fn late_move_reduction(depth: u32, index: usize) -> u32 {
+    1 + depth.ilog2() * (index as u32).ilog2() / 4
+}
+
+// the position after a generated legal move
+fn after(board: &Board, mv: Move) -> Board {
+    let mut child = board.clone();
+    child.apply_move(mv);
+    child
+}
+
+// the position with the side to move passing, which is never legal but tells how much having
+// the move is worth
+fn null_move(board: &Board) -> Board {
+    let mut child = board.clone();
+    let fields: Vec<&str> = board.fen.split_whitespace().collect();
+    let side = if fields[1] == "w" { "b" } else { "w" };
+
+    child.fen = alloc::format!("{} {} {} - {}", fields[0], side, fields[2], fields[4..].join(" "));
+    child
+}
+
+// whether the side to move has more than a lone minor piece besides pawns and the king; with
+// less than that every move tends to make things worse, and passing would hide it
+fn has_pieces(board: &Board) -> bool {
+    let color = board.get_side_to_move();
+    let pieces: i32 = [PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen].iter()
+        .map(|kind| board.piece_count(color, *kind) as i32 * kind.value())
+        .sum();
+
+    pieces >= PieceType::Rook.value()
+}
+
+// sorts the move from the transposition table first, then captures, most valuable victim and
+// then least valuable attacker first, then the other promotions, then the killer moves, then the
+// other quiet moves
+fn ordered_moves(board: &Board, mut moves: Vec<Move>, tt_move: Option<Move>, killers: &[Option<Move>; 2]) -> Vec<Move> {
+    let order = |mv: &Move| -> i32 {
+        let mut score = 0;
+
+        if tt_move == Some(*mv) {
+            score += 1_000_000;
+        }
+
+        if mv.is_capture() {
+            let victim = board.find_piece(mv.to()).map_or(PieceType::Pawn, |p| p.get_kind());
+            let attacker = board.find_piece(mv.from()).map_or(PieceType::Pawn, |p| p.get_kind());
+
+            score += 100_000 + victim.value() * 10 - attacker.value().min(1000);
+        }
+        if let Some(kind) = mv.promotion() {
+            score += 50_000 + kind.value();
+        }
+        if killers[0] == Some(*mv) {
+            score += 20_000;
+        } else if killers[1] == Some(*mv) {
+            score += 10_000;
+        }
+
+        score
+    };
+
+    moves.sort_by_key(|mv| -order(mv));
+    moves
+}
+
+// searches the position with iterative deepening, calling on_info after every finished
+// iteration, and returns what the last one found; None only when the search was stopped before
+// finding any move, or the side to move has no legal moves. history is the hashes of the
+// positions before it in the game, oldest first, for telling repetitions
+//
+// the search runs on one thread with a transposition table of its own, so the same search always
+// gives the same result
+pub fn search(board: &Board, history: &[u64], limits: &SearchLimits, stop: &dyn Fn() -> bool, on_info: &mut dyn FnMut(&SearchInfo)) -> Option<SearchResult> {
+    search_with(board, history, limits, &TranspositionTable::new(DEFAULT_HASH), &SearchOptions::default(), stop, on_info)
+}
+
+// search with a transposition table kept from one search to the next, on the given number of
+// threads and for the given number of lines; limits and stop only apply to the main thread, and
+// the nodes reported are those of all the threads
+pub fn search_with(board: &Board, history: &[u64], limits: &SearchLimits, tt: &TranspositionTable, options: &SearchOptions, stop: &dyn Fn() -> bool, on_info: &mut dyn FnMut(&SearchInfo)) -> Option<SearchResult> {
+    tt.new_search();
+
+    let shared = Shared {
+        tt,
+        nodes: AtomicU64::new(0),
+        done: AtomicBool::new(false),
+        nnue: options.nnue.as_deref(),
+        history
+    };
+    let start = now();
+
+    let helper = |id: usize| {
+        let stop = || shared.done.load(Ordering::Relaxed);
+        let limits = SearchLimits::default();
+        let mut searcher = Searcher::new(&limits, &stop, &shared, None, start);
+        searcher.nnue = shared.nnue.map(|network| Accumulators::new(network, board));
+
+        help(&mut searcher, board, id);
+        searcher.count_nodes();
+    };
+
+    let lines = with_helpers(options.threads.saturating_sub(1), &helper, || {
+        let mut searcher = Searcher::new(limits, stop, &shared, None, start);
+        searcher.nnue = shared.nnue.map(|network| Accumulators::new(network, board));
+        let lines = iterate(&mut searcher, board, options.multipv.max(1), on_info);
+
+        searcher.count_nodes();
+        shared.done.store(true, Ordering::Relaxed);
+        lines
+    });
+
+    let best = lines.first()?.clone();
+
+    Some(SearchResult {
+        best_move: best.pv[0],
+        depth: best.depth,
+        seldepth: best.seldepth,
+        score: best.score,
+        nodes: shared.nodes.load(Ordering::Relaxed),
+        time: (now() - start).max(0.0) as u64,
+        pv: best.pv,
+        lines
+    })
+}
+
+// runs main on this thread while the helpers run on threads of their own, given their ids from 1
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+fn with_helpers<T>(helpers: usize, helper: &(dyn Fn(usize) + Sync), main: impl FnOnce() -> T) -> T {
+    std::thread::scope(|scope| {
+        for id in 1..=helpers {
+            scope.spawn(move || helper(id));
+        }
+
+        main()
+    })
+}
+
+// no threads to run helpers on, the main thread searches alone
+#[cfg(not(all(feature = "std", not(target_arch = "wasm32"))))]
+fn with_helpers<T>(_helpers: usize, _helper: &(dyn Fn(usize) + Sync), main: impl FnOnce() -> T) -> T {
+    main()
+}
+
+// the main thread's iterative deepening, see search; returns the lines of the last iteration,
+// best first, or none at all
+fn iterate(searcher: &mut Searcher, board: &Board, multipv: usize, on_info: &mut dyn FnMut(&SearchInfo)) -> Vec<SearchInfo> {
+    let limits = searcher.limits;
+    let mut clock = limits.clock.as_ref().map(TimeManager::new);
+    let hard_limit = clock.as_ref().map(|clock| clock.hard_limit());
+
+    searcher.deadline = [limits.movetime, hard_limit].iter().flatten().min().copied();
+
+    let max_depth = limits.depth.unwrap_or(MAX_PLY).clamp(1, MAX_PLY);
+    let mut best: Vec<SearchInfo> = Vec::new();
+
+    for depth in 1..=max_depth {
+        let mut lines: Vec<SearchInfo> = Vec::new();
+
+        // each line leaves out the first moves of the ones before it, and runs out once every
+        // move has a line
+        for rank in 0..multipv {
+            searcher.seldepth = 0;
+
+            let (score, pv) = aspiration(searcher, board, depth, best.get(rank).map(|info| info.score));
+            if pv.is_empty() { break; }
+
+            searcher.excluded.push(pv[0]);
+            lines.push(SearchInfo {
+                depth,
+                multipv: 0,
+                seldepth: searcher.seldepth.max(depth),
+                score,
+                nodes: 0,
+                time: 0,
+                pv
+            });
+
+            if searcher.stopped { break; }
+        }
+
+        searcher.excluded.clear();
+
+        // a stopped iteration's moves are only partly searched, so they are only worth keeping
+        // when there is nothing better
+        if lines.is_empty() || (searcher.stopped && !best.is_empty()) { break; }
+
+        searcher.count_nodes();
+
+        // a line searched later can still come out ahead of one searched before it
+        lines.sort_by_key(|info| -info.score);
+
+        let nodes = searcher.shared.nodes.load(Ordering::Relaxed);
+        let time = searcher.elapsed();
+
+        for (rank, info) in lines.iter_mut().enumerate() {
+            info.multipv = rank as u32 + 1;
+            info.nodes = nodes;
+            info.time = time;
+            on_info(info);
+        }
+
+        // on the clock, a move that is forced is played straight away, and no iteration is
+        // started that there is probably not enough time left to finish
+        let out_of_time = match clock.as_mut() {
+            Some(clock) => {
+                clock.update(lines[0].pv[0], lines[0].score);
+                clock.out_of_time(time) || board.get_legal_moves().len() == 1
+            },
+            None => false
+        };
+
+        let mate = lines.iter().all(|info| info.mate_in().is_some());
+        best = lines;
+
+        // nothing changes once every line is a forced mate
+        if searcher.stopped || mate || out_of_time { break; }
+    }
+
+    best
+}
+
+// a helper thread's iterative deepening, which only fills the transposition table; every other
+// helper starts a ply deeper, so the threads are spread over two depths rather than all
+// searching the same tree at the same time
+fn help(searcher: &mut Searcher, board: &Board, id: usize) {
+    let mut previous = None;
+
+    for depth in (1 + id as u32 % 2)..=MAX_PLY {
+        let (score, pv) = aspiration(searcher, board, depth, previous);
+
+        if searcher.stopped || pv.is_empty() { break; }
+        previous = Some(score);
+    }
+}
+
+// searches the root to the given depth in a window around the previous iteration's score,
+// widening the side the score fell out of until it lands inside, and returns the score and the
+// principal variation
+fn aspiration(searcher: &mut Searcher, board: &Board, depth: u32, previous: Option<i32>) -> (i32, Vec<Move>) {
+    let mut delta = ASPIRATION_WINDOW;
+    let (mut alpha, mut beta) = match previous {
+        Some(score) if depth >= ASPIRATION_DEPTH && mate_in(score).is_none() => (score - delta, score + delta),
+        _ => (-INFINITY, INFINITY)
+    };
+
+    loop {
+        let score = searcher.negamax(board, depth, 0, alpha, beta, true);
+
+        if searcher.stopped { return (score, searcher.root_pv()); }
+
+        // the window grows quickly, and past a few pawns it is as good as unbounded
+        delta *= 2;
+        if score <= alpha && alpha > -INFINITY {
+            alpha = if delta > 1000 { -INFINITY } else { (score - delta).max(-INFINITY) };
+        } else if score >= beta && beta < INFINITY {
+            // the move that failed high is still the best guess, but not its score
+            beta = if delta > 1000 { INFINITY } else { (score + delta).min(INFINITY) };
+        } else {
+            return (score, searcher.root_pv());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a check at the last ply the tables have room for is not looked into any further
+    #[test]
+    fn test_quiescence_stops_at_max_ply() {
+        let tt = TranspositionTable::new(1);
+        let shared = Shared { tt: &tt, nodes: AtomicU64::new(0), done: AtomicBool::new(false), nnue: None, history: &[] };
+        let limits = SearchLimits::default();
+        let mut searcher = Searcher::new(&limits, &|| false, &shared, None, now());
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/4q3/4K3 w - - 0 1").unwrap();
+        assert!(board.in_check());
+
+        let score = searcher.quiescence(&board, MAX_PLY, -INFINITY, INFINITY);
+        assert_eq!(score, board.evaluate());
+        assert_eq!(searcher.nodes, 1);
+    }
+}
@@ -0,0 +1,99 @@
+// Static exchange evaluation
+//
+// Works out the material outcome of the sequence of captures on one square, with both sides
+// capturing with their least valuable attacker first and free to stop whenever going on would
+// lose material. Sliding pieces lined up behind an attacker join in once the pieces in front of
+// them have captured. Pins and checks are not taken into account.
+
+use crate::bitboard;
+use crate::{Board, Color, Move, PieceType, Rank, Square, PIECE_KINDS};
+
+impl Board {
+    // material won by the side to move with the move and the exchange that follows on the target
+    // square, in centipawns; negative when the move loses material
+    pub fn see(&self, mv: Move) -> i32 {
+        let color = self.get_side_to_move();
+        let (from, to) = (mv.from(), mv.to());
+        let mover = match self.find_piece(from) {
+            Some(p) => p.kind,
+            None => return 0
+        };
+
+        // castling never puts anything en prise
+        if mover == PieceType::King && (to.file().index() as i32 - from.file().index() as i32).abs() == 2 {
+            return 0;
+        }
+
+        let last_rank = to.rank() == Rank::FIRST || to.rank() == Rank::EIGHTH;
+
+        let mut occupied = (self.color_bb(Color::White) | self.color_bb(Color::Black)) & !bitboard::bit(from);
+
+        // gain[d] is the balance for the side making the d-th capture if the exchange stopped there
+        let mut gain = [0i32; 32];
+
+        gain[0] = match self.find_piece(to) {
+            Some(p) if p.color != color => p.kind.value(),
+            _ => 0
+        };
+
+        if mover == PieceType::Pawn && self.get_en_passant() == Some(to) && from.file() != to.file() {
+            gain[0] = PieceType::Pawn.value();
+            occupied &= !bitboard::bit(Square::new(to.file(), from.rank()));
+        }
+
+        let mut on_square = mover;
+        if let Some(promotion) = mv.promotion() {
+            gain[0] += promotion.value() - PieceType::Pawn.value();
+            on_square = promotion;
+        }
+
+        let mut side = color.opposite();
+        let mut depth = 0;
+
+        while depth + 1 < gain.len() {
+            let attackers = self.attackers_bb(to, side, occupied);
+
+            // least valuable attacker first
+            let next = PIECE_KINDS.iter().find_map(|kind| {
+                let bb = attackers & self.kind_bb(side, *kind);
+                bitboard::first(bb).map(|sq| (*kind, sq))
+            });
+
+            let (kind, sq) = match next {
+                Some(x) => x,
+                None => break
+            };
+
+            // the king can only take if the square is no longer defended
+            if kind == PieceType::King && self.attackers_bb(to, side.opposite(), occupied & !bitboard::bit(sq)) != 0 {
+                break;
+            }
+
+            depth += 1;
+            gain[depth] = on_square.value() - gain[depth - 1];
+            on_square = kind;
+
+            if kind == PieceType::Pawn && last_rank {
+                gain[depth] += PieceType::Queen.value() - PieceType::Pawn.value();
+                on_square = PieceType::Queen;
+            }
+
+            // removing the attacker uncovers any slider behind it
+            occupied &= !bitboard::bit(sq);
+            side = side.opposite();
+        }
+
+        // each side either stops or takes, whichever is better for it
+        while depth > 0 {
+            gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+            depth -= 1;
+        }
+
+        gain[0]
+    }
+
+    // true if the exchange started by the move wins at least `threshold` centipawns
+    pub fn see_ge(&self, mv: Move, threshold: i32) -> bool {
+        self.see(mv) >= threshold
+    }
+}
@@ -0,0 +1,272 @@
+// Self-play
+//
+// Plays the engine against itself to make training data: every position of every game with
+// what the search thought of it and how the game ended. Either search can play. The tree search
+// records how its visits were spread over the moves, which is what a policy network learns from;
+// the alpha-beta search only has a best move.
+//
+// For the first few plies of a game the move is drawn at random rather than always the best, so
+// that games do not all go the same way: the tree search picks a move with a probability that
+// goes with its visits to the power 1 / temperature, and the alpha-beta search picks among its
+// best few lines with probability softmax(score / (100 * temperature)). After that the best move
+// is played. A side resigns once its score has been below the threshold for a number of its moves
+// in a row, and a game that goes on too long is called a draw, as is a threefold repetition.
+//
+// Records are written as JSON lines for the training scripts, one position per line, e.g.
+//
+//   {"fen":"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1","score":35,"best":"e2e4",
+//    "visits":{"e2e4":312,"d2d4":250,"g1f3":90},"result":1}
+//
+// (on one line), where score is in centipawns from the side to move, visits is empty for the
+// alpha-beta search, and result is the game's result for the side to move, 1 for a win, 0 for a
+// draw and -1 for a loss.
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::mcts::{Evaluator, HeuristicEvaluator, Mcts, MctsConfig};
+use crate::pgn::Game;
+use crate::rng::Rng;
+use crate::search::{self, SearchLimits, SearchOptions, SearchResult, DEFAULT_HASH};
+use crate::tt::TranspositionTable;
+use crate::{Board, Color, GameStatus, Move};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SelfPlayConfig {
+    // the search for every move
+    pub limits: SearchLimits,
+    // how random the first temperature_plies moves are, 0 for not at all
+    pub temperature: f32,
+    pub temperature_plies: u32,
+    // lines the alpha-beta search picks among while the temperature is on
+    pub candidates: usize,
+    // a side resigns once its score has been below -resign_score for resign_moves of its moves
+    // in a row; None to play every game out
+    pub resign_score: Option<i32>,
+    pub resign_moves: u32,
+    // plies after which a game is called a draw
+    pub max_plies: u32,
+    pub seed: u64
+}
+
+impl Default for SelfPlayConfig {
+    fn default() -> Self {
+        SelfPlayConfig {
+            limits: SearchLimits { depth: Some(4), ..SearchLimits::default() },
+            temperature: 1.0,
+            temperature_plies: 16,
+            candidates: 4,
+            resign_score: Some(1000),
+            resign_moves: 4,
+            max_plies: 400,
+            seed: 1
+        }
+    }
+}
+
+// how a game ended
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Ending {
+    // over on the board, checkmate or a draw by rule
+    Status(GameStatus),
+    // the side to move at the time resigned
+    Resigned(Color),
+    // a position seen for the third time
+    Repetition,
+    // max_plies reached
+    Adjudicated
+}
+
+// a position of a self-play game and what the search made of it
+#[derive(Clone, PartialEq, Debug)]
+pub struct Record {
+    pub fen: String,
+    // centipawns from the point of view of the side to move, or a mate score
+    pub score: i32,
+    pub best_move: Move,
+    // the tree search's visits for each move, empty for the alpha-beta search
+    pub visits: Vec<(Move, u32)>,
+    // the game's result from the point of view of the side to move: 1, 0 or -1
+    pub result: i32
+}
+
+impl Record {
+    // the record as one line of JSON, see the top of the file
+    pub fn to_json(&self) -> String {
+        let visits: Vec<String> = self.visits.iter().map(|(mv, n)| format!("\"{}\":{}", mv, n)).collect();
+
+        format!(
+            "{{\"fen\":\"{}\",\"score\":{},\"best\":\"{}\",\"visits\":{{{}}},\"result\":{}}}",
+            self.fen, self.score, self.best_move, visits.join(","), self.result
+        )
+    }
+}
+
+pub struct SelfPlayGame {
+    pub game: Game,
+    pub ending: Ending,
+    // 1 when white won, -1 when black did, 0 for a draw
+    pub result: i32,
+    pub records: Vec<Record>
+}
+
+// what the search made of a position, and the move picked to play
+struct Choice {
+    result: SearchResult,
+    visits: Vec<(Move, u32)>,
+    mv: Move
+}
+
+enum Engine<E: Evaluator> {
+    AlphaBeta(TranspositionTable),
+    TreeSearch(Box<Mcts<E>>)
+}
+
+pub struct SelfPlay<E: Evaluator> {
+    config: SelfPlayConfig,
+    engine: Engine<E>,
+    rng: Rng
+}
+
+impl SelfPlay<HeuristicEvaluator> {
+    // self-play with the alpha-beta search
+    pub fn alpha_beta(config: SelfPlayConfig) -> SelfPlay<HeuristicEvaluator> {
+        SelfPlay { config, engine: Engine::AlphaBeta(TranspositionTable::new(DEFAULT_HASH)), rng: Rng::new(config.seed) }
+    }
+}
+
+impl<E: Evaluator> SelfPlay<E> {
+    // self-play with the tree search guided by the evaluator; the tree search's own settings
+    // are where its root noise goes
+    pub fn tree_search(config: SelfPlayConfig, evaluator: E, mcts: MctsConfig) -> SelfPlay<E> {
+        SelfPlay { config, engine: Engine::TreeSearch(Box::new(Mcts::new(&Board::default(), evaluator, mcts))), rng: Rng::new(config.seed) }
+    }
+
+    // plays a game from the position to the end, checking stop before every move; a stopped
+    // game is left unfinished, with no result and no records
+    pub fn play_game(&mut self, start: &Board, stop: &dyn Fn() -> bool) -> Option<SelfPlayGame> {
+        let mut game = Game::from_fen(&start.get_fen());
+        let mut board = start.clone();
+        let mut hashes = alloc::vec![board.hash()];
+        let mut positions: Vec<(Color, Record)> = Vec::new();
+        // moves in a row each side has been below the resignation score
+        let mut losing = [0u32; 2];
+
+        match &mut self.engine {
+            Engine::AlphaBeta(tt) => tt.clear(),
+            Engine::TreeSearch(mcts) => {
+                mcts.set_position(&board, &[]);
+                mcts.clear();
+            }
+        }
+
+        let ending = loop {
+            let side = board.get_side_to_move();
+
+            match board.status() {
+                GameStatus::Ongoing => {},
+                status => break Ending::Status(status)
+            }
+            if hashes.iter().filter(|hash| **hash == board.hash()).count() >= 3 { break Ending::Repetition; }
+            if positions.len() as u32 >= self.config.max_plies { break Ending::Adjudicated; }
+            if stop() { return None; }
+
+            let random = (positions.len() as u32) < self.config.temperature_plies && self.config.temperature > 0.0;
+            let Choice { result, visits, mv } = self.think(&board, &hashes[..hashes.len() - 1], random, stop)?;
+
+            positions.push((side, Record {
+                fen: board.get_fen(),
+                score: result.score,
+                best_move: result.best_move,
+                visits,
+                result: 0
+            }));
+
+            // the position resigned in is still worth learning from, so it is recorded too
+            if let Some(threshold) = self.config.resign_score {
+                let count = &mut losing[side as usize];
+                *count = if result.score < -threshold { *count + 1 } else { 0 };
+                if *count >= self.config.resign_moves.max(1) { break Ending::Resigned(side); }
+            }
+
+            game.play(mv);
+            board.play_move(mv);
+            hashes.push(board.hash());
+
+            if let Engine::TreeSearch(mcts) = &mut self.engine {
+                mcts.play(mv);
+            }
+        };
+
+        let result = match ending {
+            Ending::Status(GameStatus::Checkmate) | Ending::Resigned(_) => match board.get_side_to_move() {
+                Color::White => -1,
+                Color::Black => 1
+            },
+            _ => 0
+        };
+
+        let (tag, termination) = match ending {
+            Ending::Status(GameStatus::Checkmate) => ("", "normal"),
+            Ending::Status(_) => ("1/2-1/2", "normal"),
+            Ending::Resigned(_) => (if result > 0 { "1-0" } else { "0-1" }, "resignation"),
+            Ending::Repetition => ("1/2-1/2", "threefold repetition"),
+            Ending::Adjudicated => ("1/2-1/2", "adjudication")
+        };
+        if !tag.is_empty() { game.set_tag("Result", tag); }
+        game.set_tag("Termination", termination);
+
+        let records = positions.into_iter().map(|(side, mut record)| {
+            record.result = if side == Color::White { result } else { -result };
+            record
+        }).collect();
+
+        Some(SelfPlayGame { game, ending, result, records })
+    }
+
+    // searches the position and picks the move to play, at random while the temperature is on;
+    // None when stopped before there was any move. history is the hashes of the game's positions
+    // before it, which the tree search keeps track of itself
+    fn think(&mut self, board: &Board, history: &[u64], random: bool, stop: &dyn Fn() -> bool) -> Option<Choice> {
+        let config = self.config;
+
+        match &mut self.engine {
+            Engine::AlphaBeta(tt) => {
+                let options = SearchOptions { multipv: if random { config.candidates.max(1) } else { 1 }, ..SearchOptions::default() };
+                let result = search::search_with(board, history, &config.limits, tt, &options, stop, &mut |_| {})?;
+
+                // a mate counts as ten pawns, more would leave nothing for the other lines
+                let scores: Vec<i32> = result.lines.iter().map(|line| line.score.clamp(-1000, 1000)).collect();
+
+                // measured from the best line, so the best weighs 1 and a low temperature cannot overflow
+                let best = scores.iter().copied().max().unwrap_or(0);
+                let weights: Vec<f64> = scores.iter().map(|score| {
+                    libm::exp((score - best) as f64 / (100.0 * config.temperature as f64))
+                }).collect();
+
+                let mv = if random { result.lines[self.rng.pick(&weights)].pv[0] } else { result.best_move };
+                Some(Choice { result, visits: Vec::new(), mv })
+            },
+            Engine::TreeSearch(mcts) => {
+                let result = mcts.search(&config.limits, stop, &mut |_| {})?;
+                let visits = mcts.root_visits();
+
+                // the visits to the power 1 / temperature, scaled down first to stay in range
+                let most = visits.iter().map(|(_, n)| *n).max().unwrap_or(1).max(1) as f64;
+                let weights: Vec<f64> = visits.iter().map(|(_, n)| libm::pow(*n as f64 / most, 1.0 / config.temperature as f64)).collect();
+
+                let mv = if random && weights.iter().sum::<f64>() > 0.0 { visits[self.rng.pick(&weights)].0 } else { result.best_move };
+                Some(Choice { result, visits, mv })
+            }
+        }
+    }
+}
+
+impl SelfPlayGame {
+    // the records as JSON lines, see the top of the file
+    pub fn to_jsonl(&self) -> String {
+        self.records.iter().map(|record| record.to_json() + "\n").collect::<Vec<_>>().concat()
+    }
+}
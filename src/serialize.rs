@@ -0,0 +1,114 @@
+// Serde support, behind the serde feature
+//
+// Everything serializes to the notation people already use, so the formats stay stable and
+// readable in JSON:
+//
+//   Board     FEN string              "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+//   Square    algebraic               "e4" (File and Rank as "e" and "4")
+//   Move      UCI                     "e7e8q"
+//   Piece     struct                  {"kind": "Pawn", "color": "White", "square": "e4"}
+//   PieceType, Color, GameStatus      variant name, e.g. "Knight"
+//   Game      struct                  {"tags": [["Event", "?"], ...], "start": FEN, "moves": ["e2e4", ...]}
+//
+// Boards and games are checked when they are read back: the FEN has to be valid and every move
+// of a game legal, and the moves get back the flags UCI does not carry.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Display;
+use core::str::FromStr;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::pgn::Game;
+use crate::{Board, File, Move, Rank, Square};
+
+fn serialize_display<T: Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+fn deserialize_from_str<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>
+{
+    let text = String::deserialize(deserializer)?;
+    text.parse().map_err(D::Error::custom)
+}
+
+// types written as a string in their usual notation
+macro_rules! serialize_as_string {
+    ($($kind:ty),*) => {
+        $(
+            impl Serialize for $kind {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serialize_display(self, serializer)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $kind {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    deserialize_from_str(deserializer)
+                }
+            }
+        )*
+    };
+}
+
+serialize_as_string!(File, Rank, Square, Move);
+
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.fen)
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Board, D::Error> {
+        let fen = String::deserialize(deserializer)?;
+        Board::from_fen(&fen).map_err(D::Error::custom)
+    }
+}
+
+#[derive(Serialize)]
+struct GameRef<'a> {
+    tags: &'a [(String, String)],
+    start: &'a str,
+    moves: &'a [Move]
+}
+
+#[derive(Deserialize)]
+struct GameData {
+    tags: Vec<(String, String)>,
+    start: String,
+    moves: Vec<Move>
+}
+
+impl Serialize for Game {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GameRef { tags: self.tags(), start: &self.start().get_fen(), moves: self.moves() }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Game, D::Error> {
+        let data = GameData::deserialize(deserializer)?;
+        let start = Board::from_fen(&data.start).map_err(D::Error::custom)?;
+
+        let mut game = Game::from_fen(&start.get_fen());
+        for (name, value) in data.tags.iter() {
+            game.set_tag(name, value);
+        }
+
+        for (ply, mv) in data.moves.into_iter().enumerate() {
+            if !game.play(mv) {
+                return Err(D::Error::custom(format!("illegal move {} at ply {}", mv, ply + 1)));
+            }
+        }
+
+        Ok(game)
+    }
+}
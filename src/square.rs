@@ -0,0 +1,188 @@
+// Board coordinates
+//
+// files and ranks are stored 0-based (file a and rank 1 are 0), and squares are indexed from
+// a1 (0) to h8 (63) going along the ranks, the same order the bitboards use
+
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct File(u8);
+
+impl File {
+    pub const A: File = File(0);
+    pub const B: File = File(1);
+    pub const C: File = File(2);
+    pub const D: File = File(3);
+    pub const E: File = File(4);
+    pub const F: File = File(5);
+    pub const G: File = File(6);
+    pub const H: File = File(7);
+
+    // file from its 0-based index, None if it is off the board
+    pub fn new(index: u8) -> Option<File> {
+        if index < 8 { Some(File(index)) } else { None }
+    }
+
+    pub fn index(&self) -> u8 {
+        self.0
+    }
+
+    // the file `n` files to the right (or left, when negative), if it is still on the board
+    pub fn offset(&self, n: i32) -> Option<File> {
+        let index = self.0 as i32 + n;
+        if (0..8).contains(&index) { Some(File(index as u8)) } else { None }
+    }
+
+    // all files from a to h
+    pub fn all() -> impl DoubleEndedIterator<Item = File> {
+        (0..8).map(File)
+    }
+
+    pub fn to_char(&self) -> char {
+        (b'a' + self.0) as char
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct Rank(u8);
+
+impl Rank {
+    pub const FIRST: Rank = Rank(0);
+    pub const SECOND: Rank = Rank(1);
+    pub const THIRD: Rank = Rank(2);
+    pub const FOURTH: Rank = Rank(3);
+    pub const FIFTH: Rank = Rank(4);
+    pub const SIXTH: Rank = Rank(5);
+    pub const SEVENTH: Rank = Rank(6);
+    pub const EIGHTH: Rank = Rank(7);
+
+    // rank from its 0-based index, None if it is off the board
+    pub fn new(index: u8) -> Option<Rank> {
+        if index < 8 { Some(Rank(index)) } else { None }
+    }
+
+    pub fn index(&self) -> u8 {
+        self.0
+    }
+
+    // the rank `n` ranks up the board (or down, when negative), if it is still on the board
+    pub fn offset(&self, n: i32) -> Option<Rank> {
+        let index = self.0 as i32 + n;
+        if (0..8).contains(&index) { Some(Rank(index as u8)) } else { None }
+    }
+
+    // all ranks from 1 to 8
+    pub fn all() -> impl DoubleEndedIterator<Item = Rank> {
+        (0..8).map(Rank)
+    }
+
+    pub fn to_char(&self) -> char {
+        (b'1' + self.0) as char
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct Square(u8);
+
+impl Square {
+    pub fn new(file: File, rank: Rank) -> Square {
+        Square(rank.0 * 8 + file.0)
+    }
+
+    // square from its index (a1 = 0, h8 = 63), None if it is off the board
+    pub fn from_index(index: u8) -> Option<Square> {
+        if index < 64 { Some(Square(index)) } else { None }
+    }
+
+    pub fn index(&self) -> u8 {
+        self.0
+    }
+
+    pub fn file(&self) -> File {
+        File(self.0 % 8)
+    }
+
+    pub fn rank(&self) -> Rank {
+        Rank(self.0 / 8)
+    }
+
+    // the square `files` to the right and `ranks` up the board, if it is still on the board
+    pub fn offset(&self, files: i32, ranks: i32) -> Option<Square> {
+        Some(Square::new(self.file().offset(files)?, self.rank().offset(ranks)?))
+    }
+
+    // all squares from a1 to h8, rank by rank
+    pub fn all() -> impl DoubleEndedIterator<Item = Square> {
+        (0..64).map(Square)
+    }
+
+    // index for squares built from a bitboard, which are always on the board
+    pub(crate) fn from_bit_index(index: u32) -> Square {
+        debug_assert!(index < 64);
+        Square(index as u8)
+    }
+}
+
+// returned when a string is not a square, file or rank in algebraic notation
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ParseSquareError;
+
+impl fmt::Display for ParseSquareError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid square")
+    }
+}
+
+impl Error for ParseSquareError {}
+
+impl fmt::Display for File {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.file(), self.rank())
+    }
+}
+
+impl FromStr for File {
+    type Err = ParseSquareError;
+
+    fn from_str(s: &str) -> Result<File, ParseSquareError> {
+        match s.as_bytes() {
+            [c @ b'a'..=b'h'] => Ok(File(c - b'a')),
+            _ => Err(ParseSquareError)
+        }
+    }
+}
+
+impl FromStr for Rank {
+    type Err = ParseSquareError;
+
+    fn from_str(s: &str) -> Result<Rank, ParseSquareError> {
+        match s.as_bytes() {
+            [c @ b'1'..=b'8'] => Ok(Rank(c - b'1')),
+            _ => Err(ParseSquareError)
+        }
+    }
+}
+
+impl FromStr for Square {
+    type Err = ParseSquareError;
+
+    fn from_str(s: &str) -> Result<Square, ParseSquareError> {
+        if s.len() != 2 || !s.is_ascii() { return Err(ParseSquareError); }
+
+        Ok(Square::new(s[0..1].parse()?, s[1..2].parse()?))
+    }
+}
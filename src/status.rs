@@ -0,0 +1,60 @@
+// Game state checks for the position on the board
+//
+// Repetitions need the moves that led to the position, which a Board does not keep, so they are
+// left to whoever holds the game history (see wasm::BoardSingleton::status).
+
+use alloc::vec::Vec;
+
+use crate::{Board, Color, PieceType};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameStatus {
+    Ongoing,
+    Checkmate,
+    Stalemate,
+    FiftyMoveRule,
+    InsufficientMaterial,
+    Repetition
+}
+
+impl Board {
+    // whether the game is over in the current position, and why
+    pub fn status(&self) -> GameStatus {
+        if self.get_legal_moves().is_empty() {
+            return if self.in_check() { GameStatus::Checkmate } else { GameStatus::Stalemate };
+        }
+
+        if self.halfmove_clock() >= 100 {
+            GameStatus::FiftyMoveRule
+        } else if self.is_insufficient_material() {
+            GameStatus::InsufficientMaterial
+        } else {
+            GameStatus::Ongoing
+        }
+    }
+
+    // moves since the last capture or pawn move, in plies
+    pub(crate) fn halfmove_clock(&self) -> u32 {
+        self.fen.split_whitespace().nth(4).and_then(|x| x.parse::<u32>().ok()).unwrap_or(0)
+    }
+
+    // true if neither side can possibly mate: bare kings, a single minor piece, or only bishops
+    // that all stand on squares of the same color
+    pub fn is_insufficient_material(&self) -> bool {
+        let colors = [Color::White, Color::Black];
+        let heavy = [PieceType::Pawn, PieceType::Rook, PieceType::Queen];
+
+        if colors.iter().any(|c| heavy.iter().any(|k| self.piece_count(*c, *k) > 0)) {
+            return false;
+        }
+
+        let knights: usize = colors.iter().map(|c| self.piece_count(*c, PieceType::Knight)).sum();
+        let bishops: Vec<bool> = colors.iter()
+            .flat_map(|c| self.pieces_of(*c)[PieceType::Bishop as usize].iter())
+            .map(|p| (p.square.file().index() + p.square.rank().index()) % 2 == 0)
+            .collect();
+
+        knights + bishops.len() <= 1 || (knights == 0 && bishops.iter().all(|dark| *dark == bishops[0]))
+    }
+}
@@ -0,0 +1,289 @@
+// SVG board diagrams
+//
+// Pieces are drawn from path data in the document itself, so they look the same whatever fonts
+// the viewer has: each kind on the board is drawn once in <defs> and placed on its squares with
+// <use>. Squares are 45 units wide, and the picture scales to whatever size it is shown at.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::{Board, Color, File, Move, PieceType, Rank, Square};
+
+const SQUARE: f64 = 45.0;
+
+// annotation colors, the same four lichess offers
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Brush {
+    Green,
+    Red,
+    Blue,
+    Yellow
+}
+
+impl Brush {
+    fn color(&self) -> &'static str {
+        match self {
+            Brush::Green => "#15781b",
+            Brush::Red => "#882020",
+            Brush::Blue => "#003088",
+            Brush::Yellow => "#e68f00"
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Arrow {
+    pub from: Square,
+    pub to: Square,
+    pub brush: Brush
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Circle {
+    pub square: Square,
+    pub brush: Brush
+}
+
+// square and highlight colors as css colors
+#[derive(Clone, PartialEq, Debug)]
+pub struct Theme {
+    pub light: String,
+    pub dark: String,
+    pub last_move: String,
+    pub check: String,
+    pub coordinates_light: String,
+    pub coordinates_dark: String
+}
+
+impl Theme {
+    pub fn brown() -> Theme {
+        Theme {
+            light: "#f0d9b5".to_string(),
+            dark: "#b58863".to_string(),
+            last_move: "rgba(155, 199, 0, 0.41)".to_string(),
+            check: "rgba(255, 0, 0, 0.6)".to_string(),
+            coordinates_light: "#b58863".to_string(),
+            coordinates_dark: "#f0d9b5".to_string()
+        }
+    }
+
+    pub fn blue() -> Theme {
+        Theme {
+            light: "#dee3e6".to_string(),
+            dark: "#8ca2ad".to_string(),
+            last_move: "rgba(155, 199, 0, 0.41)".to_string(),
+            check: "rgba(255, 0, 0, 0.6)".to_string(),
+            coordinates_light: "#8ca2ad".to_string(),
+            coordinates_dark: "#dee3e6".to_string()
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::brown()
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct RenderOptions {
+    // side shown at the bottom of the board
+    pub orientation: Color,
+    // file letters along the bottom and rank numbers along the side, inside the edge squares
+    pub coordinates: bool,
+    pub last_move: Option<Move>,
+    // highlight the king of the side to move when it is in check
+    pub check: bool,
+    pub arrows: Vec<Arrow>,
+    pub circles: Vec<Circle>,
+    pub theme: Theme
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions {
+            orientation: Color::White,
+            coordinates: true,
+            last_move: None,
+            check: true,
+            arrows: Vec::new(),
+            circles: Vec::new(),
+            theme: Theme::default()
+        }
+    }
+}
+
+// top left corner of the square in the picture
+fn corner(square: Square, orientation: Color) -> (f64, f64) {
+    let (file, rank) = (square.file().index() as f64, square.rank().index() as f64);
+
+    match orientation {
+        Color::White => (file * SQUARE, (7.0 - rank) * SQUARE),
+        Color::Black => ((7.0 - file) * SQUARE, rank * SQUARE)
+    }
+}
+
+fn center(square: Square, orientation: Color) -> (f64, f64) {
+    let (x, y) = corner(square, orientation);
+    (x + SQUARE / 2.0, y + SQUARE / 2.0)
+}
+
+// rounds to two decimals so the arrow coordinates stay short
+fn round(value: f64) -> f64 {
+    libm::round(value * 100.0) / 100.0
+}
+
+// the pieces, 45 units square like the board's squares; Colin M.L. Burnett's set from Wikimedia
+// Commons, used under the BSD license in LICENSE_CBURNETT (it is also offered under the GFDL and
+// the GPL)
+fn piece_drawing(color: Color, kind: PieceType) -> &'static str {
+    match (color, kind) {
+        (Color::White, PieceType::Pawn) => r##"<g id="white-pawn"><path d="M22.5 9c-2.21 0-4 1.79-4 4 0 .89.29 1.71.78 2.38C17.33 16.5 16 18.59 16 21c0 2.03.94 3.84 2.41 5.03-3 1.06-7.41 5.55-7.41 13.47h23c0-7.92-4.41-12.41-7.41-13.47 1.47-1.19 2.41-3 2.41-5.03 0-2.41-1.33-4.5-3.28-5.62.49-.67.78-1.49.78-2.38 0-2.21-1.79-4-4-4z" fill="#fff" stroke="#000" stroke-width="1.5" stroke-linecap="round"/></g>"##,
+        (Color::White, PieceType::Knight) => r##"<g id="white-knight" fill="none" fill-rule="evenodd" stroke="#000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><path d="M22 10c10.5 1 16.5 8 16 29H15c0-9 10-6.5 8-21" fill="#fff"/><path d="M24 18c.38 2.91-5.55 7.37-8 9-3 2-2.82 4.34-5 4-1.042-.94 1.41-3.04 0-3-1 0 .19 1.23-1 2-1 0-4.003 1-4-4 0-2 6-12 6-12s1.89-1.9 2-3.5c-.73-.994-.5-2-.5-3 1-1 3 2.5 3 2.5h2s.78-1.992 2.5-3c1 0 1 3 1 3" fill="#fff"/><path d="M9.5 25.5a.5.5 0 1 1-1 0 .5.5 0 1 1 1 0zm5.433-9.75a.5 1.5 30 1 1-.866-.5.5 1.5 30 1 1 .866.5z" fill="#000"/></g>"##,
+        (Color::White, PieceType::Bishop) => r##"<g id="white-bishop" fill="none" fill-rule="evenodd" stroke="#000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><g fill="#fff" stroke-linecap="butt"><path d="M9 36c3.39-.97 10.11.43 13.5-2 3.39 2.43 10.11 1.03 13.5 2 0 0 1.65.54 3 2-.68.97-1.65.99-3 .5-3.39-.97-10.11.46-13.5-1-3.39 1.46-10.11.03-13.5 1-1.354.49-2.323.47-3-.5 1.354-1.94 3-2 3-2zM15 32c2.5 2.5 12.5 2.5 15 0 .5-1.5 0-2 0-2 0-2.5-2.5-4-2.5-4 5.5-1.5 6-11.5-5-15.5-11 4-10.5 14-5 15.5 0 0-2.5 1.5-2.5 4 0 0-.5.5 0 2zM25 8a2.5 2.5 0 1 1-5 0 2.5 2.5 0 1 1 5 0z"/></g><path d="M17.5 26h10M15 30h15m-7.5-14.5v5M20 18h5" stroke-linejoin="miter"/></g>"##,
+        (Color::White, PieceType::Rook) => r##"<g id="white-rook" fill="#fff" fill-rule="evenodd" stroke="#000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><path d="M9 39h27v-3H9v3zM12 36v-4h21v4H12zM11 14V9h4v2h5V9h5v2h5V9h4v5" stroke-linecap="butt"/><path d="M34 14l-3 3H14l-3-3"/><path d="M31 17v12.5H14V17" stroke-linecap="butt" stroke-linejoin="miter"/><path d="M31 29.5l1.5 2.5h-20l1.5-2.5"/><path d="M11 14h23" fill="none" stroke-linejoin="miter"/></g>"##,
+        (Color::White, PieceType::Queen) => r##"<g id="white-queen" fill="#fff" fill-rule="evenodd" stroke="#000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><path d="M8 12a2 2 0 1 1-4 0 2 2 0 1 1 4 0zM24.5 7.5a2 2 0 1 1-4 0 2 2 0 1 1 4 0zM41 12a2 2 0 1 1-4 0 2 2 0 1 1 4 0zM16 8.5a2 2 0 1 1-4 0 2 2 0 1 1 4 0zM33 9a2 2 0 1 1-4 0 2 2 0 1 1 4 0z"/><path d="M9 26c8.5-1.5 21-1.5 27 0l2-12-7 11V11l-5.5 13.5-3-15-3 15-5.5-14V25L7 14l2 12zM9 26c0 2 1.5 2 2.5 4 1 1.5 1 1 .5 3.5-1.5 1-1.5 2.5-1.5 2.5-1.5 1.5.5 2.5.5 2.5 6.5 1 16.5 1 23 0 0 0 1.5-1 0-2.5 0 0 .5-1.5-1-2.5-.5-2.5-.5-2 .5-3.5 1-2 2.5-2 2.5-4-8.5-1.5-18.5-1.5-27 0z" stroke-linecap="butt"/><path d="M11.5 30c3.5-1 18.5-1 22 0M12 33.5c6-1 15-1 21 0" fill="none"/></g>"##,
+        (Color::White, PieceType::King) => r##"<g id="white-king" fill="none" fill-rule="evenodd" stroke="#000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><path d="M22.5 11.63V6M20 8h5" stroke-linejoin="miter"/><path d="M22.5 25s4.5-7.5 3-10.5c0 0-1-2.5-3-2.5s-3 2.5-3 2.5c-1.5 3 3 10.5 3 10.5" fill="#fff" stroke-linecap="butt" stroke-linejoin="miter"/><path d="M11.5 37c5.5 3.5 15.5 3.5 21 0v-7s9-4.5 6-10.5c-4-6.5-13.5-3.5-16 4V27v-3.5c-3.5-7.5-13-10.5-16-4-3 6 5 10 5 10V37z" fill="#fff"/><path d="M11.5 30c5.5-3 15.5-3 21 0m-21 3.5c5.5-3 15.5-3 21 0m-21 3.5c5.5-3 15.5-3 21 0"/></g>"##,
+        (Color::Black, PieceType::Pawn) => r##"<g id="black-pawn"><path d="M22.5 9c-2.21 0-4 1.79-4 4 0 .89.29 1.71.78 2.38C17.33 16.5 16 18.59 16 21c0 2.03.94 3.84 2.41 5.03-3 1.06-7.41 5.55-7.41 13.47h23c0-7.92-4.41-12.41-7.41-13.47 1.47-1.19 2.41-3 2.41-5.03 0-2.41-1.33-4.5-3.28-5.62.49-.67.78-1.49.78-2.38 0-2.21-1.79-4-4-4z" fill="#000" stroke="#000" stroke-width="1.5" stroke-linecap="round"/></g>"##,
+        (Color::Black, PieceType::Knight) => r##"<g id="black-knight" fill="none" fill-rule="evenodd" stroke="#000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><path d="M22 10c10.5 1 16.5 8 16 29H15c0-9 10-6.5 8-21" fill="#000"/><path d="M24 18c.38 2.91-5.55 7.37-8 9-3 2-2.82 4.34-5 4-1.042-.94 1.41-3.04 0-3-1 0 .19 1.23-1 2-1 0-4.003 1-4-4 0-2 6-12 6-12s1.89-1.9 2-3.5c-.73-.994-.5-2-.5-3 1-1 3 2.5 3 2.5h2s.78-1.992 2.5-3c1 0 1 3 1 3" fill="#000"/><path d="M9.5 25.5a.5.5 0 1 1-1 0 .5.5 0 1 1 1 0zm5.433-9.75a.5 1.5 30 1 1-.866-.5.5 1.5 30 1 1 .866.5z" fill="#ececec" stroke="#ececec"/><path d="M24.55 10.4l-.45 1.45.5.15c3.15 1 5.65 2.49 7.9 6.75S35.75 29.06 35.25 39l-.05.5h2.25l.05-.5c.5-10.06-.88-16.85-3.25-21.34-2.37-4.49-5.79-6.64-9.19-7.16l-.51-.1z" fill="#ececec" stroke="none"/></g>"##,
+        (Color::Black, PieceType::Bishop) => r##"<g id="black-bishop" fill="none" fill-rule="evenodd" stroke="#000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><path d="M9 36c3.39-.97 10.11.43 13.5-2 3.39 2.43 10.11 1.03 13.5 2 0 0 1.65.54 3 2-.68.97-1.65.99-3 .5-3.39-.97-10.11.46-13.5-1-3.39 1.46-10.11.03-13.5 1-1.354.49-2.323.47-3-.5 1.354-1.94 3-2 3-2zm6-4c2.5 2.5 12.5 2.5 15 0 .5-1.5 0-2 0-2 0-2.5-2.5-4-2.5-4 5.5-1.5 6-11.5-5-15.5-11 4-10.5 14-5 15.5 0 0-2.5 1.5-2.5 4 0 0-.5.5 0 2zM25 8a2.5 2.5 0 1 1-5 0 2.5 2.5 0 1 1 5 0z" fill="#000" stroke-linecap="butt"/><path d="M17.5 26h10M15 30h15m-7.5-14.5v5M20 18h5" stroke="#fff" stroke-linejoin="miter"/></g>"##,
+        (Color::Black, PieceType::Rook) => r##"<g id="black-rook" fill="#000" fill-rule="evenodd" stroke="#000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><path d="M9 39h27v-3H9v3zM12.5 32l1.5-2.5h17l1.5 2.5h-20zM12 36v-4h21v4H12z" stroke-linecap="butt"/><path d="M14 29.5v-13h17v13H14z" stroke-linecap="butt" stroke-linejoin="miter"/><path d="M14 16.5L11 14h23l-3 2.5H14zM11 14V9h4v2h5V9h5v2h5V9h4v5H11z" stroke-linecap="butt"/><path d="M12 35.5h21M13 31.5h19M14 29.5h17M14 16.5h17M11 14h23" fill="none" stroke="#fff" stroke-width="1" stroke-linejoin="miter"/></g>"##,
+        (Color::Black, PieceType::Queen) => r##"<g id="black-queen" fill="#000" fill-rule="evenodd" stroke="#000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><g stroke="none"><circle cx="6" cy="12" r="2.75"/><circle cx="14" cy="9" r="2.75"/><circle cx="22.5" cy="8" r="2.75"/><circle cx="31" cy="9" r="2.75"/><circle cx="39" cy="12" r="2.75"/></g><path d="M9 26c8.5-1.5 21-1.5 27 0l2.5-12.5L31 25l-.3-14.1-5.2 13.6-3-14.5-3 14.5-5.2-13.6L14 25 6.5 13.5 9 26zM9 26c0 2 1.5 2 2.5 4 1 1.5 1 1 .5 3.5-1.5 1-1.5 2.5-1.5 2.5-1.5 1.5.5 2.5.5 2.5 6.5 1 16.5 1 23 0 0 0 1.5-1 0-2.5 0 0 .5-1.5-1-2.5-.5-2.5-.5-2 .5-3.5 1-2 2.5-2 2.5-4-8.5-1.5-18.5-1.5-27 0z" stroke-linecap="butt"/><path d="M11 38.5a35 35 1 0 0 23 0" fill="none" stroke-linecap="butt"/><path d="M11 29a35 35 1 0 1 23 0M12.5 31.5h20M11.5 34.5a35 35 1 0 0 22 0M10.5 37.5a35 35 1 0 0 24 0" fill="none" stroke="#fff"/></g>"##,
+        (Color::Black, PieceType::King) => r##"<g id="black-king" fill="none" fill-rule="evenodd" stroke="#000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><path d="M22.5 11.63V6" stroke-linejoin="miter"/><path d="M22.5 25s4.5-7.5 3-10.5c0 0-1-2.5-3-2.5s-3 2.5-3 2.5c-1.5 3 3 10.5 3 10.5" fill="#000" stroke-linecap="butt" stroke-linejoin="miter"/><path d="M11.5 37c5.5 3.5 15.5 3.5 21 0v-7s9-4.5 6-10.5c-4-6.5-13.5-3.5-16 4V27v-3.5c-3.5-7.5-13-10.5-16-4-3 6 5 10 5 10V37z" fill="#000"/><path d="M20 8h5" stroke-linejoin="miter"/><path d="M32 29.5s8.5-4 6.03-9.65C34.15 14 25 18 22.5 24.5l.01 2.1-.01-2.1C20 18 9.906 14 6.997 19.85c-2.497 5.65 4.853 9 4.853 9M11.5 30c5.5-3 15.5-3 21 0m-21 3.5c5.5-3 15.5-3 21 0m-21 3.5c5.5-3 15.5-3 21 0" stroke="#fff"/></g>"##
+    }
+}
+
+// the id of the piece's drawing, for <use>
+fn piece_id(color: Color, kind: PieceType) -> &'static str {
+    match (color, kind) {
+        (Color::White, PieceType::Pawn) => "white-pawn",
+        (Color::White, PieceType::Knight) => "white-knight",
+        (Color::White, PieceType::Bishop) => "white-bishop",
+        (Color::White, PieceType::Rook) => "white-rook",
+        (Color::White, PieceType::Queen) => "white-queen",
+        (Color::White, PieceType::King) => "white-king",
+        (Color::Black, PieceType::Pawn) => "black-pawn",
+        (Color::Black, PieceType::Knight) => "black-knight",
+        (Color::Black, PieceType::Bishop) => "black-bishop",
+        (Color::Black, PieceType::Rook) => "black-rook",
+        (Color::Black, PieceType::Queen) => "black-queen",
+        (Color::Black, PieceType::King) => "black-king"
+    }
+}
+
+// draws the board as a standalone svg document
+pub fn render_svg(board: &Board, options: &RenderOptions) -> String {
+    let theme = &options.theme;
+    let size = SQUARE * 8.0;
+    let mut svg = String::new();
+
+    // writing to a String cannot fail, so the results of write! are ignored throughout
+    let _ = write!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" viewBox="0 0 {0} {0}" width="{0}" height="{0}">"#, size);
+
+    // the drawings of the pieces on the board, once each
+    let mut drawn: Vec<(Color, PieceType)> = Vec::new();
+    for square in Square::all() {
+        if let Some(piece) = board.find_piece(square) {
+            let key = (piece.get_color(), piece.get_kind());
+            if !drawn.contains(&key) { drawn.push(key); }
+        }
+    }
+    if !drawn.is_empty() {
+        svg.push_str("<defs>");
+        for (color, kind) in drawn {
+            svg.push_str(piece_drawing(color, kind));
+        }
+        svg.push_str("</defs>");
+    }
+
+    for square in Square::all() {
+        let (x, y) = corner(square, options.orientation);
+        let dark = (square.file().index() + square.rank().index()) % 2 == 0;
+        let fill = if dark { &theme.dark } else { &theme.light };
+
+        let _ = write!(svg, r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#, x, y, SQUARE, SQUARE, fill);
+    }
+
+    let mut highlights = Vec::new();
+    if let Some(mv) = options.last_move {
+        highlights.push((mv.from(), &theme.last_move));
+        highlights.push((mv.to(), &theme.last_move));
+    }
+    if options.check && board.in_check() {
+        if let Some(king) = board.king_square(board.get_side_to_move()) {
+            highlights.push((king, &theme.check));
+        }
+    }
+    for (square, fill) in highlights {
+        let (x, y) = corner(square, options.orientation);
+        let _ = write!(svg, r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#, x, y, SQUARE, SQUARE, fill);
+    }
+
+    if options.coordinates {
+        let font = r#"font-family="sans-serif" font-size="9" font-weight="bold""#;
+
+        // files along the bottom edge and ranks along the left edge, in the color of the other square shade
+        let (bottom, left) = match options.orientation {
+            Color::White => (Rank::FIRST, File::A),
+            Color::Black => (Rank::EIGHTH, File::H)
+        };
+
+        for file in File::all() {
+            let square = Square::new(file, bottom);
+            let (x, y) = corner(square, options.orientation);
+            let dark = (file.index() + bottom.index()) % 2 == 0;
+            let fill = if dark { &theme.coordinates_dark } else { &theme.coordinates_light };
+
+            let _ = write!(svg, r#"<text x="{}" y="{}" text-anchor="end" fill="{}" {}>{}</text>"#, x + SQUARE - 2.0, y + SQUARE - 2.0, fill, font, file);
+        }
+
+        for rank in Rank::all() {
+            let square = Square::new(left, rank);
+            let (x, y) = corner(square, options.orientation);
+            let dark = (left.index() + rank.index()) % 2 == 0;
+            let fill = if dark { &theme.coordinates_dark } else { &theme.coordinates_light };
+
+            let _ = write!(svg, r#"<text x="{}" y="{}" fill="{}" {}>{}</text>"#, x + 2.0, y + 10.0, fill, font, rank);
+        }
+    }
+
+    for square in Square::all() {
+        if let Some(piece) = board.find_piece(square) {
+            let (x, y) = corner(square, options.orientation);
+            let _ = write!(svg, r##"<use xlink:href="#{}" x="{}" y="{}"/>"##, piece_id(piece.get_color(), piece.get_kind()), x, y);
+        }
+    }
+
+    for circle in options.circles.iter() {
+        let (x, y) = center(circle.square, options.orientation);
+
+        let _ = write!(svg, r#"<circle cx="{}" cy="{}" r="{}" fill="none" stroke="{}" stroke-width="{}" opacity="0.8"/>"#,
+            x, y, SQUARE * 0.45, circle.brush.color(), SQUARE * 0.07);
+    }
+
+    for arrow in options.arrows.iter().filter(|a| a.from != a.to) {
+        let (x1, y1) = center(arrow.from, options.orientation);
+        let (x2, y2) = center(arrow.to, options.orientation);
+        let length = libm::hypot(x2 - x1, y2 - y1);
+        let (dx, dy) = ((x2 - x1) / length, (y2 - y1) / length);
+
+        // the shaft stops where the head starts, and the head's tip sits a little short of the center
+        let head = SQUARE * 0.4;
+        let tip = (x2 - dx * SQUARE * 0.1, y2 - dy * SQUARE * 0.1);
+        let base = (tip.0 - dx * head, tip.1 - dy * head);
+        let wing = (-dy * head * 0.55, dx * head * 0.55);
+        let left = (round(base.0 + wing.0), round(base.1 + wing.1));
+        let right = (round(base.0 - wing.0), round(base.1 - wing.1));
+
+        let _ = write!(svg, r#"<g fill="{0}" stroke="{0}" opacity="0.8">"#, arrow.brush.color());
+        let _ = write!(svg, r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke-width="{}" stroke-linecap="round"/>"#,
+            x1, y1, round(base.0), round(base.1), SQUARE * 0.15);
+        let _ = write!(svg, r#"<polygon points="{},{} {},{} {},{}" stroke="none"/>"#,
+            round(tip.0), round(tip.1), left.0, left.1, right.0, right.1);
+        svg.push_str("</g>");
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
@@ -0,0 +1,2174 @@
+// Syzygy endgame tablebase probing
+//
+// Reads WDL (.rtbw) and DTZ (.rtbz) tables from a directory and looks up positions in them. The
+// table layout, index encoding and decompression follow the format written by the Syzygy
+// generator, as documented in the probing code of Stockfish and Fathom.
+//
+// Tables are loaded lazily: opening a directory only records which files exist, and a table is
+// opened the first time a position needs it. On unix the file is memory-mapped, so only the
+// blocks a probe touches are read from disk; elsewhere it is read into memory whole.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::ops::{Deref, Neg};
+use std::path::{Path, PathBuf};
+use std::string::{String, ToString};
+use std::sync::OnceLock;
+use std::vec::Vec;
+use std::{format, vec};
+
+use crate::bitboard;
+use crate::{Board, Color, Move, PieceType, Square};
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xe8, 0x23, 0x5d];
+const DTZ_MAGIC: [u8; 4] = [0xd7, 0x66, 0x0c, 0xa5];
+
+// largest number of pieces (kings included) the index encoding supports
+const MAX_PIECES: usize = 7;
+
+// value used to rank moves in best_tablebase_move, larger than any dtz
+const MAX_DTZ: i32 = 1 << 18;
+
+// flags stored per table (and per file for tables with pawns)
+const FLAG_STM: u8 = 1;
+const FLAG_MAPPED: u8 = 2;
+const FLAG_WIN_PLIES: u8 = 4;
+const FLAG_LOSS_PLIES: u8 = 8;
+const FLAG_WIDE: u8 = 16;
+const FLAG_SINGLE_VALUE: u8 = 128;
+
+// win/draw/loss result of a position, from the point of view of the side to move
+//
+// cursed wins and blessed losses are results that would be a win or loss, but are a draw under
+// the 50 move rule
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Wdl {
+    Loss = -2,
+    BlessedLoss = -1,
+    Draw = 0,
+    CursedWin = 1,
+    Win = 2
+}
+
+impl Wdl {
+    fn from_i32(value: i32) -> Wdl {
+        match value {
+            v if v <= -2 => Wdl::Loss,
+            -1 => Wdl::BlessedLoss,
+            0 => Wdl::Draw,
+            1 => Wdl::CursedWin,
+            _ => Wdl::Win
+        }
+    }
+
+    fn signum(self) -> i32 {
+        (self as i32).signum()
+    }
+}
+
+impl Neg for Wdl {
+    type Output = Wdl;
+
+    fn neg(self) -> Wdl {
+        Wdl::from_i32(-(self as i32))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum TableKind {
+    Wdl,
+    Dtz
+}
+
+// tables are looked up by kind and name, like (Wdl, "KRvK")
+type TableKey = (TableKind, String);
+
+// a set of tablebase files found in one or more directories
+pub struct Tablebase {
+    files: HashMap<TableKey, TableFile>,
+    max_pieces: usize
+}
+
+// a table file, opened the first time it is needed
+//
+// each file has its own lock, so opening one only holds up the threads that need that same table
+struct TableFile {
+    path: PathBuf,
+    // None for files that turned out to be unreadable
+    table: OnceLock<Option<Table>>
+}
+
+impl Tablebase {
+    // scans a directory for tablebase files
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Tablebase> {
+        let mut tablebase = Tablebase {
+            files: HashMap::new(),
+            max_pieces: 0
+        };
+
+        tablebase.add_directory(path)?;
+
+        Ok(tablebase)
+    }
+
+    // adds the tablebase files in another directory, for tables split over several folders
+    pub fn add_directory<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        for entry in fs::read_dir(path)? {
+            let path = entry?.path();
+
+            let kind = match path.extension().and_then(|x| x.to_str()) {
+                Some("rtbw") => TableKind::Wdl,
+                Some("rtbz") => TableKind::Dtz,
+                _ => continue
+            };
+
+            let name = match path.file_stem().and_then(|x| x.to_str()) {
+                Some(name) => name.to_string(),
+                None => continue
+            };
+
+            // only accept names like KRPvKB
+            let count = match parse_table_name(&name) {
+                Some((white, black)) => white.len() + black.len(),
+                None => continue
+            };
+
+            self.max_pieces = self.max_pieces.max(count);
+            self.files.insert((kind, name), TableFile { path, table: OnceLock::new() });
+        }
+
+        Ok(())
+    }
+
+    // number of pieces (kings included) of the largest table found
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    // finds and loads the table for the given material, returning it along with whether the
+    // position has to be color flipped to match the table
+    fn table(&self, kind: TableKind, white: &str, black: &str) -> Option<(&Table, bool)> {
+        let name = format!("{}v{}", white, black);
+        let flipped = format!("{}v{}", black, white);
+
+        if let Some(table) = self.load(kind, &name) {
+            return Some((table, false));
+        }
+
+        self.load(kind, &flipped).map(|table| (table, true))
+    }
+
+    fn load(&self, kind: TableKind, name: &str) -> Option<&Table> {
+        let file = self.files.get(&(kind, name.to_string()))?;
+
+        file.table
+            .get_or_init(|| Table::new(kind, name, TableBytes::open(&file.path).ok()?))
+            .as_ref()
+    }
+}
+
+// the contents of a table file
+enum TableBytes {
+    #[cfg(unix)]
+    Mapped(Mapping),
+    Read(Vec<u8>)
+}
+
+impl TableBytes {
+    #[cfg(unix)]
+    fn open(path: &Path) -> io::Result<TableBytes> {
+        Mapping::open(path).map(TableBytes::Mapped)
+    }
+
+    #[cfg(not(unix))]
+    fn open(path: &Path) -> io::Result<TableBytes> {
+        fs::read(path).map(TableBytes::Read)
+    }
+}
+
+impl From<Vec<u8>> for TableBytes {
+    fn from(bytes: Vec<u8>) -> TableBytes {
+        TableBytes::Read(bytes)
+    }
+}
+
+impl Deref for TableBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            #[cfg(unix)]
+            TableBytes::Mapped(mapping) => mapping.bytes(),
+            TableBytes::Read(bytes) => bytes
+        }
+    }
+}
+
+// a read only memory map of a whole file
+#[cfg(unix)]
+struct Mapping {
+    ptr: *mut libc::c_void,
+    len: usize
+}
+
+// SAFETY: the mapping is never written to and is unmapped only when dropped
+#[cfg(unix)]
+unsafe impl Send for Mapping {}
+#[cfg(unix)]
+unsafe impl Sync for Mapping {}
+
+#[cfg(unix)]
+impl Mapping {
+    fn open(path: &Path) -> io::Result<Mapping> {
+        use std::os::unix::io::AsRawFd;
+
+        let file = fs::File::open(path)?;
+        let len = file.metadata()?.len() as usize;
+
+        // there is nothing to map in an empty file, and no table in one either
+        if len == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "empty table file"));
+        }
+
+        // SAFETY: a fresh private read only mapping of the whole file; the mapping outlives the
+        // file descriptor, which can be closed right away. tablebase files are not expected to
+        // change while they are in use, as with every other Syzygy prober
+        let ptr = unsafe { libc::mmap(core::ptr::null_mut(), len, libc::PROT_READ, libc::MAP_PRIVATE, file.as_raw_fd(), 0) };
+
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Mapping { ptr, len })
+    }
+
+    fn bytes(&self) -> &[u8] {
+        // SAFETY: ptr points at len readable bytes for as long as the mapping lives
+        unsafe { core::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // SAFETY: ptr and len are those of a mapping made by Mapping::open, unmapped only here
+        unsafe {
+            libc::munmap(self.ptr, self.len);
+        }
+    }
+}
+
+// splits a table name like KRvKP into its two sides
+fn parse_table_name(name: &str) -> Option<(&str, &str)> {
+    let mut sides = name.split('v');
+    let white = sides.next()?;
+    let black = sides.next()?;
+
+    let valid = |side: &str| side.starts_with('K') && side[1..].chars().all(|c| "QRBNP".contains(c));
+
+    if sides.next().is_some() || !valid(white) || !valid(black) || white.len() + black.len() > MAX_PIECES {
+        return None;
+    }
+
+    Some((white, black))
+}
+
+// piece codes used inside the tables: 1-6 for white pawn to king, 9-14 for black
+fn piece_code(color: Color, kind: PieceType) -> u8 {
+    let code = kind as u8 + 1;
+    if color == Color::Black { code | 8 } else { code }
+}
+
+fn char_kind(c: char) -> PieceType {
+    match c {
+        'P' => PieceType::Pawn,
+        'N' => PieceType::Knight,
+        'B' => PieceType::Bishop,
+        'R' => PieceType::Rook,
+        'Q' => PieceType::Queen,
+        _ => PieceType::King
+    }
+}
+
+fn rank_of(sq: u8) -> i32 {
+    (sq >> 3) as i32
+}
+
+fn file_of(sq: u8) -> i32 {
+    (sq & 7) as i32
+}
+
+// negative below the a1-h8 diagonal (on the h1 side), zero on it, positive above it
+fn off_diagonal(sq: u8) -> i32 {
+    rank_of(sq) - file_of(sq)
+}
+
+// lookup tables shared by all tables, used to compute position indexes
+struct Indexes {
+    map_b1h1h7: [u64; 64],
+    map_a1d1d4: [u64; 64],
+    map_kk: [[u64; 64]; 10],
+    binomial: [[u64; 64]; 6],
+    map_pawns: [u64; 64],
+    lead_pawn_idx: [[u64; 64]; 6],
+    lead_pawns_size: [[u64; 4]; 6]
+}
+
+fn indexes() -> &'static Indexes {
+    static INDEXES: OnceLock<Indexes> = OnceLock::new();
+    INDEXES.get_or_init(Indexes::new)
+}
+
+impl Indexes {
+    fn new() -> Indexes {
+        let mut ix = Indexes {
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+            binomial: [[0; 64]; 6],
+            map_pawns: [0; 64],
+            lead_pawn_idx: [[0; 64]; 6],
+            lead_pawns_size: [[0; 4]; 6]
+        };
+
+        // squares below the a1-h8 diagonal are numbered 0..27
+        let mut code = 0;
+        for sq in 0..64u8 {
+            if off_diagonal(sq) < 0 {
+                ix.map_b1h1h7[sq as usize] = code;
+                code += 1;
+            }
+        }
+
+        // squares of the a1-d1-d4 triangle are numbered 0..9, with the diagonal last
+        let mut diagonal = Vec::new();
+        code = 0;
+        for sq in 0..28u8 {
+            if off_diagonal(sq) < 0 && file_of(sq) <= 3 {
+                ix.map_a1d1d4[sq as usize] = code;
+                code += 1;
+            } else if off_diagonal(sq) == 0 && file_of(sq) <= 3 {
+                diagonal.push(sq);
+            }
+        }
+        for sq in diagonal {
+            ix.map_a1d1d4[sq as usize] = code;
+            code += 1;
+        }
+
+        // the 462 legal placements of two kings with the first one in the a1-d1-d4 triangle,
+        // keeping the second one below the diagonal when the first is on it
+        let mut both_on_diagonal = Vec::new();
+        code = 0;
+        for idx in 0..10 {
+            for s1 in 0..28u8 {
+                if ix.map_a1d1d4[s1 as usize] != idx || (idx == 0 && s1 != 1) {
+                    continue;
+                }
+
+                for s2 in 0..64u8 {
+                    let adjacent = s1 == s2 || bitboard::king_attacks(Square::from_bit_index(s1 as u32)) & 1u64 << s2 != 0;
+
+                    // skip illegal positions, and the second king above the diagonal when the first is on it
+                    if adjacent || (off_diagonal(s1) == 0 && off_diagonal(s2) > 0) {
+                        continue;
+                    } else if off_diagonal(s1) == 0 && off_diagonal(s2) == 0 {
+                        both_on_diagonal.push((idx, s2));
+                    } else {
+                        ix.map_kk[idx as usize][s2 as usize] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+        for (idx, s2) in both_on_diagonal {
+            ix.map_kk[idx as usize][s2 as usize] = code;
+            code += 1;
+        }
+
+        // binomial[k][n] is the number of ways to choose k squares out of n
+        ix.binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..6usize.min(n + 1) {
+                ix.binomial[k][n] = if k > 0 { ix.binomial[k - 1][n - 1] } else { 0 }
+                    + if k < n { ix.binomial[k][n - 1] } else { 0 };
+            }
+        }
+
+        // map_pawns numbers the squares a2-h7 so that the leading pawn, the one nearest the edge
+        // and with the lowest rank, has the highest value
+        let mut available = 47;
+        for lead_count in 1..6 {
+            for file in 0..4u8 {
+                let mut idx = 0;
+
+                for rank in 1..7u8 {
+                    let sq = rank * 8 + file;
+
+                    if lead_count == 1 {
+                        ix.map_pawns[sq as usize] = available;
+                        available -= 1;
+                        ix.map_pawns[(sq ^ 7) as usize] = available;
+                        available = available.saturating_sub(1);
+                    }
+
+                    ix.lead_pawn_idx[lead_count][sq as usize] = idx;
+                    idx += ix.binomial[lead_count - 1][ix.map_pawns[sq as usize] as usize];
+                }
+
+                ix.lead_pawns_size[lead_count][file as usize] = idx;
+            }
+        }
+
+        ix
+    }
+}
+
+// decompression data for one table (per side to move and per leading pawn file)
+#[derive(Clone, Default)]
+struct PairsData {
+    flags: u8,
+    block_size: u64,
+    span: u64,
+    num_blocks: u64,
+    min_sym_len: u8,
+    lowest_sym: usize,
+    btree: usize,
+    block_lengths: usize,
+    block_length_size: usize,
+    sparse_index: usize,
+    sparse_index_size: usize,
+    data: usize,
+    base64: Vec<u64>,
+    symlen: Vec<u8>,
+    pieces: [u8; MAX_PIECES],
+    group_idx: [u64; MAX_PIECES + 1],
+    group_len: [usize; MAX_PIECES + 1],
+    map_idx: [usize; 4]
+}
+
+impl PairsData {
+    // number of positions covered by the table, found from the multiplier of the last group
+    fn size(&self) -> u64 {
+        let groups = self.group_len.iter().position(|&x| x == 0).unwrap_or(MAX_PIECES);
+        self.group_idx[groups]
+    }
+}
+
+struct Table {
+    kind: TableKind,
+    bytes: TableBytes,
+    piece_count: usize,
+    has_pawns: bool,
+    has_unique_pieces: bool,
+    // pawns of the leading color, then of the other color
+    pawn_count: [usize; 2],
+    // both sides have the same material, so only white to move is stored
+    symmetric: bool,
+    // indexed by [side][file]
+    items: Vec<Vec<PairsData>>,
+    map: usize
+}
+
+impl Table {
+    fn new(kind: TableKind, name: &str, bytes: TableBytes) -> Option<Table> {
+        let (white, black) = parse_table_name(name)?;
+        let count = |side: &str, c: char| side.chars().filter(|&x| x == c).count();
+
+        let white_pawns = count(white, 'P');
+        let black_pawns = count(black, 'P');
+
+        // the leading color is the one with fewer pawns, as long as it has any
+        let lead_white = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+
+        let has_unique_pieces = "QRBNP".chars().any(|c| count(white, c) == 1 || count(black, c) == 1);
+
+        let magic = match kind {
+            TableKind::Wdl => WDL_MAGIC,
+            TableKind::Dtz => DTZ_MAGIC
+        };
+
+        if bytes.len() < 5 || bytes[0..4] != magic {
+            return None;
+        }
+
+        let mut table = Table {
+            kind,
+            bytes,
+            piece_count: white.len() + black.len(),
+            has_pawns: white_pawns + black_pawns > 0,
+            has_unique_pieces,
+            pawn_count: if lead_white { [white_pawns, black_pawns] } else { [black_pawns, white_pawns] },
+            symmetric: white == black,
+            items: Vec::new(),
+            map: 0
+        };
+
+        table.parse()?;
+
+        Some(table)
+    }
+
+    fn byte(&self, p: usize) -> Option<u8> {
+        self.bytes.get(p).copied()
+    }
+
+    fn u16_le(&self, p: usize) -> Option<u16> {
+        Some(u16::from_le_bytes([self.byte(p)?, self.byte(p + 1)?]))
+    }
+
+    fn u32_le(&self, p: usize) -> Option<u32> {
+        Some(u32::from_le_bytes([self.byte(p)?, self.byte(p + 1)?, self.byte(p + 2)?, self.byte(p + 3)?]))
+    }
+
+    fn u32_be(&self, p: usize) -> Option<u32> {
+        Some(u32::from_be_bytes([self.byte(p)?, self.byte(p + 1)?, self.byte(p + 2)?, self.byte(p + 3)?]))
+    }
+
+    fn sides(&self) -> usize {
+        if self.kind == TableKind::Wdl && !self.symmetric { 2 } else { 1 }
+    }
+
+    fn get(&self, stm: usize, file: usize) -> &PairsData {
+        &self.items[stm % self.sides()][if self.has_pawns { file } else { 0 }]
+    }
+
+    // reads the table header and sets up the decompression data
+    fn parse(&mut self) -> Option<()> {
+        const SPLIT: u8 = 1;
+        const HAS_PAWNS: u8 = 2;
+
+        let flags = self.byte(4)?;
+        if (flags & HAS_PAWNS != 0) != self.has_pawns || (flags & SPLIT != 0) == self.symmetric {
+            return None;
+        }
+
+        let mut p = 5;
+        let sides = self.sides();
+        let files = if self.has_pawns { 4 } else { 1 };
+        let both_pawns = self.has_pawns && self.pawn_count[1] > 0;
+
+        self.items = vec![vec![PairsData::default(); files]; sides];
+
+        for f in 0..files {
+            let first = self.byte(p)?;
+            let second = if both_pawns { self.byte(p + 1)? } else { 0xff };
+            let order = [[first & 0xf, second & 0xf], [first >> 4, second >> 4]];
+            p += 1 + both_pawns as usize;
+
+            for k in 0..self.piece_count {
+                let b = self.byte(p)?;
+                for i in 0..sides {
+                    self.items[i][f].pieces[k] = if i == 1 { b >> 4 } else { b & 0xf };
+                }
+                p += 1;
+            }
+
+            for (i, side_order) in order.iter().enumerate().take(sides) {
+                self.set_groups(i, f, side_order);
+            }
+        }
+
+        // word alignment
+        p += p & 1;
+
+        for f in 0..files {
+            for i in 0..sides {
+                p = self.set_sizes(i, f, p)?;
+            }
+        }
+
+        if self.kind == TableKind::Dtz {
+            p = self.set_dtz_map(p, files)?;
+        }
+
+        for f in 0..files {
+            for i in 0..sides {
+                self.items[i][f].sparse_index = p;
+                p += self.items[i][f].sparse_index_size * 6;
+            }
+        }
+
+        for f in 0..files {
+            for i in 0..sides {
+                self.items[i][f].block_lengths = p;
+                p += self.items[i][f].block_length_size * 2;
+            }
+        }
+
+        for f in 0..files {
+            for i in 0..sides {
+                // 64 byte alignment
+                p = (p + 0x3f) & !0x3f;
+                self.items[i][f].data = p;
+                p += (self.items[i][f].num_blocks * self.items[i][f].block_size) as usize;
+            }
+        }
+
+        // compressed data may end right at the last aligned boundary of an empty table
+        let used = self.items.iter().flatten().filter(|d| d.num_blocks > 0).map(|d| d.data + (d.num_blocks * d.block_size) as usize).max().unwrap_or(0);
+        if used > self.bytes.len() {
+            return None;
+        }
+
+        Some(())
+    }
+
+    // groups pieces that are encoded together and computes the index multiplier of each group
+    //
+    // a group is made of pieces of the same kind and color, except for the leading group which,
+    // without pawns, is the three first pieces when there is a unique piece or the two kings
+    // otherwise. with pawns, the leading pawns come first
+    fn set_groups(&mut self, side: usize, file: usize, order: &[u8; 2]) {
+        let has_pawns = self.has_pawns;
+        let has_unique = self.has_unique_pieces;
+        let both_pawns = has_pawns && self.pawn_count[1] > 0;
+        let piece_count = self.piece_count;
+        let ix = indexes();
+        let d = &mut self.items[side][file];
+
+        let mut n = 0;
+        let mut first_len: i32 = if has_pawns { 0 } else if has_unique { 3 } else { 2 };
+        d.group_len[n] = 1;
+
+        for i in 1..piece_count {
+            first_len -= 1;
+            if first_len > 0 || d.pieces[i] == d.pieces[i - 1] {
+                d.group_len[n] += 1;
+            } else {
+                n += 1;
+                d.group_len[n] = 1;
+            }
+        }
+
+        n += 1;
+        d.group_len[n] = 0;
+
+        // groups are not necessarily encoded in order: the leading group is at order[0] and the
+        // remaining pawns (when both sides have pawns) at order[1]
+        let mut next = if both_pawns { 2 } else { 1 };
+        let mut free_squares = 64 - d.group_len[0] - if both_pawns { d.group_len[1] } else { 0 };
+        let mut idx: u64 = 1;
+        let mut k = 0;
+
+        while next < n || k == order[0] as usize || k == order[1] as usize {
+            if k == order[0] as usize {
+                d.group_idx[0] = idx;
+                idx *= if has_pawns {
+                    ix.lead_pawns_size[d.group_len[0]][file]
+                } else if has_unique {
+                    31332
+                } else {
+                    462
+                };
+            } else if k == order[1] as usize {
+                d.group_idx[1] = idx;
+                idx *= ix.binomial[d.group_len[1]][48 - d.group_len[0]];
+            } else {
+                d.group_idx[next] = idx;
+                idx *= ix.binomial[d.group_len[next]][free_squares];
+                free_squares -= d.group_len[next];
+                next += 1;
+            }
+
+            k += 1;
+        }
+
+        d.group_idx[n] = idx;
+    }
+
+    // reads the block sizes and the huffman code of one table
+    fn set_sizes(&mut self, side: usize, file: usize, mut p: usize) -> Option<usize> {
+        let flags = self.byte(p)?;
+        p += 1;
+
+        if flags & FLAG_SINGLE_VALUE != 0 {
+            let value = self.byte(p)?;
+            let d = &mut self.items[side][file];
+            d.flags = flags;
+            d.min_sym_len = value;
+            return Some(p + 1);
+        }
+
+        let block_size = 1u64 << self.byte(p)?;
+        let span = 1u64 << self.byte(p + 1)?;
+        let padding = self.byte(p + 2)? as usize;
+        let num_blocks = self.u32_le(p + 3)? as u64;
+        let max_sym_len = self.byte(p + 7)?;
+        let min_sym_len = self.byte(p + 8)?;
+        p += 9;
+
+        if min_sym_len == 0 || max_sym_len < min_sym_len || max_sym_len > 32 {
+            return None;
+        }
+
+        let lowest_sym = p;
+        let lengths = (max_sym_len - min_sym_len + 1) as usize;
+        let mut base64 = vec![0u64; lengths];
+
+        // canonical huffman code: base64[i] is the lowest code of length min_sym_len + i, padded to 64 bits
+        for i in (0..lengths - 1).rev() {
+            let low = self.u16_le(lowest_sym + 2 * i)? as u64;
+            let next_low = self.u16_le(lowest_sym + 2 * (i + 1))? as u64;
+            base64[i] = base64[i + 1].wrapping_add(low).wrapping_sub(next_low) / 2;
+        }
+        for (i, base) in base64.iter_mut().enumerate() {
+            *base = base.checked_shl((64 - i - min_sym_len as usize) as u32).unwrap_or(0);
+        }
+
+        p += lengths * 2;
+        let symbols = self.u16_le(p)? as usize;
+        p += 2;
+        let btree = p;
+
+        if btree + symbols * 3 > self.bytes.len() {
+            return None;
+        }
+
+        // each symbol expands into a pair of symbols, recursively: symlen is the number of values
+        // (minus one) a symbol stands for
+        let mut symlen = vec![0u8; symbols];
+        let mut visited = vec![false; symbols];
+        for sym in 0..symbols {
+            if !visited[sym] {
+                symlen[sym] = self.set_symlen(btree, sym, &mut symlen, &mut visited)?;
+            }
+        }
+
+        let tb_size = self.items[side][file].size();
+
+        let d = &mut self.items[side][file];
+        d.flags = flags;
+        d.block_size = block_size;
+        d.span = span;
+        d.sparse_index_size = tb_size.div_ceil(span) as usize;
+        d.num_blocks = num_blocks;
+        d.block_length_size = num_blocks as usize + padding;
+        d.min_sym_len = min_sym_len;
+        d.lowest_sym = lowest_sym;
+        d.base64 = base64;
+        d.btree = btree;
+        d.symlen = symlen;
+
+        Some(btree + symbols * 3 + (symbols & 1))
+    }
+
+    fn set_symlen(&self, btree: usize, sym: usize, symlen: &mut Vec<u8>, visited: &mut Vec<bool>) -> Option<u8> {
+        visited[sym] = true;
+
+        let (left, right) = self.pair(btree, sym);
+        if right == 0xfff {
+            return Some(0);
+        }
+
+        if left >= symlen.len() || right >= symlen.len() {
+            return None;
+        }
+
+        if !visited[left] {
+            symlen[left] = self.set_symlen(btree, left, symlen, visited)?;
+        }
+        if !visited[right] {
+            symlen[right] = self.set_symlen(btree, right, symlen, visited)?;
+        }
+
+        Some(symlen[left].wrapping_add(symlen[right]).wrapping_add(1))
+    }
+
+    // left and right child symbols of a symbol, packed as 12 bits each
+    fn pair(&self, btree: usize, sym: usize) -> (usize, usize) {
+        let p = btree + 3 * sym;
+        let lr = &self.bytes[p..p + 3];
+        let left = (((lr[1] & 0xf) as usize) << 8) | lr[0] as usize;
+        let right = ((lr[2] as usize) << 4) | (lr[1] >> 4) as usize;
+        (left, right)
+    }
+
+    // dtz values are stored as indexes into a per file map, one map for each wdl result
+    fn set_dtz_map(&mut self, mut p: usize, files: usize) -> Option<usize> {
+        self.map = p;
+
+        for f in 0..files {
+            let flags = self.items[0][f].flags;
+
+            if flags & FLAG_MAPPED == 0 {
+                continue;
+            }
+
+            if flags & FLAG_WIDE != 0 {
+                p += p & 1;
+                for i in 0..4 {
+                    self.items[0][f].map_idx[i] = (p - self.map) / 2 + 1;
+                    p += 2 * self.u16_le(p)? as usize + 2;
+                }
+            } else {
+                for i in 0..4 {
+                    self.items[0][f].map_idx[i] = p - self.map + 1;
+                    p += self.byte(p)? as usize + 1;
+                }
+            }
+        }
+
+        Some(p + (p & 1))
+    }
+
+    // finds the value stored at idx
+    fn decompress_pairs(&self, d: &PairsData, idx: u64) -> Option<i32> {
+        if d.flags & FLAG_SINGLE_VALUE != 0 {
+            return Some(d.min_sym_len as i32);
+        }
+
+        // the sparse index points at the block holding every span-th value, from there we walk
+        // the block lengths to the block that actually contains idx
+        let k = (idx / d.span) as usize;
+        if k >= d.sparse_index_size {
+            return None;
+        }
+
+        let entry = d.sparse_index + 6 * k;
+        let mut block = self.u32_le(entry)? as usize;
+        let mut offset = self.u16_le(entry + 4)? as i64;
+
+        offset += (idx % d.span) as i64 - (d.span / 2) as i64;
+
+        let block_length = |b: usize| self.u16_le(d.block_lengths + 2 * b).map(|x| x as i64);
+
+        while offset < 0 {
+            block = block.checked_sub(1)?;
+            offset += block_length(block)? + 1;
+        }
+
+        while offset > block_length(block)? {
+            offset -= block_length(block)? + 1;
+            block += 1;
+        }
+
+        if block as u64 >= d.num_blocks {
+            return None;
+        }
+
+        // read huffman symbols until reaching the one that covers our offset
+        let mut ptr = d.data + (block as u64 * d.block_size) as usize;
+        let mut buf64 = ((self.u32_be(ptr)? as u64) << 32) | self.u32_be(ptr + 4)? as u64;
+        ptr += 8;
+        let mut buf64_size = 64;
+        let min_sym_len = d.min_sym_len as usize;
+        let mut sym;
+
+        loop {
+            let mut len = 0;
+
+            while len + 1 < d.base64.len() && buf64 < d.base64[len] {
+                len += 1;
+            }
+
+            sym = (buf64.wrapping_sub(d.base64[len]) >> (64 - len - min_sym_len)) as usize;
+            sym += self.u16_le(d.lowest_sym + 2 * len)? as usize;
+
+            if sym >= d.symlen.len() {
+                return None;
+            }
+
+            if offset < d.symlen[sym] as i64 + 1 {
+                break;
+            }
+
+            offset -= d.symlen[sym] as i64 + 1;
+            len += min_sym_len;
+            buf64 = buf64.checked_shl(len as u32).unwrap_or(0);
+            buf64_size -= len as i32;
+
+            if buf64_size <= 32 {
+                buf64_size += 32;
+                // the last block may end before the buffer is full
+                buf64 |= (self.u32_be(ptr).unwrap_or(0) as u64) << (64 - buf64_size);
+                ptr += 4;
+            }
+        }
+
+        // expand the symbol down to the leaf holding our value
+        while d.symlen[sym] != 0 {
+            let (left, right) = self.pair(d.btree, sym);
+
+            if offset < d.symlen[left] as i64 + 1 {
+                sym = left;
+            } else {
+                offset -= d.symlen[left] as i64 + 1;
+                sym = right;
+            }
+        }
+
+        Some(self.pair(d.btree, sym).0 as i32)
+    }
+
+    // dtz tables only store one side to move; returns false if the other side is needed
+    fn check_dtz_stm(&self, stm: usize, file: usize) -> bool {
+        let flags = self.get(stm, file).flags;
+        (flags & FLAG_STM) as usize == stm || (self.symmetric && !self.has_pawns)
+    }
+
+    // turns a stored value into a wdl score or a dtz in plies
+    fn map_score(&self, file: usize, value: i32, wdl: Wdl) -> Option<i32> {
+        if self.kind == TableKind::Wdl {
+            return Some(value - 2);
+        }
+
+        const WDL_MAP: [usize; 5] = [1, 3, 0, 2, 0];
+
+        let d = self.get(0, file);
+        let flags = d.flags;
+        let mut value = value;
+
+        if flags & FLAG_MAPPED != 0 {
+            let idx = d.map_idx[WDL_MAP[(wdl as i32 + 2) as usize]] + value as usize;
+
+            value = if flags & FLAG_WIDE != 0 {
+                self.u16_le(self.map + 2 * idx)? as i32
+            } else {
+                self.byte(self.map + idx)? as i32
+            };
+        }
+
+        // values are stored in moves unless the table says they are in plies
+        if (wdl == Wdl::Win && flags & FLAG_WIN_PLIES == 0)
+            || (wdl == Wdl::Loss && flags & FLAG_LOSS_PLIES == 0)
+            || wdl == Wdl::CursedWin
+            || wdl == Wdl::BlessedLoss {
+            value *= 2;
+        }
+
+        Some(value + 1)
+    }
+}
+
+// result of looking a position up in a single table
+enum Lookup {
+    Value(i32),
+    // the dtz table only stores the other side to move
+    ChangeStm
+}
+
+// where a position is stored in a single table
+enum Slot {
+    // the side to move and leading pawn file of the sub table, and the index in it
+    Index(usize, usize, u64),
+    // the dtz table only stores the other side to move
+    ChangeStm
+}
+
+// the pieces of a board as (table piece code, square index) pairs, ordered by square
+fn board_pieces(board: &Board) -> Vec<(u8, u8)> {
+    let mut pieces = Vec::new();
+
+    for color in [Color::White, Color::Black].iter() {
+        for list in board.pieces_of(*color).iter() {
+            for piece in list.iter() {
+                pieces.push((piece_code(*color, piece.kind), piece.square.index()));
+            }
+        }
+    }
+
+    pieces.sort_by_key(|&(_, sq)| sq);
+    pieces
+}
+
+// material of one side written the way table names are, like KRP
+fn material(board: &Board, color: Color) -> String {
+    let mut side = String::new();
+
+    for c in "KQRBNP".chars() {
+        let count = board.piece_count(color, char_kind(c));
+        for _ in 0..count {
+            side.push(c);
+        }
+    }
+
+    side
+}
+
+// computes the index of the position in the table and returns the stored value
+fn probe_table(board: &Board, table: &Table, black_stronger: bool, wdl: Wdl) -> Option<Lookup> {
+    match position_index(board, table, black_stronger)? {
+        Slot::Index(stm, file, idx) => {
+            let value = table.decompress_pairs(table.get(stm, file), idx)?;
+            table.map_score(file, value, wdl).map(Lookup::Value)
+        },
+        Slot::ChangeStm => Some(Lookup::ChangeStm)
+    }
+}
+
+// finds the sub table and index the position is stored at
+fn position_index(board: &Board, table: &Table, black_stronger: bool) -> Option<Slot> {
+    let ix = indexes();
+    let pieces_on_board = board_pieces(board);
+    let black_to_move = board.get_side_to_move() == Color::Black;
+
+    // symmetric tables only store white to move, and tables are written with the stronger side
+    // as white, so both cases are looked up with colors and ranks swapped
+    let flip = (table.symmetric && black_to_move) || black_stronger;
+    let flip_color = if flip { 8 } else { 0 };
+    let flip_squares = if flip { 56 } else { 0 };
+    let stm = (flip != black_to_move) as usize;
+
+    let mut squares = [0u8; MAX_PIECES];
+    let mut pieces = [0u8; MAX_PIECES];
+    let mut size = 0;
+    let mut lead_count = 0;
+    let mut lead_pawns = 0u64;
+    let mut tb_file = 0;
+
+    let pawn_order = |sq: &u8| ix.map_pawns[*sq as usize];
+
+    if table.has_pawns {
+        // the first piece of every sub table is a pawn of the leading color
+        let lead = table.get(0, 0).pieces[0] ^ flip_color;
+
+        for &(code, sq) in pieces_on_board.iter().filter(|&&(code, _)| code == lead) {
+            squares[size] = sq ^ flip_squares;
+            pieces[size] = code ^ flip_color;
+            lead_pawns |= 1u64 << sq;
+            size += 1;
+        }
+
+        lead_count = size;
+
+        let max = (0..lead_count).rev().max_by_key(|&i| pawn_order(&squares[i]))?;
+        squares.swap(0, max);
+
+        let file = file_of(squares[0]);
+        tb_file = file.min(7 - file) as usize;
+    }
+
+    if table.kind == TableKind::Dtz && !table.check_dtz_stm(stm, tb_file) {
+        return Some(Slot::ChangeStm);
+    }
+
+    for &(code, sq) in pieces_on_board.iter() {
+        if lead_pawns & 1u64 << sq == 0 {
+            squares[size] = sq ^ flip_squares;
+            pieces[size] = code ^ flip_color;
+            size += 1;
+        }
+    }
+
+    if size != table.piece_count {
+        return None;
+    }
+
+    let d = table.get(stm, tb_file);
+
+    // put the pieces in the order the table was written with
+    for i in lead_count..size.saturating_sub(1) {
+        for j in i..size {
+            if d.pieces[i] == pieces[j] {
+                pieces.swap(i, j);
+                squares.swap(i, j);
+                break;
+            }
+        }
+    }
+
+    // mirror so the leading piece is on files a-d
+    if file_of(squares[0]) > 3 {
+        for sq in squares.iter_mut().take(size) {
+            *sq ^= 7;
+        }
+    }
+
+    let mut idx: u64;
+
+    if table.has_pawns {
+        idx = ix.lead_pawn_idx[lead_count][squares[0] as usize];
+
+        squares[1..lead_count].sort_by_key(pawn_order);
+
+        for (i, sq) in squares.iter().enumerate().take(lead_count).skip(1) {
+            idx += ix.binomial[i][ix.map_pawns[*sq as usize] as usize];
+        }
+    } else {
+        // without pawns, also mirror so the leading piece is on ranks 1-4
+        if rank_of(squares[0]) > 3 {
+            for sq in squares.iter_mut().take(size) {
+                *sq ^= 56;
+            }
+        }
+
+        // and make sure the first leading piece off the a1-h8 diagonal is below it
+        for i in 0..d.group_len[0] {
+            if off_diagonal(squares[i]) == 0 {
+                continue;
+            }
+
+            if off_diagonal(squares[i]) > 0 {
+                for sq in squares.iter_mut().take(size).skip(i) {
+                    *sq = ((*sq >> 3) | (*sq << 3)) & 63;
+                }
+            }
+
+            break;
+        }
+
+        if table.has_unique_pieces {
+            let s = [squares[0] as u64, squares[1] as u64, squares[2] as u64];
+            let adjust1 = (s[1] > s[0]) as u64;
+            let adjust2 = (s[2] > s[0]) as u64 + (s[2] > s[1]) as u64;
+            let rank = |sq: u8| rank_of(sq) as u64;
+
+            idx = if off_diagonal(squares[0]) != 0 {
+                (ix.map_a1d1d4[s[0] as usize] * 63 + (s[1] - adjust1)) * 62 + s[2] - adjust2
+            } else if off_diagonal(squares[1]) != 0 {
+                (6 * 63 + rank(squares[0]) * 28 + ix.map_b1h1h7[s[1] as usize]) * 62 + s[2] - adjust2
+            } else if off_diagonal(squares[2]) != 0 {
+                6 * 63 * 62 + 4 * 28 * 62
+                    + rank(squares[0]) * 7 * 28
+                    + (rank(squares[1]) - adjust1) * 28
+                    + ix.map_b1h1h7[s[2] as usize]
+            } else {
+                6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28
+                    + rank(squares[0]) * 7 * 6
+                    + (rank(squares[1]) - adjust1) * 6
+                    + rank(squares[2]) - adjust2
+            };
+        } else {
+            idx = ix.map_kk[ix.map_a1d1d4[squares[0] as usize] as usize][squares[1] as usize];
+        }
+    }
+
+    // encode the remaining groups, each as a combination of the squares left by earlier groups
+    idx *= d.group_idx[0];
+    let mut start = d.group_len[0];
+    let mut remaining_pawns = table.has_pawns && table.pawn_count[1] > 0;
+    let mut next = 1;
+
+    while d.group_len[next] != 0 {
+        let len = d.group_len[next];
+        squares[start..start + len].sort_unstable();
+
+        let mut n = 0;
+        for i in 0..len {
+            let sq = squares[start + i];
+            let adjust = squares[..start].iter().filter(|&&s| sq > s).count() as u64;
+            let pawn_offset = if remaining_pawns { 8 } else { 0 };
+            n += ix.binomial[i + 1][(sq as u64 - adjust - pawn_offset) as usize];
+        }
+
+        remaining_pawns = false;
+        idx += n * d.group_idx[next];
+        start += len;
+        next += 1;
+    }
+
+    Some(Slot::Index(stm, tb_file, idx))
+}
+
+// probes the table for the material on the board, without looking at captures
+fn probe_material(board: &Board, tb: &Tablebase, kind: TableKind, wdl: Wdl) -> Option<Lookup> {
+    let white = material(board, Color::White);
+    let black = material(board, Color::Black);
+
+    // bare kings are a draw and have no table
+    if white.len() + black.len() == 2 {
+        return Some(Lookup::Value(0));
+    }
+
+    let (table, black_stronger) = tb.table(kind, &white, &black)?;
+    probe_table(board, table, black_stronger && white != black, wdl)
+}
+
+// true if the move resets the 50 move counter
+fn is_zeroing(board: &Board, mv: &Move) -> bool {
+    board.is_capture(mv)
+        || board.find_piece(mv.from()).map(|p| p.kind) == Some(PieceType::Pawn)
+}
+
+fn after_move(board: &Board, mv: Move) -> Board {
+    let mut next = board.clone();
+    next.apply_move(mv);
+    next
+}
+
+// wdl of the position, resolving captures first
+//
+// tables store "don't care" values when the side to move has a winning capture, and positions
+// with en passant rights are not stored at all, so captures have to be searched before trusting
+// the table. the returned flag is set when the best move is a zeroing move, in which case the dtz
+// table cannot be trusted either
+fn search(board: &Board, tb: &Tablebase, check_zeroing: bool) -> Option<(Wdl, bool)> {
+    let moves = board.get_legal_moves();
+    let mut best = Wdl::Loss;
+    let mut searched = 0;
+
+    for mv in moves.iter() {
+        let pawn_move = board.find_piece(mv.from()).map(|p| p.kind) == Some(PieceType::Pawn);
+
+        if !board.is_capture(mv) && (!check_zeroing || !pawn_move) {
+            continue;
+        }
+
+        searched += 1;
+
+        let (value, _) = search(&after_move(board, *mv), tb, false)?;
+        let value = -value;
+
+        if value > best {
+            best = value;
+
+            if value >= Wdl::Win {
+                return Some((value, true));
+            }
+        }
+    }
+
+    // when every legal move was a capture we already know the exact result
+    let no_more_moves = searched > 0 && searched == moves.len();
+
+    let value = if no_more_moves {
+        best
+    } else {
+        match probe_material(board, tb, TableKind::Wdl, Wdl::Draw)? {
+            Lookup::Value(v) => Wdl::from_i32(v),
+            Lookup::ChangeStm => return None
+        }
+    };
+
+    if best >= value {
+        return Some((best, best > Wdl::Draw || no_more_moves));
+    }
+
+    Some((value, false))
+}
+
+// dtz of the position just before a zeroing move that led to a position with the given wdl
+fn dtz_before_zeroing(wdl: Wdl) -> i32 {
+    match wdl {
+        Wdl::Win => 1,
+        Wdl::CursedWin => 101,
+        Wdl::BlessedLoss => -101,
+        Wdl::Loss => -1,
+        Wdl::Draw => 0
+    }
+}
+
+fn probe_dtz(board: &Board, tb: &Tablebase) -> Option<i32> {
+    let (wdl, zeroing_best) = search(board, tb, true)?;
+
+    // dtz tables do not store draws
+    if wdl == Wdl::Draw {
+        return Some(0);
+    }
+
+    if zeroing_best {
+        return Some(dtz_before_zeroing(wdl));
+    }
+
+    match probe_material(board, tb, TableKind::Dtz, wdl)? {
+        Lookup::Value(dtz) => {
+            let cursed = wdl == Wdl::BlessedLoss || wdl == Wdl::CursedWin;
+            Some((dtz + if cursed { 100 } else { 0 }) * wdl.signum())
+        },
+        Lookup::ChangeStm => {
+            // the table stores the other side to move, so look one ply ahead for the move that
+            // keeps the result with the best distance
+            let mut min_dtz = 0xffff;
+
+            for mv in board.get_legal_moves() {
+                let zeroing = is_zeroing(board, &mv);
+                let next = after_move(board, mv);
+
+                let mut dtz = if zeroing {
+                    -dtz_before_zeroing(search(&next, tb, false)?.0)
+                } else {
+                    -probe_dtz(&next, tb)?
+                };
+
+                // a mating move is always the shortest
+                if dtz == 1 && next.in_check() && next.get_legal_moves().is_empty() {
+                    min_dtz = 1;
+                }
+
+                if !zeroing {
+                    dtz += dtz.signum();
+                }
+
+                if dtz < min_dtz && dtz.signum() == wdl.signum() {
+                    min_dtz = dtz;
+                }
+            }
+
+            Some(if min_dtz == 0xffff { -1 } else { min_dtz })
+        }
+    }
+}
+
+// tables have no castling rights and only go up to the number of pieces available
+fn probeable(board: &Board, tb: &Tablebase) -> bool {
+    let castling = board.get_castle_ability(Color::White);
+    let castling_black = board.get_castle_ability(Color::Black);
+    let count = board_pieces(board).len();
+
+    !(castling.0 || castling.1 || castling_black.0 || castling_black.1) && (count == 2 || count <= tb.max_pieces())
+}
+
+impl Board {
+    // win/draw/loss of the position for the side to move, if it is covered by the tablebase
+    pub fn probe_wdl(&self, tb: &Tablebase) -> Option<Wdl> {
+        if !probeable(self, tb) { return None; }
+
+        search(self, tb, false).map(|(wdl, _)| wdl)
+    }
+
+    // distance to zeroing the 50 move counter in plies, positive when the side to move wins and
+    // negative when it loses, 0 for draws
+    //
+    // the value may be off by one ply since some tables store moves rather than plies
+    pub fn probe_dtz(&self, tb: &Tablebase) -> Option<i32> {
+        if !probeable(self, tb) { return None; }
+
+        probe_dtz(self, tb)
+    }
+
+    // picks the move that keeps the best tablebase result, winning as fast as possible or losing
+    // as slowly as possible with regard to the 50 move rule
+    pub fn best_tablebase_move(&self, tb: &Tablebase) -> Option<Move> {
+        if !probeable(self, tb) { return None; }
+
+        let halfmoves = self.fen.split_whitespace().nth(4).and_then(|x| x.parse::<i32>().ok()).unwrap_or(0);
+        let mut best: Option<(i32, Move)> = None;
+
+        for mv in self.get_legal_moves() {
+            let next = after_move(self, mv);
+
+            let mut dtz = if is_zeroing(self, &mv) {
+                dtz_before_zeroing(-next.probe_wdl(tb)?)
+            } else if halfmoves + 1 >= 100 && !(next.in_check() && next.get_legal_moves().is_empty()) {
+                0
+            } else {
+                let dtz = -probe_dtz(&next, tb)?;
+                dtz + dtz.signum()
+            };
+
+            if next.in_check() && dtz == 2 && next.get_legal_moves().is_empty() {
+                dtz = 1;
+            }
+
+            // wins that can be completed within the 50 move rule beat every other result, the
+            // closer the better, then come cursed wins, draws, blessed losses and real losses
+            let rank = if dtz > 0 {
+                if dtz + halfmoves <= 99 { MAX_DTZ - dtz } else { MAX_DTZ / 2 - (dtz + halfmoves) }
+            } else if dtz < 0 {
+                if -dtz * 2 + halfmoves < 100 { -MAX_DTZ - dtz } else { -MAX_DTZ / 2 + (-dtz + halfmoves) }
+            } else {
+                0
+            };
+
+            match best {
+                Some((r, _)) if r >= rank => {},
+                _ => best = Some((rank, mv))
+            }
+        }
+
+        best.map(|(_, mv)| mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+
+    #[test]
+    fn test_king_pair_indexes() {
+        let ix = indexes();
+
+        // two kings with the first in the a1-d1-d4 triangle have 462 distinct placements, numbered
+        // from 0, so every code but 0 shows up exactly once
+        let mut codes = ix.map_kk.iter().flatten().copied().filter(|&x| x != 0).collect::<Vec<u64>>();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), 461);
+        assert_eq!(*codes.last().unwrap(), 461);
+    }
+
+    #[test]
+    fn test_pawn_indexes() {
+        let ix = indexes();
+
+        // a2 is the most leading square, followed by its mirror h2
+        assert_eq!(ix.map_pawns[8], 47);
+        assert_eq!(ix.map_pawns[15], 46);
+        assert_eq!(ix.binomial[2][5], 10);
+
+        // a single leading pawn can stand on any of the 6 squares of its file
+        for file in 0..4 {
+            assert_eq!(ix.lead_pawns_size[1][file], 6);
+        }
+    }
+
+    // builds a KQvK wdl table whose values for white to move are compressed with a small huffman
+    // code, including a symbol that expands into a pair of values
+    fn compressed_table(values: &[u8]) -> Vec<u8> {
+        // symbols: 0 is a loss ("00"), 1 expands to win then loss ("01"), 2 is a win ("1")
+        let mut blocks: Vec<Vec<u8>> = Vec::new();
+        let mut lengths = Vec::new();
+        let mut i = 0;
+
+        while i < values.len() {
+            let mut bits = Vec::new();
+            let start = i;
+
+            // keep a spare word at the end of each block since the decoder reads ahead
+            while i < values.len() && bits.len() < 8 * 64 - 34 {
+                if values[i] == 4 && values.get(i + 1) == Some(&0) {
+                    bits.extend_from_slice(&[0, 1]);
+                    i += 2;
+                } else if values[i] == 4 {
+                    bits.push(1);
+                    i += 1;
+                } else {
+                    bits.extend_from_slice(&[0, 0]);
+                    i += 1;
+                }
+            }
+
+            let mut block = vec![0u8; 64];
+            for (j, bit) in bits.iter().enumerate() {
+                block[j / 8] |= bit << (7 - j % 8);
+            }
+            blocks.push(block);
+            lengths.push((i - start - 1) as u16);
+        }
+
+        let span = 256;
+        let mut bytes = vec![0x71, 0xe8, 0x23, 0x5d, 0x01, 0x00, 0x66, 0x55, 0xee, 0x00];
+
+        // side 0: block size 2^6, span 2^8, no padding, then symbol lengths 1..2
+        bytes.extend_from_slice(&[0x00, 6, 8, 0]);
+        bytes.extend_from_slice(&(blocks.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&[2, 1]);
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&0u16.to_le_bytes());
+        bytes.extend_from_slice(&3u16.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0xf0, 0xff, 2, 0x00, 0x00, 4, 0xf0, 0xff, 0]);
+
+        // side 1 always loses
+        bytes.extend_from_slice(&[0x80, 0]);
+
+        // every sparse entry points at the value in the middle of its span
+        let total = values.len();
+        for k in 0..total.div_ceil(span) {
+            let target = k * span + span / 2;
+            let mut block = 0;
+            let mut start = 0;
+            while block + 1 < lengths.len() && start + (lengths[block] as usize) < target {
+                start += lengths[block] as usize + 1;
+                block += 1;
+            }
+            bytes.extend_from_slice(&(block as u32).to_le_bytes());
+            bytes.extend_from_slice(&((target - start) as u16).to_le_bytes());
+        }
+
+        for length in lengths.iter() {
+            bytes.extend_from_slice(&length.to_le_bytes());
+        }
+
+        while bytes.len() % 64 != 0 {
+            bytes.push(0);
+        }
+        for block in blocks.iter() {
+            bytes.extend_from_slice(block);
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn test_decompress_pairs() {
+        // a mix of runs and pairs, deterministic but irregular
+        let values = (0..31332u64)
+            .map(|i| if (i * 7919 + i / 13) % 5 < 2 { 0 } else { 4 })
+            .collect::<Vec<u8>>();
+
+        let table = Table::new(TableKind::Wdl, "KQvK", compressed_table(&values).into()).unwrap();
+
+        for idx in (0..values.len()).step_by(97).chain(values.len() - 3..values.len()) {
+            assert_eq!(table.decompress_pairs(table.get(0, 0), idx as u64), Some(values[idx] as i32), "index {}", idx);
+        }
+        assert_eq!(table.decompress_pairs(table.get(1, 0), 5), Some(0));
+    }
+
+    #[test]
+    fn test_encode_pairs() {
+        // short runs of three values, so pairs get paired again
+        let values = (0..31332u32).map(|i| [4, 4, 0, 2, 4][((i / 3) * 7 % 5) as usize]).collect::<Vec<u32>>();
+
+        let header = [0x71, 0xe8, 0x23, 0x5d, 0x01, 0x00, 0x66, 0x55, 0xee, 0x00];
+        let items = [encode(&values, 0), encode(&[0], 0)];
+        let table = Table::new(TableKind::Wdl, "KQvK", table_file(&header, &items, TableKind::Wdl).into()).unwrap();
+
+        assert!(table.items[0][0].symlen.iter().any(|&len| len >= 3));
+        for (idx, &value) in values.iter().enumerate() {
+            assert_eq!(table.decompress_pairs(table.get(0, 0), idx as u64), Some(value as i32), "index {}", idx);
+        }
+    }
+
+    // the fixture tables are compressed the same way, so probing them decodes pair symbols too
+    #[test]
+    fn test_fixtures_use_pairs() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/syzygy/KQvKR.rtbw");
+        let table = Table::new(TableKind::Wdl, "KQvKR", fs::read(path).unwrap().into()).unwrap();
+
+        assert!(table.items.iter().flatten().all(|d| d.symlen.iter().any(|&len| len > 0)));
+    }
+
+    #[test]
+    fn test_diagonal_indexes() {
+        let header = [0x71, 0xe8, 0x23, 0x5d, 0x01, 0x00, 0x66, 0x55, 0xee, 0x00, 0x80, 4, 0x80, 0];
+        let table = Table::new(TableKind::Wdl, "KQvK", header.to_vec().into()).unwrap();
+        let index = |fen: &str| match position_index(&Board::new(fen.to_string()), &table, false) {
+            Some(Slot::Index(_, _, idx)) => idx,
+            _ => panic!("no index for {}", fen)
+        };
+
+        // with all three pieces on the a1-h8 diagonal the index comes from their ranks alone, in
+        // the last 4 * 7 * 6 indexes of the table
+        assert_ne!(index("7k/8/8/8/3K4/8/8/Q7 b - - 0 1"), index("8/8/5K2/8/8/8/1k6/Q7 b - - 0 1"));
+        assert_eq!(index("7k/8/8/8/8/8/1K6/Q7 w - - 0 1"), 31164 + 7 * 6 + 5);
+        assert_eq!(index("7Q/6k1/8/8/3K4/8/8/8 b - - 0 1"), 31331);
+    }
+
+    #[test]
+    fn test_table_names() {
+        assert_eq!(parse_table_name("KRPvKB"), Some(("KRP", "KB")));
+        assert_eq!(parse_table_name("KRvKvK"), None);
+        assert_eq!(parse_table_name("QvK"), None);
+        assert_eq!(-Wdl::CursedWin, Wdl::BlessedLoss);
+    }
+
+    // the fixture tables in tests/fixtures/syzygy are not the official ones: they are solved
+    // here by retrograde analysis and written in the same format, using the index code above.
+    // regenerate them with
+    //
+    //   cargo test --release --lib syzygy -- --ignored
+
+    // a move out of a position, either into the same endgame along with whether it zeroes the
+    // move counter, or into another one whose wdl for the side to move there is already known
+    #[derive(Clone, Copy)]
+    enum Child {
+        Inner(u32, bool),
+        Outer(i32)
+    }
+
+    // a solved endgame with at most one piece of each kind on a side: wdl and dtz in plies for the
+    // side to move of every position id, None for illegal positions
+    //
+    // a position id is the squares of the pieces, the first in the lowest bits, and then the side
+    // to move. without pawns a position is only solved once for all its reflections and rotations,
+    // under the smallest id among them
+    struct Endgame {
+        name: String,
+        // piece codes in the order of the squares of an id: the kings, white's pieces, black's
+        pieces: Vec<u8>,
+        wdl: Vec<Option<i32>>,
+        dtz: Vec<i32>
+    }
+
+    impl Endgame {
+        fn new(name: &str) -> Endgame {
+            let (white, black) = parse_table_name(name).unwrap();
+            let mut pieces = vec![piece_code(Color::White, PieceType::King), piece_code(Color::Black, PieceType::King)];
+            pieces.extend(white[1..].chars().map(|c| piece_code(Color::White, char_kind(c))));
+            pieces.extend(black[1..].chars().map(|c| piece_code(Color::Black, char_kind(c))));
+
+            Endgame { name: name.to_string(), pieces, wdl: Vec::new(), dtz: Vec::new() }
+        }
+
+        fn positions(&self) -> usize {
+            2 << (6 * self.pieces.len())
+        }
+
+        fn has_pawns(&self) -> bool {
+            self.pieces.iter().any(|&code| code & 7 == 1)
+        }
+
+        // the id the position is solved under
+        fn canonical(&self, squares: &[u8], black_to_move: bool) -> usize {
+            let symmetries = if self.has_pawns() { 1 } else { 8 };
+
+            (0..symmetries)
+                .map(|symmetry| squares.iter().rev().fold(0, |id, &sq| id * 64 + transform(sq, symmetry) as usize))
+                .min()
+                .unwrap() * 2 + black_to_move as usize
+        }
+    }
+
+    // one of the eight ways to reflect and rotate the board: flips the file, flips the rank and
+    // swaps the two, as the bits of symmetry say
+    fn transform(sq: u8, symmetry: u8) -> u8 {
+        let (mut file, mut rank) = (sq & 7, sq >> 3);
+
+        if symmetry & 1 != 0 {
+            file = 7 - file;
+        }
+        if symmetry & 2 != 0 {
+            rank = 7 - rank;
+        }
+        if symmetry & 4 != 0 {
+            (file, rank) = (rank, file);
+        }
+
+        rank * 8 + file
+    }
+
+    fn position_fen(pieces: &[u8], squares: &[u8], black_to_move: bool) -> String {
+        let mut rows = Vec::new();
+
+        for rank in (0..8u8).rev() {
+            let mut row = String::new();
+            let mut empty = 0;
+
+            for file in 0..8u8 {
+                match squares.iter().position(|&sq| sq == rank * 8 + file) {
+                    Some(i) => {
+                        if empty > 0 {
+                            row.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+
+                        let c = b"PNBRQK"[(pieces[i] & 7) as usize - 1] as char;
+                        row.push(if pieces[i] & 8 != 0 { c.to_ascii_lowercase() } else { c });
+                    },
+                    None => empty += 1
+                }
+            }
+
+            if empty > 0 {
+                row.push_str(&empty.to_string());
+            }
+            rows.push(row);
+        }
+
+        format!("{} {} - - 0 1", rows.join("/"), if black_to_move { 'b' } else { 'w' })
+    }
+
+    // calls f with the id it is solved under and the board of every legal position of the
+    // endgame, or only of those that are solved under their own id
+    fn for_each_position(endgame: &Endgame, solved_only: bool, mut f: impl FnMut(usize, &Board)) {
+        let n = endgame.pieces.len();
+        let mut squares = vec![0u8; n];
+
+        for combination in 0..1usize << (6 * n) {
+            for (i, sq) in squares.iter_mut().enumerate() {
+                *sq = (combination >> (6 * i) & 63) as u8;
+            }
+
+            // pawns never stand on the first or last rank
+            let misplaced = endgame.pieces.iter().zip(squares.iter()).any(|(&code, sq)| code & 7 == 1 && !(8..56).contains(sq));
+            let overlapping = (1..n).any(|i| squares[..i].contains(&squares[i]));
+            let adjacent = bitboard::king_attacks(Square::from_bit_index(squares[0] as u32)) & 1u64 << squares[1] != 0;
+
+            if misplaced || overlapping || adjacent {
+                continue;
+            }
+
+            let ids = [false, true].map(|black| endgame.canonical(&squares, black));
+            if solved_only && ids[0] != combination * 2 {
+                continue;
+            }
+
+            let boards = [false, true].map(|black| Board::new(position_fen(&endgame.pieces, &squares, black)));
+
+            // the side that just moved cannot be in check
+            for (i, board) in boards.iter().enumerate() {
+                if !boards[1 - i].in_check() {
+                    f(ids[i], board);
+                }
+            }
+        }
+    }
+
+    fn child(endgame: &Endgame, board: &Board, mv: Move, solved: &[&Endgame]) -> Child {
+        let zeroing = is_zeroing(board, &mv);
+        let next = after_move(board, mv);
+        let black_to_move = next.get_side_to_move() == Color::Black;
+        let pieces = board_pieces(&next);
+
+        // a bare king draw once the last piece is taken
+        if pieces.len() == 2 {
+            return Child::Outer(0);
+        }
+
+        // the id of the position in an endgame with the same pieces, seen from the other side
+        // when the colors are swapped
+        let locate = |other: &Endgame, swapped: bool| -> Option<usize> {
+            if other.pieces.len() != pieces.len() {
+                return None;
+            }
+
+            let squares = other.pieces.iter().map(|&code| {
+                let code = if swapped { code ^ 8 } else { code };
+                pieces.iter().find(|&&(c, _)| c == code).map(|&(_, sq)| if swapped { sq ^ 56 } else { sq })
+            }).collect::<Option<Vec<u8>>>()?;
+
+            Some(other.canonical(&squares, black_to_move != swapped))
+        };
+
+        if let Some(id) = locate(endgame, false) {
+            return Child::Inner(id as u32, zeroing);
+        }
+
+        // a capture or a promotion, into an endgame solved before
+        solved.iter()
+            .find_map(|other| [false, true].iter().find_map(|&swapped| locate(other, swapped)).map(|id| Child::Outer(other.wdl[id].unwrap())))
+            .unwrap_or_else(|| panic!("nothing solved for {}", next.get_fen()))
+    }
+
+    fn solve(name: &str, solved: &[&Endgame]) -> Endgame {
+        let mut endgame = Endgame::new(name);
+        let positions = endgame.positions();
+
+        // the moves out of a position are children[first[id]..first[id + 1]]
+        let mut first = vec![0u32; positions + 1];
+        let mut children: Vec<Child> = Vec::new();
+        let mut wdl: Vec<Option<i32>> = vec![None; positions];
+        let mut legal = vec![false; positions];
+        let mut mated = vec![false; positions];
+
+        for_each_position(&endgame, true, |id, board| {
+            let moves = board.get_legal_moves();
+            legal[id] = true;
+
+            if moves.is_empty() {
+                mated[id] = board.in_check();
+                wdl[id] = Some(if mated[id] { -2 } else { 0 });
+            }
+
+            children.extend(moves.into_iter().map(|mv| child(&endgame, board, mv, solved)));
+            first[id + 1] = children.len() as u32;
+        });
+
+        // positions come in order, and the ones skipped have no moves
+        for id in 1..=positions {
+            first[id] = first[id].max(first[id - 1]);
+        }
+        let moves = |id: usize| &children[first[id] as usize..first[id + 1] as usize];
+
+        // a position is won when a move leads to a lost one and lost when every move leads to a
+        // won one, and whatever is left at the end is drawn
+        let value = |child: &Child, wdl: &[Option<i32>]| match *child {
+            Child::Inner(id, _) => wdl[id as usize].map(|x| -x),
+            Child::Outer(x) => Some(-x)
+        };
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            for id in 0..positions {
+                if !legal[id] || wdl[id].is_some() {
+                    continue;
+                }
+
+                if moves(id).iter().any(|c| value(c, &wdl) == Some(2)) {
+                    wdl[id] = Some(2);
+                } else if moves(id).iter().all(|c| value(c, &wdl) == Some(-2)) {
+                    wdl[id] = Some(-2);
+                } else {
+                    continue;
+                }
+
+                changed = true;
+            }
+        }
+
+        for (id, value) in wdl.iter_mut().enumerate() {
+            if legal[id] && value.is_none() {
+                *value = Some(0);
+            }
+        }
+
+        // dtz with the conventions of probe_dtz: a zeroing or mating move that keeps the win is
+        // one ply, a mated position is -1, and otherwise the winner goes for the shortest and the
+        // loser for the longest distance
+        let mut dtz = mated.iter().map(|&x| if x { -1 } else { 0 }).collect::<Vec<i32>>();
+
+        changed = true;
+        while changed {
+            changed = false;
+
+            for id in 0..positions {
+                let best = match wdl[id] {
+                    Some(2) => moves(id).iter().filter_map(|c| match *c {
+                        Child::Outer(-2) => Some(1),
+                        Child::Inner(next, zeroing) if wdl[next as usize] == Some(-2) => {
+                            let next = next as usize;
+
+                            if zeroing || mated[next] {
+                                Some(1)
+                            } else if dtz[next] < 0 {
+                                Some(1 - dtz[next])
+                            } else {
+                                None
+                            }
+                        },
+                        _ => None
+                    }).min(),
+                    Some(-2) if !mated[id] => moves(id).iter().map(|c| match *c {
+                        Child::Inner(next, false) => if dtz[next as usize] > 0 { Some(dtz[next as usize] + 1) } else { None },
+                        _ => Some(1)
+                    }).collect::<Option<Vec<i32>>>().and_then(|x| x.into_iter().max()).map(|x| -x),
+                    _ => None
+                };
+
+                if let Some(best) = best {
+                    if best != dtz[id] {
+                        dtz[id] = best;
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        // nothing here comes near the 50 move rule, so there are no cursed wins or blessed losses
+        assert!(dtz.iter().all(|x| x.abs() <= 100));
+
+        endgame.wdl = wdl;
+        endgame.dtz = dtz;
+        endgame
+    }
+
+    // huffman code lengths for symbols seen the given number of times
+    fn code_lengths(counts: &[u64]) -> Vec<u8> {
+        let mut heap = counts.iter().enumerate().map(|(i, &count)| Reverse((count, i))).collect::<BinaryHeap<_>>();
+        let mut parent = vec![usize::MAX; counts.len()];
+
+        while heap.len() > 1 {
+            let Reverse((a, i)) = heap.pop().unwrap();
+            let Reverse((b, j)) = heap.pop().unwrap();
+            parent[i] = parent.len();
+            parent[j] = parent.len();
+            heap.push(Reverse((a + b, parent.len())));
+            parent.push(usize::MAX);
+        }
+
+        (0..counts.len())
+            .map(|mut node| {
+                let mut len = 0;
+                while parent[node] != usize::MAX {
+                    node = parent[node];
+                    len += 1;
+                }
+                len
+            })
+            .collect()
+    }
+
+    // one compressed sub table, in the parts that go to different places in the file
+    struct Encoded {
+        sizes: Vec<u8>,
+        sparse_index: Vec<u8>,
+        block_lengths: Vec<u8>,
+        data: Vec<u8>
+    }
+
+    fn encode(values: &[u32], flags: u8) -> Encoded {
+        const BLOCK_SIZE: u8 = 10;
+        const SPAN: u8 = 10;
+        // pair symbols are made while some pair of neighbours is seen this often, up to this many
+        const MIN_PAIRS: u32 = 8;
+        const MAX_PAIRS: usize = 255;
+
+        let mut leaves = values.to_vec();
+        leaves.sort_unstable();
+        leaves.dedup();
+
+        if leaves.len() == 1 {
+            let sizes = vec![FLAG_SINGLE_VALUE | flags, leaves[0] as u8];
+            return Encoded { sizes, sparse_index: Vec::new(), block_lengths: Vec::new(), data: Vec::new() };
+        }
+
+        // the first symbols are the values themselves, and every symbol after them stands for a
+        // pair of symbols before it: the most common pair of neighbours becomes a new symbol, over
+        // and over. a symbol covers at most 256 values, which is all the decoder can count
+        let mut stream = values.iter().map(|value| leaves.binary_search(value).unwrap()).collect::<Vec<usize>>();
+        let mut pairs: Vec<(usize, usize)> = Vec::new();
+        let mut covers = vec![1; leaves.len()];
+
+        while pairs.len() < MAX_PAIRS {
+            let symbols = covers.len();
+            let mut counts = vec![0u32; symbols * symbols];
+            for pair in stream.windows(2) {
+                counts[pair[0] * symbols + pair[1]] += 1;
+            }
+
+            let best = (0..counts.len())
+                .filter(|&i| covers[i / symbols] + covers[i % symbols] <= 256)
+                .max_by_key(|&i| (counts[i], Reverse(i)))
+                .filter(|&i| counts[i] >= MIN_PAIRS);
+
+            let (left, right) = match best {
+                Some(i) => (i / symbols, i % symbols),
+                None => break
+            };
+
+            let mut replaced = Vec::with_capacity(stream.len());
+            let mut i = 0;
+            while i < stream.len() {
+                if stream[i] == left && stream.get(i + 1) == Some(&right) {
+                    replaced.push(symbols);
+                    i += 2;
+                } else {
+                    replaced.push(stream[i]);
+                    i += 1;
+                }
+            }
+
+            stream = replaced;
+            pairs.push((left, right));
+            covers.push(covers[left] + covers[right]);
+        }
+
+        let symbols = covers.len();
+        let mut counts = vec![0; symbols];
+        for &sym in stream.iter() {
+            counts[sym] += 1;
+        }
+
+        // symbols are numbered from the longest codes to the shortest as the decoder expects, and
+        // codes of each length count up from where the longer ones ended. every symbol needs a
+        // code, even one only used inside others, and counting those once keeps the codes short
+        let lengths = code_lengths(&counts.iter().map(|&count: &u64| count.max(1)).collect::<Vec<u64>>());
+        let (min_len, max_len) = (*lengths.iter().min().unwrap(), *lengths.iter().max().unwrap());
+        assert!(max_len <= 32);
+
+        let mut order = (0..symbols).collect::<Vec<usize>>();
+        order.sort_by_key(|&i| (Reverse(lengths[i]), i));
+
+        let mut rank = vec![0; symbols];
+        for (r, &i) in order.iter().enumerate() {
+            rank[i] = r;
+        }
+
+        let mut codes = vec![0u64; symbols];
+        let mut code = 0u64;
+        let mut len = max_len;
+
+        for &i in order.iter() {
+            code >>= len - lengths[i];
+            len = lengths[i];
+            codes[i] = code;
+            code += 1;
+        }
+
+        let mut sizes = vec![flags, BLOCK_SIZE, SPAN, 0, 0, 0, 0, 0, max_len, min_len];
+
+        for len in min_len..=max_len {
+            let longer = lengths.iter().filter(|&&x| x > len).count() as u16;
+            sizes.extend_from_slice(&longer.to_le_bytes());
+        }
+
+        // a value is a left half of 12 bits with a right half of 0xfff, a pair the two symbols
+        sizes.extend_from_slice(&(symbols as u16).to_le_bytes());
+        for &i in order.iter() {
+            let (left, right) = match i.checked_sub(leaves.len()) {
+                Some(pair) => (rank[pairs[pair].0], rank[pairs[pair].1]),
+                None => (leaves[i] as usize, 0xfff)
+            };
+            sizes.extend_from_slice(&[left as u8, (left >> 8) as u8 | (right << 4) as u8, (right >> 4) as u8]);
+        }
+        if symbols % 2 == 1 {
+            sizes.push(0);
+        }
+
+        // fill blocks with whole symbols, as many values as a block length can count at most
+        let block_bits = 8 << BLOCK_SIZE;
+        let mut data = Vec::new();
+        let mut block_lengths = Vec::new();
+        let mut starts = vec![0];
+        let mut block = vec![0u8; block_bits / 8];
+        let mut bit = 0;
+        let mut idx = 0;
+
+        for &sym in stream.iter() {
+            let len = lengths[sym] as usize;
+
+            if bit + len > block_bits || idx + covers[sym] - starts.last().unwrap() > 1 << 16 {
+                data.append(&mut block);
+                block = vec![0u8; block_bits / 8];
+                block_lengths.extend_from_slice(&((idx - starts.last().unwrap() - 1) as u16).to_le_bytes());
+                starts.push(idx);
+                bit = 0;
+            }
+
+            for j in 0..len {
+                if codes[sym] >> (len - 1 - j) & 1 != 0 {
+                    block[(bit + j) / 8] |= 0x80 >> ((bit + j) % 8);
+                }
+            }
+            bit += len;
+            idx += covers[sym];
+        }
+
+        data.append(&mut block);
+        block_lengths.extend_from_slice(&((values.len() - starts.last().unwrap() - 1) as u16).to_le_bytes());
+        sizes[4..8].copy_from_slice(&(starts.len() as u32).to_le_bytes());
+
+        // every sparse index entry points at the value in the middle of its span
+        let span = 1usize << SPAN;
+        let mut sparse_index = Vec::new();
+
+        for k in 0..values.len().div_ceil(span) {
+            let target = k * span + span / 2;
+            let block = starts.iter().rposition(|&start| start <= target).unwrap();
+            sparse_index.extend_from_slice(&(block as u32).to_le_bytes());
+            sparse_index.extend_from_slice(&((target - starts[block]) as u16).to_le_bytes());
+        }
+
+        Encoded { sizes, sparse_index, block_lengths, data }
+    }
+
+    // lays the sub tables out after the header the way Table::parse reads them
+    fn table_file(header: &[u8], items: &[Encoded], kind: TableKind) -> Vec<u8> {
+        let mut bytes = header.to_vec();
+
+        for item in items.iter() {
+            bytes.extend_from_slice(&item.sizes);
+        }
+        if kind == TableKind::Dtz && bytes.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        for item in items.iter() {
+            bytes.extend_from_slice(&item.sparse_index);
+        }
+        for item in items.iter() {
+            bytes.extend_from_slice(&item.block_lengths);
+        }
+        for item in items.iter() {
+            bytes.resize((bytes.len() + 0x3f) & !0x3f, 0);
+            bytes.extend_from_slice(&item.data);
+        }
+
+        bytes
+    }
+
+    fn write_tables(endgame: &Endgame, dir: &Path) {
+        let name = &endgame.name;
+        let pawns = endgame.has_pawns();
+        let files = if pawns { 4 } else { 1 };
+
+        // split and pawn flags, then per file the group order and the pieces of both sides to
+        // move: the leading pawn first in pawn tables, then white's king and pieces and black's
+        let is_pawn = |code: &&u8| **code & 7 == 1;
+        let (kings, others) = endgame.pieces.split_at(2);
+        let pieces = others.iter().filter(is_pawn)
+            .chain(kings[..1].iter())
+            .chain(others.iter().filter(|code| !is_pawn(code) && **code & 8 == 0))
+            .chain(kings[1..].iter())
+            .chain(others.iter().filter(|code| !is_pawn(code) && **code & 8 != 0))
+            .collect::<Vec<&u8>>();
+        let mut header = vec![if pawns { 3 } else { 1 }];
+
+        for _ in 0..files {
+            header.push(0);
+            header.extend(pieces.iter().map(|&&x| x | x << 4));
+        }
+        if header.len() % 2 == 1 {
+            header.push(0);
+        }
+
+        let kinds = [
+            (TableKind::Wdl, WDL_MAGIC, "rtbw", 0),
+            (TableKind::Dtz, DTZ_MAGIC, "rtbz", FLAG_WIN_PLIES | FLAG_LOSS_PLIES)
+        ];
+
+        // single value sub tables are enough to work out the indexes
+        let tables = kinds.map(|(kind, magic, _, flags)| {
+            let mut bytes = magic.to_vec();
+            bytes.extend_from_slice(&header);
+
+            let sides = if kind == TableKind::Wdl { 2 } else { 1 };
+            for _ in 0..files * sides {
+                bytes.extend_from_slice(&[FLAG_SINGLE_VALUE | flags, 0]);
+            }
+
+            Table::new(kind, name, bytes.into()).unwrap()
+        });
+        let mut values = tables.each_ref().map(|table| table.items.iter()
+            .map(|side| side.iter().map(|d| vec![None; d.size() as usize]).collect::<Vec<_>>())
+            .collect::<Vec<_>>());
+
+        for_each_position(endgame, false, |id, board| {
+            let wdl = endgame.wdl[id].unwrap();
+
+            for (table, values) in tables.iter().zip(values.iter_mut()) {
+                // the dtz table stores wins and losses with white to move, as plies less one
+                let value = match table.kind {
+                    TableKind::Wdl => (wdl + 2) as u32,
+                    TableKind::Dtz if wdl != 0 => (endgame.dtz[id].abs() - 1) as u32,
+                    TableKind::Dtz => continue
+                };
+
+                match position_index(board, table, false) {
+                    Some(Slot::Index(stm, file, idx)) => {
+                        let slot = &mut values[stm][file][idx as usize];
+                        assert!(slot.is_none() || *slot == Some(value), "{} disagrees with a symmetric position", board.get_fen());
+                        *slot = Some(value);
+                    },
+                    Some(Slot::ChangeStm) if table.kind == TableKind::Dtz => {},
+                    _ => panic!("no index for {}", board.get_fen())
+                }
+            }
+        });
+
+        for ((&(kind, magic, extension, flags), table), values) in kinds.iter().zip(tables.iter()).zip(values.iter()) {
+            // indexes no legal position maps to get the most common value
+            let filled = values.iter()
+                .map(|side| side.iter().map(|values| {
+                    let mut known = values.iter().flatten().copied().collect::<Vec<u32>>();
+                    known.sort_unstable();
+                    let common = known.chunk_by(|a, b| a == b).max_by_key(|run| run.len()).map_or(0, |run| run[0]);
+                    values.iter().map(|x| x.unwrap_or(common)).collect::<Vec<u32>>()
+                }).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+
+            let mut start = magic.to_vec();
+            start.extend_from_slice(&header);
+
+            let items = (0..files)
+                .flat_map(|f| filled.iter().map(move |side| encode(&side[f], flags)))
+                .collect::<Vec<_>>();
+            let bytes = table_file(&start, &items, kind);
+
+            let table = Table::new(table.kind, name, bytes.clone().into()).unwrap();
+            for (stm, side) in filled.iter().enumerate() {
+                for (file, values) in side.iter().enumerate() {
+                    for (idx, &value) in values.iter().enumerate() {
+                        assert_eq!(table.decompress_pairs(&table.items[stm][file], idx as u64), Some(value as i32));
+                    }
+                }
+            }
+
+            fs::write(dir.join(format!("{}.{}", name, extension)), bytes).unwrap();
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn write_fixture_tables() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/syzygy");
+
+        // the minor pieces are draws throughout, but probing KPvK looks at underpromotions too
+        let queen = solve("KQvK", &[]);
+        let rook = solve("KRvK", &[]);
+        let bishop = solve("KBvK", &[]);
+        let knight = solve("KNvK", &[]);
+        let pawn = solve("KPvK", &[&queen, &rook, &bishop, &knight]);
+        let queen_rook = solve("KQvKR", &[&queen, &rook]);
+
+        for endgame in [&queen, &rook, &bishop, &knight, &pawn, &queen_rook].iter() {
+            write_tables(endgame, &dir);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+// Time management for games played on a clock
+//
+// A search on the clock gets two limits. The soft limit is what a move should normally take: no
+// new iteration is started once it has passed. The hard limit is the most a move may ever take
+// and stops the search in the middle of an iteration. Both come from the time left, spread over
+// the moves still to play, plus most of the increment, minus the overhead of getting the move
+// to the board.
+//
+// The soft limit then moves with the search: it shrinks while the best move stays the same
+// iteration after iteration and grows when the best move keeps changing or the score drops, up
+// to the hard limit.
+
+use crate::Move;
+
+// moves the time left is spread over when the time control does not say
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+
+// the most of the time left a single move may take, in percent, with the soft limit at most half
+const MAX_SOFT_PERCENT: u64 = 50;
+const MAX_HARD_PERCENT: u64 = 80;
+
+// how many times the normal share of time a move may take when the search is unsettled
+const HARD_FACTOR: u64 = 4;
+
+// a score drop of this many centipawns between iterations counts as a sign of trouble
+const SCORE_DROP: i32 = 30;
+
+// the clock as a UCI go command gives it, in milliseconds for the side to move
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct TimeControl {
+    pub remaining: u64,
+    pub increment: u64,
+    // moves until the next time control, None when the rest of the game is played on this clock
+    pub movestogo: Option<u32>,
+    // time lost between the search finishing and the move being on the board
+    pub overhead: u64
+}
+
+// decides how long each move may take, see the top of the file
+#[derive(Clone, Debug)]
+pub struct TimeManager {
+    soft: u64,
+    hard: u64,
+
+    // how much of the soft limit to use, in percent, from how settled the search looks
+    scale: u64,
+
+    best_move: Option<Move>,
+    // iterations in a row that the best move has stayed the same
+    stable: u32,
+    // best move changes, halved every iteration so that recent ones count the most
+    changes: u32,
+    score: Option<i32>
+}
+
+impl TimeManager {
+    pub fn new(control: &TimeControl) -> TimeManager {
+        let available = control.remaining.saturating_sub(control.overhead);
+        let moves = control.movestogo.map_or(DEFAULT_MOVES_TO_GO, |moves| moves.max(1) as u64);
+
+        // the increment comes back after the move, but counting all of it would leave nothing
+        // in hand when the clock gets low
+        let share = available / moves + control.increment * 3 / 4;
+
+        let soft = share.min(available * MAX_SOFT_PERCENT / 100);
+        let hard = (share * HARD_FACTOR).min(available * MAX_HARD_PERCENT / 100).max(soft);
+
+        TimeManager {
+            soft,
+            hard,
+            scale: 100,
+            best_move: None,
+            stable: 0,
+            changes: 0,
+            score: None
+        }
+    }
+
+    // the time to stop in the middle of an iteration, in milliseconds
+    pub fn hard_limit(&self) -> u64 {
+        self.hard
+    }
+
+    // the time after which no new iteration is started, in milliseconds
+    pub fn soft_limit(&self) -> u64 {
+        (self.soft * self.scale / 100).min(self.hard)
+    }
+
+    // takes in the result of a finished iteration and adjusts the soft limit
+    pub fn update(&mut self, best_move: Move, score: i32) {
+        self.changes /= 2;
+
+        if self.best_move == Some(best_move) {
+            self.stable += 1;
+        } else {
+            if self.best_move.is_some() { self.changes += 2; }
+            self.stable = 0;
+        }
+
+        // a best move that has held for a while is unlikely to change, one that keeps
+        // changing needs more looking at
+        let stability = match self.stable {
+            0..=1 => 100,
+            2..=3 => 85,
+            _ => 70
+        } + 30 * self.changes as u64;
+
+        // a falling score means the position is worse than it looked, which is worth time
+        let drop = self.score.map_or(0, |previous| previous - score);
+        let trouble = if drop >= SCORE_DROP { 100 + (drop.min(150) as u64) } else { 100 };
+
+        self.scale = stability * trouble / 100;
+        self.best_move = Some(best_move);
+        self.score = Some(score);
+    }
+
+    // whether a search that has been going for elapsed milliseconds should not start another
+    // iteration
+    pub fn out_of_time(&self, elapsed: u64) -> bool {
+        elapsed >= self.soft_limit()
+    }
+}
@@ -0,0 +1,144 @@
+// Transposition table
+//
+// Remembers what the search found out about the positions it has been through, by their hash:
+// the best move, the score and the depth it was searched to. Finding a position again, by a
+// different move order or in the next iteration, either settles it straight away or at least
+// says which move to try first.
+//
+// The table is shared by all the search threads without any locking. Each slot is two atomic
+// words, the entry packed into one and the hash xored with it in the other. Two threads writing
+// the same slot at once can leave the words from different entries, but then the hash no longer
+// comes out right and the slot just reads as empty.
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use crate::Move;
+
+// what the score of an entry says about the position's real score
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Bound {
+    Exact,
+    // the real score is at least this, the search failed high
+    Lower,
+    // the real score is at most this, no move reached alpha
+    Upper
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Entry {
+    pub best_move: Option<Move>,
+    pub score: i32,
+    pub depth: u32,
+    pub bound: Bound
+}
+
+// the packed entry:
+//
+// bits 0-15 the best move, 0 for none
+// bits 16-31 the score
+// bits 32-39 the depth
+// bits 40-41 the bound, 0 for an empty slot
+// bits 42-49 the search the entry was stored in
+struct Slot {
+    check: AtomicU64,
+    data: AtomicU64
+}
+
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+    // counts searches, so entries left from earlier ones are replaced first
+    generation: AtomicU8
+}
+
+impl TranspositionTable {
+    // a table taking up about the given number of megabytes, with at least one slot
+    pub fn new(megabytes: usize) -> TranspositionTable {
+        let count = (megabytes * 1024 * 1024 / core::mem::size_of::<Slot>()).max(1);
+
+        TranspositionTable {
+            slots: (0..count).map(|_| Slot { check: AtomicU64::new(0), data: AtomicU64::new(0) }).collect(),
+            generation: AtomicU8::new(0)
+        }
+    }
+
+    // forgets every entry, e.g. for a new game
+    pub fn clear(&self) {
+        for slot in self.slots.iter() {
+            slot.check.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+    }
+
+    // starts a new search, making what earlier ones stored the first to go
+    pub fn new_search(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn slot(&self, hash: u64) -> &Slot {
+        // the high bits of the product pick a slot evenly for any table size
+        &self.slots[((hash as u128 * self.slots.len() as u128) >> 64) as usize]
+    }
+
+    pub fn probe(&self, hash: u64) -> Option<Entry> {
+        let slot = self.slot(hash);
+        let data = slot.data.load(Ordering::Relaxed);
+
+        if slot.check.load(Ordering::Relaxed) ^ data != hash { return None; }
+
+        let bound = match (data >> 40) & 3 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            3 => Bound::Upper,
+            _ => return None
+        };
+
+        Some(Entry {
+            best_move: unpack_move(data),
+            score: (data >> 16) as u16 as i16 as i32,
+            depth: ((data >> 32) & 0xff) as u32,
+            bound
+        })
+    }
+
+    // stores the entry unless the slot holds a deeper search of the same position from this
+    // search, or a much deeper one of another position
+    pub fn store(&self, hash: u64, entry: Entry) {
+        let slot = self.slot(hash);
+        let generation = self.generation.load(Ordering::Relaxed);
+
+        let old = slot.data.load(Ordering::Relaxed);
+        let old_depth = ((old >> 32) & 0xff) as u32;
+        let old_generation = (old >> 42) as u8;
+        let same = slot.check.load(Ordering::Relaxed) ^ old == hash;
+
+        if old_generation == generation && old_depth > entry.depth + if same { 0 } else { 2 } {
+            return;
+        }
+
+        // a new entry without a move keeps the one the position already had
+        let best_move = entry.best_move.or(if same { unpack_move(old) } else { None });
+        let bound = match entry.bound {
+            Bound::Exact => 1,
+            Bound::Lower => 2,
+            Bound::Upper => 3
+        };
+
+        let data = best_move.map_or(0, |mv| mv.bits() as u64)
+            | (entry.score as i16 as u16 as u64) << 16
+            | (entry.depth.min(255) as u64) << 32
+            | bound << 40
+            | (generation as u64) << 42;
+
+        slot.check.store(hash ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+}
+
+// the best move of a packed entry
+fn unpack_move(data: u64) -> Option<Move> {
+    match data as u16 {
+        0 => None,
+        bits => Move::from_bits(bits)
+    }
+}
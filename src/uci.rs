@@ -0,0 +1,385 @@
+// Universal Chess Interface
+//
+// The text protocol chess GUIs use to talk to engines: commands come in one per line and the
+// engine answers with lines of its own. Searches run on their own thread so that stop, isready
+// and quit are still answered while the engine thinks. Supported commands:
+//
+//   uci                                   identify the engine and list its options
+//   setoption name NAME value VALUE
+//   isready                               answered with readyok
+//   ucinewgame                            forget the last game
+//   position [startpos | fen FEN] [moves M...]
+//   go [depth N] [nodes N] [movetime MS] [infinite]
+//      [wtime MS] [btime MS] [winc MS] [binc MS] [movestogo N]
+//   stop                                  stop searching and report the best move so far
+//   quit
+//
+// With wtime and btime the engine is on the clock and decides for itself how long to think.
+//
+// The Threads option spreads the search over more threads, and Hash sets the size of the
+// transposition table in megabytes; the table is kept from one search to the next and cleared on
+// ucinewgame. MultiPV sets how many lines to report, each with its own info line every
+// iteration.
+//
+// Search switches between the alpha-beta search and Monte Carlo tree search. The tree search
+// runs on one thread and reports one line; it keeps its tree from one go to the next while the
+// game goes on, and nodes counts its playouts. It is guided by the network in WeightsFile, or
+// by the heuristic evaluator when that is empty.
+//
+// EvalFile is an NNUE network for the alpha-beta search to evaluate positions with, in place of
+// the hand-crafted evaluation, which is used again when the option is set to <empty>.
+//
+// While searching, each finished iteration is reported as an info line with the score and the
+// principal variation, and the search ends with bestmove and the expected reply to ponder on.
+
+use std::boxed::Box;
+use std::format;
+use std::io::{self, BufRead, Write};
+use std::string::{String, ToString};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::vec::Vec;
+
+use crate::mcts::{Evaluator, HeuristicEvaluator, Mcts, MctsConfig};
+use crate::nn::{Network, NetworkEvaluator};
+use crate::nnue;
+use crate::search::{self, SearchInfo, SearchLimits, SearchOptions, DEFAULT_HASH};
+use crate::time::TimeControl;
+use crate::tt::TranspositionTable;
+use crate::{Board, Color};
+
+const STARTPOS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+// milliseconds kept back on every move for the GUI and the operating system
+const DEFAULT_OVERHEAD: u64 = 30;
+const MAX_OVERHEAD: u64 = 5000;
+
+const MAX_THREADS: usize = 256;
+const MAX_MULTIPV: usize = 256;
+const MAX_HASH: usize = 65536;
+
+// where the engine's answers go, shared with the search thread
+pub type Output = Arc<Mutex<dyn Write + Send>>;
+
+pub struct Uci {
+    board: Board,
+    // the hashes of the positions before it, for the tree search to spot repetitions
+    history: Vec<u64>,
+    output: Output,
+
+    // the Move Overhead, Threads, MultiPV, Hash, Search and EvalFile options; WeightsFile goes
+    // into the tree search's evaluator
+    overhead: u64,
+    options: SearchOptions,
+    tt: Arc<TranspositionTable>,
+    tree_search: bool,
+
+    // the tree search, with the tree from the last go
+    mcts: Arc<Mutex<Mcts<Box<dyn Evaluator + Send>>>>,
+
+    // the running search, the flag that stops it and whether it is go infinite
+    search: Option<(JoinHandle<()>, Arc<AtomicBool>, bool)>
+}
+
+impl Uci {
+    // an engine answering on stdout
+    pub fn new() -> Uci {
+        Uci::with_output(Arc::new(Mutex::new(io::stdout())))
+    }
+
+    pub fn with_output(output: Output) -> Uci {
+        Uci {
+            board: Board::default(),
+            history: Vec::new(),
+            output,
+            overhead: DEFAULT_OVERHEAD,
+            options: SearchOptions::default(),
+            tt: Arc::new(TranspositionTable::new(DEFAULT_HASH)),
+            tree_search: false,
+            mcts: Arc::new(Mutex::new(Mcts::new(&Board::default(), Box::new(HeuristicEvaluator), MctsConfig::default()))),
+            search: None
+        }
+    }
+
+    fn send(&self, line: &str) {
+        send(&self.output, line);
+    }
+
+    // runs one command, returns false on quit
+    pub fn execute(&mut self, line: &str) -> bool {
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        match words.as_slice() {
+            [] => {},
+            ["uci"] => {
+                self.send(&format!("id name alpha-rust {}", env!("CARGO_PKG_VERSION")));
+                self.send(&format!("id author {}", env!("CARGO_PKG_AUTHORS")));
+                self.send(&format!("option name Move Overhead type spin default {} min 0 max {}", DEFAULT_OVERHEAD, MAX_OVERHEAD));
+                self.send(&format!("option name Threads type spin default 1 min 1 max {}", MAX_THREADS));
+                self.send(&format!("option name MultiPV type spin default 1 min 1 max {}", MAX_MULTIPV));
+                self.send(&format!("option name Hash type spin default {} min 1 max {}", DEFAULT_HASH, MAX_HASH));
+                self.send("option name Search type combo default AlphaBeta var AlphaBeta var MCTS");
+                self.send("option name WeightsFile type string default <empty>");
+                self.send("option name EvalFile type string default <empty>");
+                self.send("uciok");
+            },
+            ["setoption", "name", args @ ..] => self.set_option(args),
+            ["isready"] => self.send("readyok"),
+            ["ucinewgame"] => {
+                self.stop();
+                self.board = Board::default();
+                self.history.clear();
+                self.tt.clear();
+                if let Ok(mut mcts) = self.mcts.lock() { mcts.clear(); }
+            },
+            ["position", args @ ..] => match parse_position_with_history(args) {
+                Ok((board, history)) => {
+                    self.board = board;
+                    self.history = history;
+                },
+                Err(e) => self.send(&format!("info string {}", e))
+            },
+            ["go", args @ ..] => match parse_go(args, self.board.get_side_to_move()) {
+                Some(mut limits) => {
+                    if let Some(clock) = limits.clock.as_mut() {
+                        clock.overhead = self.overhead;
+                    }
+                    self.go(limits)
+                },
+                None => self.send(&format!("info string bad go command: {}", line))
+            },
+            ["stop"] => self.stop(),
+            ["quit"] => {
+                self.stop();
+                return false;
+            },
+            _ => self.send(&format!("info string unknown command: {}", line))
+        }
+
+        true
+    }
+
+    // option names can have spaces in them, so everything up to "value" is the name
+    fn set_option(&mut self, args: &[&str]) {
+        let value_at = args.iter().position(|word| *word == "value").unwrap_or(args.len());
+        let name = args[..value_at].join(" ");
+        let value = args.get(value_at + 1..).map(|words| words.join(" ")).unwrap_or_default();
+
+        match name.to_ascii_lowercase().as_str() {
+            "move overhead" => match value.parse::<u64>() {
+                Ok(ms) if ms <= MAX_OVERHEAD => self.overhead = ms,
+                _ => self.send(&format!("info string bad value for Move Overhead: {}", value))
+            },
+            "threads" => match value.parse::<usize>() {
+                Ok(threads) if (1..=MAX_THREADS).contains(&threads) => self.options.threads = threads,
+                _ => self.send(&format!("info string bad value for Threads: {}", value))
+            },
+            "multipv" => match value.parse::<usize>() {
+                Ok(lines) if (1..=MAX_MULTIPV).contains(&lines) => self.options.multipv = lines,
+                _ => self.send(&format!("info string bad value for MultiPV: {}", value))
+            },
+            // a search still running keeps the table it started with
+            "hash" => match value.parse::<usize>() {
+                Ok(megabytes) if (1..=MAX_HASH).contains(&megabytes) => self.tt = Arc::new(TranspositionTable::new(megabytes)),
+                _ => self.send(&format!("info string bad value for Hash: {}", value))
+            },
+            "search" => match value.to_ascii_lowercase().as_str() {
+                "alphabeta" => self.tree_search = false,
+                "mcts" => self.tree_search = true,
+                _ => self.send(&format!("info string bad value for Search: {}", value))
+            },
+            "weightsfile" => match load_evaluator(&value) {
+                Ok(evaluator) => {
+                    self.stop();
+                    self.mcts = Arc::new(Mutex::new(Mcts::new(&self.board, evaluator, MctsConfig::default())));
+                },
+                Err(e) => self.send(&format!("info string could not load {}: {}", value, e))
+            },
+            // a search still running keeps the network it started with
+            "evalfile" => match load_nnue(&value) {
+                Ok(network) => self.options.nnue = network,
+                Err(e) => self.send(&format!("info string could not load {}: {}", value, e))
+            },
+            _ => self.send(&format!("info string unknown option: {}", name))
+        }
+    }
+
+    // starts searching the current position on another thread
+    fn go(&mut self, limits: SearchLimits) {
+        self.stop();
+
+        let board = self.board.clone();
+        let history = self.history.clone();
+        let tt = Arc::clone(&self.tt);
+        let options = self.options.clone();
+        let mcts = self.tree_search.then(|| Arc::clone(&self.mcts));
+        let output = Arc::clone(&self.output);
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&stop);
+
+        let handle = thread::spawn(move || {
+            let stopped = || flag.load(Ordering::Relaxed);
+            let mut report = |info: &SearchInfo| send(&output, &format!("info {}", info));
+
+            let result = match mcts.as_ref().and_then(|mcts| mcts.lock().ok()) {
+                Some(mut mcts) => {
+                    mcts.set_position(&board, &history);
+                    mcts.search(&limits, &stopped, &mut report)
+                },
+                None => search::search_with(&board, &history, &limits, &tt, &options, &stopped, &mut report)
+            };
+
+            // go infinite keeps its answer until stop, which unparks the thread
+            while limits.infinite && !stopped() {
+                thread::park();
+            }
+
+            match result {
+                Some(result) => send(&output, &result.to_string()),
+                // stopped before the first iteration finished, any legal move beats none; UCI has
+                // no way of saying there is no move at all, 0000 is the null move
+                None => match board.get_legal_moves().first() {
+                    Some(mv) => send(&output, &format!("bestmove {}", mv)),
+                    None => send(&output, "bestmove 0000")
+                }
+            }
+        });
+
+        self.search = Some((handle, stop, limits.infinite));
+    }
+
+    // stops the search if there is one, once it has sent its best move
+    pub fn stop(&mut self) {
+        if let Some((handle, stop, _)) = self.search.as_ref() {
+            stop.store(true, Ordering::Relaxed);
+            handle.thread().unpark();
+        }
+        self.wait();
+    }
+
+    // waits for the search to finish by itself; go infinite never does, so it is stopped
+    pub fn wait(&mut self) {
+        if let Some((handle, stop, infinite)) = self.search.take() {
+            if infinite {
+                stop.store(true, Ordering::Relaxed);
+                handle.thread().unpark();
+            }
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Default for Uci {
+    fn default() -> Self {
+        Uci::new()
+    }
+}
+
+// the network in a weights file, or the heuristic evaluator for none
+fn load_evaluator(path: &str) -> Result<Box<dyn Evaluator + Send>, String> {
+    if path.is_empty() || path == "<empty>" {
+        return Ok(Box::new(HeuristicEvaluator));
+    }
+
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    let network = Network::from_bytes(&bytes).map_err(|e| e.to_string())?;
+
+    Ok(Box::new(NetworkEvaluator::new(network)))
+}
+
+// the NNUE network in a file, or none for the hand-crafted evaluation
+fn load_nnue(path: &str) -> Result<Option<Arc<nnue::Network>>, String> {
+    if path.is_empty() || path == "<empty>" {
+        return Ok(None);
+    }
+
+    nnue::Network::load(path).map(|network| Some(Arc::new(network))).map_err(|e| e.to_string())
+}
+
+fn send(output: &Output, line: &str) {
+    // a GUI that has gone away cannot be told about it
+    if let Ok(mut out) = output.lock() {
+        let _ = writeln!(out, "{}", line);
+        let _ = out.flush();
+    }
+}
+
+// the position after "position": startpos or a FEN, then optionally the moves played from it
+pub fn parse_position(args: &[&str]) -> Result<Board, String> {
+    parse_position_with_history(args).map(|(board, _)| board)
+}
+
+// the position after "position" and the hashes of the positions on the way to it, oldest first
+pub fn parse_position_with_history(args: &[&str]) -> Result<(Board, Vec<u64>), String> {
+    let moves_at = args.iter().position(|word| *word == "moves").unwrap_or(args.len());
+
+    let mut board = match &args[..moves_at] {
+        ["startpos"] => Board::new(STARTPOS.to_string()),
+        ["fen", fen @ ..] => Board::from_fen(&fen.join(" ")).map_err(|e| e.to_string())?,
+        _ => return Err(format!("bad position command: position {}", args.join(" ")))
+    };
+
+    let mut history = Vec::new();
+    for text in args.iter().skip(moves_at + 1) {
+        let mv = text.parse().map_err(|_| format!("invalid move {}", text))?;
+        history.push(board.hash());
+        if !board.play_move(mv) {
+            return Err(format!("illegal move {}", text));
+        }
+    }
+
+    Ok((board, history))
+}
+
+// the limits after "go" for the given side to move; a go without limits searches until stopped
+// or until there is nothing left to search, go infinite answers only once stopped
+pub fn parse_go(args: &[&str], side: Color) -> Option<SearchLimits> {
+    let mut limits = SearchLimits::default();
+    let mut clock = TimeControl::default();
+    let mut on_clock = false;
+    let mut words = args.iter();
+
+    while let Some(word) = words.next() {
+        match (*word, side) {
+            ("depth", _) => limits.depth = Some(words.next()?.parse().ok()?),
+            ("nodes", _) => limits.nodes = Some(words.next()?.parse().ok()?),
+            ("movetime", _) => limits.movetime = Some(words.next()?.parse().ok()?),
+            ("infinite", _) => limits.infinite = true,
+            ("movestogo", _) => clock.movestogo = Some(words.next()?.parse().ok()?),
+
+            // only the clock of the side to move matters, but the other one still has to be read
+            ("wtime", Color::White) | ("btime", Color::Black) => {
+                clock.remaining = parse_time(words.next()?)?;
+                on_clock = true;
+            },
+            ("winc", Color::White) | ("binc", Color::Black) => clock.increment = parse_time(words.next()?)?,
+            ("wtime", _) | ("btime", _) | ("winc", _) | ("binc", _) => { parse_time(words.next()?)?; },
+            _ => return None
+        }
+    }
+
+    if on_clock { limits.clock = Some(clock); }
+
+    Some(limits)
+}
+
+// a time in milliseconds; some GUIs send negative times once the clock has run out
+fn parse_time(word: &str) -> Option<u64> {
+    word.parse::<i64>().ok().map(|ms| ms.max(0) as u64)
+}
+
+// talks UCI on stdin and stdout until quit or the end of the input
+pub fn run() {
+    let mut uci = Uci::new();
+
+    for line in io::stdin().lock().lines() {
+        match line {
+            Ok(line) => if !uci.execute(line.trim()) { return; },
+            Err(_) => break
+        }
+    }
+
+    // input piped in from a script ends right after its go, so the search is left to finish
+    uci.wait();
+}
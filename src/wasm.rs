@@ -0,0 +1,480 @@
+// JavaScript bindings, behind the wasm feature
+//
+// The core types stay plain Rust, and everything JavaScript sees is defined here: small enums
+// mirroring Color, PieceType and GameStatus, a Piece with getters, the BoardSingleton that holds
+// a game for the page, and analyze with its Limits for running the search in a web worker.
+//
+// Complex values (vecs of structs, Options of structs, closures) do not cross the boundary well,
+// so squares and moves go back and forth as strings in algebraic and UCI notation, and the
+// search reports plain objects described by the TypeScript definitions below.
+
+use std::format;
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::search::{self, SearchInfo, SearchLimits, SearchOptions, SearchResult, DEFAULT_HASH};
+use crate::time::TimeControl;
+use crate::tt::TranspositionTable;
+use crate::{piece_char, svg, Board, Move, Square};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPESCRIPT: &'static str = r#"
+/** Progress of a search, reported after every finished iteration. */
+export interface SearchInfo {
+    /** Depth of the iteration in plies. */
+    depth: number;
+    /** Rank of the line among those searched, 1 for the best. */
+    multipv: number;
+    /** Deepest ply reached, captures searched at the end of the lines included. */
+    seldepth: number;
+    /** Score in centipawns from the side to move's point of view. */
+    score: number;
+    /** Moves to mate, negative when the side to move is getting mated, null without a mate. */
+    mate: number | null;
+    nodes: number;
+    nps: number;
+    /** Milliseconds since the search started. */
+    time: number;
+    /** Principal variation in UCI notation, separated by spaces. */
+    pv: string;
+}
+
+/** What a search found, from the last iteration it finished. */
+export interface SearchResult extends SearchInfo {
+    /** Best move in UCI notation, the first move of pv. */
+    bestmove: string;
+    /** Expected reply in UCI notation, null if the line ends after the best move. */
+    ponder: string | null;
+    /** Every line of the last iteration, best first, more than one when multipv asks for it. */
+    lines: SearchInfo[];
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "(info: SearchInfo) => void")]
+    pub type SearchInfoCallback;
+
+    #[wasm_bindgen(typescript_type = "SearchResult")]
+    pub type SearchResultObject;
+
+    #[wasm_bindgen(typescript_type = "SearchInfo[]")]
+    pub type SearchInfoArray;
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Color {
+    White,
+    Black
+}
+
+impl From<crate::Color> for Color {
+    fn from(color: crate::Color) -> Color {
+        match color {
+            crate::Color::White => Color::White,
+            crate::Color::Black => Color::Black
+        }
+    }
+}
+
+impl From<Color> for crate::Color {
+    fn from(color: Color) -> crate::Color {
+        match color {
+            Color::White => crate::Color::White,
+            Color::Black => crate::Color::Black
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PieceType {
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King
+}
+
+impl From<crate::PieceType> for PieceType {
+    fn from(kind: crate::PieceType) -> PieceType {
+        match kind {
+            crate::PieceType::Pawn => PieceType::Pawn,
+            crate::PieceType::Knight => PieceType::Knight,
+            crate::PieceType::Bishop => PieceType::Bishop,
+            crate::PieceType::Rook => PieceType::Rook,
+            crate::PieceType::Queen => PieceType::Queen,
+            crate::PieceType::King => PieceType::King
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameStatus {
+    Ongoing,
+    Checkmate,
+    Stalemate,
+    FiftyMoveRule,
+    InsufficientMaterial,
+    Repetition
+}
+
+impl From<crate::GameStatus> for GameStatus {
+    fn from(status: crate::GameStatus) -> GameStatus {
+        match status {
+            crate::GameStatus::Ongoing => GameStatus::Ongoing,
+            crate::GameStatus::Checkmate => GameStatus::Checkmate,
+            crate::GameStatus::Stalemate => GameStatus::Stalemate,
+            crate::GameStatus::FiftyMoveRule => GameStatus::FiftyMoveRule,
+            crate::GameStatus::InsufficientMaterial => GameStatus::InsufficientMaterial,
+            crate::GameStatus::Repetition => GameStatus::Repetition
+        }
+    }
+}
+
+// a piece on the board as JavaScript sees it, with its square in algebraic notation
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct Piece {
+    kind: PieceType,
+    color: Color,
+    square: Square
+}
+
+#[wasm_bindgen]
+impl Piece {
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> PieceType {
+        self.kind
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn square(&self) -> String {
+        self.square.to_string()
+    }
+}
+
+impl From<&crate::Piece> for Piece {
+    fn from(piece: &crate::Piece) -> Piece {
+        Piece { kind: piece.kind.into(), color: piece.color.into(), square: piece.square }
+    }
+}
+
+// the game shown on the page, with the moves played so it can take them back and spot repetitions
+#[wasm_bindgen]
+pub struct BoardSingleton {
+    board: Board,
+
+    // positions before each move played along with the move, most recent last, for undo and
+    // repetition checks
+    history: Vec<(Board, Move)>
+}
+
+#[wasm_bindgen]
+impl BoardSingleton {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> BoardSingleton {
+        BoardSingleton {
+            board: Board::default(),
+            history: Vec::new()
+        }
+    }
+
+    // setting a position starts a new game, so there is nothing left to undo
+    pub fn set_fen(&mut self, fen: &str) -> Result<(), JsError> {
+        self.board = fen.parse::<Board>()?;
+        self.history.clear();
+        Ok(())
+    }
+
+    pub fn get_fen(&self) -> String {
+        self.board.get_fen()
+    }
+
+    pub fn side_to_move(&self) -> Color {
+        self.board.get_side_to_move().into()
+    }
+
+    // every piece on the board, white's first
+    pub fn pieces(&self) -> Vec<Piece> {
+        [crate::Color::White, crate::Color::Black].iter()
+            .flat_map(|color| self.board.pieces(*color))
+            .map(Piece::from)
+            .collect()
+    }
+
+    // every legal move in UCI notation, e.g. "e2e4" or "e7e8q"
+    pub fn legal_moves(&self) -> Vec<String> {
+        self.board.get_legal_moves().iter().map(|mv| mv.to_string()).collect()
+    }
+
+    // legal moves of the piece on the given square in UCI notation, empty if the square is not valid
+    pub fn legal_moves_from(&self, square: &str) -> Vec<String> {
+        let from = match square.parse::<Square>() {
+            Ok(sq) => sq,
+            Err(_) => return Vec::new()
+        };
+
+        self.board.get_legal_moves().iter().filter(|mv| mv.from() == from).map(|mv| mv.to_string()).collect()
+    }
+
+    // plays a move given in UCI notation or SAN
+    pub fn play(&mut self, text: &str) -> Result<(), JsError> {
+        let mv = self.board.parse_move(text).map_err(|e| JsError::new(&format!("{}: {}", e, text)))?;
+
+        self.history.push((self.board.clone(), mv));
+        self.board.play_move(mv);
+        Ok(())
+    }
+
+    // takes back the last move, returns false if there was none
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some((board, _)) => {
+                self.board = board;
+                true
+            },
+            None => false
+        }
+    }
+
+    // whether the game is over, including draws by threefold repetition
+    pub fn status(&self) -> GameStatus {
+        let status = self.board.status();
+
+        // positions repeat when the placement, side to move, castling rights and en passant square match
+        let key = |board: &Board| board.get_fen().split_whitespace().take(4).collect::<Vec<&str>>().join(" ");
+        let current = key(&self.board);
+        let repetitions = 1 + self.history.iter().filter(|(board, _)| key(board) == current).count();
+
+        if status == crate::GameStatus::Ongoing && repetitions >= 3 { GameStatus::Repetition } else { status.into() }
+    }
+
+    // piece on each square from a1 to h8, as 0 for an empty square, 1-6 for a white pawn, knight,
+    // bishop, rook, queen or king and 7-12 for the black pieces in the same order
+    pub fn get_squares(&self) -> Vec<u8> {
+        Square::all()
+            .map(|sq| match self.board.find_piece(sq) {
+                Some(p) => p.kind as u8 + 1 + if p.color == crate::Color::Black { 6 } else { 0 },
+                None => 0
+            })
+            .collect()
+    }
+
+    // the last move played in UCI notation, if there is one to undo
+    pub fn get_last_move(&self) -> Option<String> {
+        self.history.last().map(|(_, mv)| mv.to_string())
+    }
+
+    // index of the king of the side to move if it is in check, to highlight it
+    pub fn get_check_square(&self) -> Option<u8> {
+        if !self.board.in_check() { return None; }
+
+        self.board.king_square(self.board.get_side_to_move()).map(|sq| sq.index())
+    }
+
+    // indexes of the squares the piece on the given index can move to
+    pub fn get_targets(&self, square: u8) -> Vec<u8> {
+        let from = match Square::from_index(square) {
+            Some(sq) => sq,
+            None => return Vec::new()
+        };
+
+        let mut targets: Vec<u8> = self.board.get_legal_moves().iter().filter(|mv| mv.from() == from).map(|mv| mv.to().index()).collect();
+
+        // promotions give one move per piece on the same square
+        targets.dedup();
+        targets
+    }
+
+    // the board as an svg picture, with the last move and any check highlighted
+    pub fn get_svg(&self, flipped: bool) -> String {
+        let options = svg::RenderOptions {
+            orientation: if flipped { crate::Color::Black } else { crate::Color::White },
+            last_move: self.history.last().map(|(_, mv)| *mv),
+            ..svg::RenderOptions::default()
+        };
+
+        svg::render_svg(&self.board, &options)
+    }
+
+    // attack counts for the given color as a flat array from a1 to h8, for heat-map overlays
+    pub fn get_attack_map(&self, color: Color) -> Vec<u8> {
+        self.board.get_attack_map(color.into()).to_vec()
+    }
+
+    // captured pieces of the given color as FEN characters, e.g. "ppn" for two black pawns and a knight
+    pub fn get_captured(&self, color: Color) -> String {
+        let color = color.into();
+        self.board.captured(color).iter().map(|kind| piece_char(*kind, color)).collect()
+    }
+
+    // material balance in points, positive when white is ahead
+    pub fn get_material_difference(&self) -> i32 {
+        self.board.material_difference()
+    }
+
+    // the best count moves searched to the given depth, best first, as SearchInfo objects whose
+    // pv starts with the move; runs on the calling thread, so keep the depth low on the page
+    pub fn top_moves(&self, count: u32, depth: u32) -> SearchInfoArray {
+        let limits = SearchLimits { depth: Some(depth.max(1)), ..SearchLimits::default() };
+        let options = SearchOptions { multipv: count as usize, ..SearchOptions::default() };
+        let tt = TranspositionTable::new(DEFAULT_HASH);
+
+        let history: Vec<u64> = self.history.iter().map(|(board, _)| board.hash()).collect();
+
+        let lines = search::search_with(&self.board, &history, &limits, &tt, &options, &|| false, &mut |_| {})
+            .map(|result| result.lines)
+            .unwrap_or_default();
+
+        lines.iter().map(info_object).collect::<js_sys::Array>().unchecked_into()
+    }
+}
+
+impl Default for BoardSingleton {
+    fn default() -> Self {
+        BoardSingleton::new()
+    }
+}
+
+// how long a search may go on, 0 meaning no limit for every field
+//
+// with remaining set the engine is playing on a clock and decides for itself how long to think,
+// within any depth or movetime also given
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Limits {
+    pub depth: u32,
+    // milliseconds
+    pub movetime: f64,
+    // the clock of the side to move, in milliseconds
+    pub remaining: f64,
+    pub increment: f64,
+    pub movestogo: u32,
+    // time lost getting the move to the board, e.g. to the worker's messages
+    pub overhead: f64,
+    // lines to report, each with a different first move; 0 counts as 1
+    pub multipv: u32
+}
+
+#[wasm_bindgen]
+impl Limits {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Limits {
+        Limits::default()
+    }
+}
+
+impl From<&Limits> for SearchLimits {
+    fn from(limits: &Limits) -> SearchLimits {
+        let positive = |ms: f64| if ms > 0.0 { Some(ms as u64) } else { None };
+
+        SearchLimits {
+            depth: if limits.depth > 0 { Some(limits.depth) } else { None },
+            nodes: None,
+            movetime: positive(limits.movetime),
+            clock: positive(limits.remaining).map(|remaining| TimeControl {
+                remaining,
+                increment: positive(limits.increment).unwrap_or(0),
+                movestogo: if limits.movestogo > 0 { Some(limits.movestogo) } else { None },
+                overhead: positive(limits.overhead).unwrap_or(0)
+            }),
+            infinite: false
+        }
+    }
+}
+
+// entry point for running a search in a web worker
+//
+// on_info is called with a SearchInfo object after every iteration, once for each line when
+// limits asks for more than one. The search stops once the
+// first element of stop is set to something other than 0 from another thread, which needs the
+// Int32Array to be backed by a SharedArrayBuffer. Returns a SearchResult object, or undefined if
+// there are no legal moves.
+#[wasm_bindgen]
+pub fn analyze(fen: &str, limits: &Limits, on_info: &SearchInfoCallback, stop: Option<js_sys::Int32Array>) -> Result<Option<SearchResultObject>, JsError> {
+    let board = fen.parse::<Board>()?;
+    let options = SearchOptions { multipv: limits.multipv.max(1) as usize, ..SearchOptions::default() };
+    let limits = SearchLimits::from(limits);
+
+    let stopped = || match &stop {
+        Some(flag) => js_sys::Atomics::load(flag, 0).is_ok_and(|value| value != 0),
+        None => false
+    };
+
+    let callback = on_info.unchecked_ref::<js_sys::Function>();
+    let mut report = |info: &SearchInfo| {
+        // an exception thrown by the callback has nowhere better to go than the next report
+        let _ = callback.call1(&JsValue::NULL, &info_object(info));
+    };
+
+    let tt = TranspositionTable::new(DEFAULT_HASH);
+    let result = search::search_with(&board, &[], &limits, &tt, &options, &stopped, &mut report);
+
+    Ok(result.map(|result| result_object(&result).unchecked_into()))
+}
+
+// the SearchInfo object from the TypeScript definitions
+fn info_object(info: &SearchInfo) -> js_sys::Object {
+    let mate = info.mate_in().map_or(JsValue::NULL, JsValue::from);
+
+    plain_object(&[
+        ("depth", JsValue::from(info.depth)),
+        ("multipv", JsValue::from(info.multipv)),
+        ("seldepth", JsValue::from(info.seldepth)),
+        ("score", JsValue::from(info.score)),
+        ("mate", mate),
+        ("nodes", JsValue::from(info.nodes as f64)),
+        ("nps", JsValue::from(info.nps() as f64)),
+        ("time", JsValue::from(info.time as f64)),
+        ("pv", JsValue::from(uci_line(&info.pv)))
+    ])
+}
+
+// the SearchResult object from the TypeScript definitions
+fn result_object(result: &SearchResult) -> js_sys::Object {
+    let mate = result.mate_in().map_or(JsValue::NULL, JsValue::from);
+    let ponder = result.ponder().map_or(JsValue::NULL, |mv| JsValue::from(mv.to_string()));
+
+    plain_object(&[
+        ("bestmove", JsValue::from(result.best_move.to_string())),
+        ("ponder", ponder),
+        ("depth", JsValue::from(result.depth)),
+        ("multipv", JsValue::from(1)),
+        ("seldepth", JsValue::from(result.seldepth)),
+        ("score", JsValue::from(result.score)),
+        ("mate", mate),
+        ("nodes", JsValue::from(result.nodes as f64)),
+        ("nps", JsValue::from(result.nps() as f64)),
+        ("time", JsValue::from(result.time as f64)),
+        ("pv", JsValue::from(uci_line(&result.pv))),
+        ("lines", result.lines.iter().map(info_object).collect::<js_sys::Array>().into())
+    ])
+}
+
+// moves in UCI notation separated by spaces
+fn uci_line(moves: &[Move]) -> String {
+    moves.iter().map(|mv| mv.to_string()).collect::<Vec<String>>().join(" ")
+}
+
+fn plain_object(fields: &[(&str, JsValue)]) -> js_sys::Object {
+    let object = js_sys::Object::new();
+
+    // setting a property on a fresh plain object cannot fail
+    for (key, value) in fields.iter() {
+        let _ = js_sys::Reflect::set(&object, &JsValue::from(*key), value);
+    }
+
+    object
+}
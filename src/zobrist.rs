@@ -0,0 +1,70 @@
+// Zobrist hashing
+//
+// Every piece on every square, the side to move, each castling right and each en passant file
+// has a fixed random number, and the hash of a position is the numbers of everything in it
+// xored together. The same position reached by different move orders gets the same hash, which
+// is what the transposition table looks positions up by.
+
+use crate::{Board, Color};
+
+// 12 pieces on 64 squares, then black to move, the four castling rights and the eight files
+const PIECE_KEYS: usize = 12 * 64;
+const SIDE_KEY: usize = PIECE_KEYS;
+const CASTLING_KEYS: usize = SIDE_KEY + 1;
+const EN_PASSANT_KEYS: usize = CASTLING_KEYS + 4;
+const KEY_COUNT: usize = EN_PASSANT_KEYS + 8;
+
+// the numbers come from a fixed seed, so hashes are the same on every run and every platform
+static KEYS: [u64; KEY_COUNT] = keys(0x5eed);
+
+// splitmix64, small and good enough for hash keys
+const fn keys(seed: u64) -> [u64; KEY_COUNT] {
+    let mut keys = [0; KEY_COUNT];
+    let mut state = seed;
+    let mut i = 0;
+
+    while i < KEY_COUNT {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[i] = z ^ (z >> 31);
+
+        i += 1;
+    }
+
+    keys
+}
+
+impl Board {
+    // the Zobrist hash of the position, see the top of the file; the move clocks are left out,
+    // so positions that only differ in them hash the same
+    pub fn hash(&self) -> u64 {
+        let mut hash = 0;
+
+        for color in [Color::White, Color::Black].iter() {
+            for piece in self.pieces(*color) {
+                let index = (*color as usize * 6 + piece.kind as usize) * 64 + piece.square.index() as usize;
+                hash ^= KEYS[index];
+            }
+        }
+
+        let mut fields = self.fen.split_whitespace().skip(1);
+
+        if fields.next() == Some("b") {
+            hash ^= KEYS[SIDE_KEY];
+        }
+
+        let castling = fields.next().unwrap_or("-");
+        for (i, right) in "KQkq".chars().enumerate() {
+            if castling.contains(right) { hash ^= KEYS[CASTLING_KEYS + i]; }
+        }
+
+        if let Some(file) = fields.next().and_then(|square| square.bytes().next()).filter(|file| (b'a'..=b'h').contains(file)) {
+            hash ^= KEYS[EN_PASSANT_KEYS + (file - b'a') as usize];
+        }
+
+        hash
+    }
+}
@@ -0,0 +1,83 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::{Board, Color, PieceType, Square};
+
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    fn sq(square: &str) -> Square {
+        square.parse().unwrap()
+    }
+
+    #[test]
+    fn test_attackers_to() {
+        init();
+
+        // e4 is hit by the d3 pawn, the c3 knight and the h7 queen, but not by the h1 rook
+        let board = Board::new("4k3/7Q/8/8/8/2NP4/8/4K2R w K - 0 1".to_string());
+        let mut kinds: Vec<PieceType> = board.attackers_to(sq("e4"), Color::White).iter().map(|p| p.get_kind()).collect();
+        kinds.sort_by_key(|k| *k as usize);
+        assert_eq!(kinds, vec![PieceType::Pawn, PieceType::Knight, PieceType::Queen]);
+
+        assert!(board.attackers_to(sq("e4"), Color::Black).is_empty());
+    }
+
+    #[test]
+    fn test_is_square_attacked() {
+        init();
+
+        let board = Board::new("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1".to_string());
+
+        // the rook covers the a-file and the first rank up to the king
+        assert!(board.is_square_attacked(sq("a8"), Color::White));
+        assert!(board.is_square_attacked(sq("d1"), Color::White));
+        assert!(!board.is_square_attacked(sq("f1"), Color::Black));
+        // the black king covers the squares around it
+        assert!(board.is_square_attacked(sq("e7"), Color::Black));
+        assert!(!board.is_square_attacked(sq("e6"), Color::Black));
+    }
+
+    #[test]
+    fn test_attack_map_start_position() {
+        init();
+
+        let board = Board::new("".to_string());
+        let map = board.get_attack_map(Color::White);
+
+        // third rank: a3 and h3 by a pawn and a knight, c3 and f3 by two pawns and a knight
+        assert_eq!(map[16..24], [2, 2, 3, 2, 2, 3, 2, 2]);
+        // d2 is defended by the queen, king, knight and bishop
+        assert_eq!(map[sq("d2").index() as usize], 4);
+        // nothing beyond the third rank
+        assert!(map[24..].iter().all(|c| *c == 0));
+
+        let black = board.get_attack_map(Color::Black);
+        assert_eq!(black[40..48], [2, 2, 3, 2, 2, 3, 2, 2]);
+    }
+
+    #[test]
+    fn test_defense_map() {
+        init();
+
+        // the b2 pawn is defended by the bishop, the bishop by the rook; the d4 knight, the rook
+        // and the king are left on their own
+        let board = Board::new("4k3/8/8/8/3N4/8/1P6/R1B1K3 w - - 0 1".to_string());
+        let map = board.get_defense_map(Color::White);
+
+        assert_eq!(map[sq("b2").index() as usize], 1);
+        assert_eq!(map[sq("c1").index() as usize], 1);
+        assert_eq!(map[sq("d4").index() as usize], 0);
+        // squares without a white piece are 0 even when attacked
+        assert_eq!(map[sq("a3").index() as usize], 0);
+        assert_eq!(map.iter().map(|c| *c as u32).sum::<u32>(), 2);
+    }
+}
@@ -3,7 +3,7 @@ mod utils;
 
 #[cfg(test)]
 mod tests {
-    use alpha_rust::Board;
+    use alpha_rust::{Board, Color, ParseFenError, PieceType, Square};
 
     use std::sync::Once;
 
@@ -15,6 +15,10 @@ mod tests {
         });
     }
 
+    fn sq(square: &str) -> Square {
+        square.parse().unwrap()
+    }
+
     #[test]
     fn test_board_init() {
         init();
@@ -46,19 +50,19 @@ mod tests {
         println!("true");
 
         print!("Checking if black king is in correct position... ");
-        assert_eq!(board.get_black_pieces()[5][0].get_position(), alpha_rust::Coordinates { rank: 8, file: 5});
+        assert_eq!(board.get_black_pieces()[5][0].get_position(), sq("e8"));
         println!("true");
 
         print!("Checking if white king is in correct position... ");
-        assert_eq!(board.get_white_pieces()[5][0].get_position(), alpha_rust::Coordinates { rank: 1, file: 5});
+        assert_eq!(board.get_white_pieces()[5][0].get_position(), sq("e1"));
         println!("true");
 
         print!("Checking if e2 pawn is in correct position... ");
-        assert_eq!(board.get_white_pieces()[0][4].get_position(), alpha_rust::Coordinates { rank: 2, file: 5});
+        assert_eq!(board.get_white_pieces()[0][4].get_position(), sq("e2"));
         println!("true");
 
         print!("Checking if e7 pawn is in correct position... ");
-        assert_eq!(board.get_black_pieces()[0][4].get_position(), alpha_rust::Coordinates { rank: 7, file: 5});
+        assert_eq!(board.get_black_pieces()[0][4].get_position(), sq("e7"));
         println!("true");
 
         print!("\n\n");
@@ -75,15 +79,15 @@ mod tests {
         println!("true");
 
         print!("Checking if white e pawn is in correct position... ");
-        assert_eq!(board.get_white_pieces()[0][0].get_position(), alpha_rust::Coordinates { rank: 4, file: 5});
+        assert_eq!(board.get_white_pieces()[0][0].get_position(), sq("e4"));
         println!("true");
 
         print!("Checking if black c pawn is in correct position... ");
-        assert_eq!(board.get_black_pieces()[0][7].get_position(), alpha_rust::Coordinates { rank: 5, file: 3});
+        assert_eq!(board.get_black_pieces()[0][7].get_position(), sq("c5"));
         println!("true");
 
         print!("Checking if white knight is in correct position... ");
-        assert_eq!(board.get_white_pieces()[1][0].get_position(), alpha_rust::Coordinates { rank: 3, file: 6});
+        assert_eq!(board.get_white_pieces()[1][0].get_position(), sq("f3"));
         println!("true");
     }
 
@@ -99,23 +103,23 @@ mod tests {
         println!("true");
 
         print!("Checking if white a pawn is in correct position... ");
-        assert_eq!(board.get_white_pieces()[0][0].get_position(), alpha_rust::Coordinates { rank: 4, file: 5});
+        assert_eq!(board.get_white_pieces()[0][0].get_position(), sq("e4"));
         println!("true");
 
         print!("Checking if black h pawn is in correct position... ");
-        assert_eq!(board.get_black_pieces()[0][6].get_position(), alpha_rust::Coordinates { rank: 5, file: 3});
+        assert_eq!(board.get_black_pieces()[0][6].get_position(), sq("c5"));
         println!("true");
 
         print!("Checking if white knight is in correct position... ");
-        assert_eq!(board.get_white_pieces()[1][0].get_position(), alpha_rust::Coordinates { rank: 3, file: 6});
+        assert_eq!(board.get_white_pieces()[1][0].get_position(), sq("f3"));
         println!("true");
 
         print!("Checking if first black queen is in correct position... ");
-        assert_eq!(board.get_black_pieces()[4][0].get_position(), alpha_rust::Coordinates { rank: 8, file: 4});
+        assert_eq!(board.get_black_pieces()[4][0].get_position(), sq("d8"));
         println!("true");
 
         print!("Checking if second black queen is in correct position... ");
-        assert_eq!(board.get_black_pieces()[4][1].get_position(), alpha_rust::Coordinates { rank: 7, file: 6});
+        assert_eq!(board.get_black_pieces()[4][1].get_position(), sq("f7"));
         println!("true");
     }
 
@@ -131,9 +135,9 @@ mod tests {
         print!("Checking if pawn move 1. e4 is successful... ");
         let pawn = &mut board.get_white_pieces()[0][4];
 
-        let mv = board.make_move(pawn, 4, 5);
+        let mv = board.make_move(pawn, sq("e4"));
         assert!(mv);
-        assert_eq!(board.find_piece_by_coords(4, 5).unwrap(), pawn);
+        assert_eq!(board.find_piece(sq("e4")).unwrap(), pawn);
         println!("true");
     }
 
@@ -149,10 +153,10 @@ mod tests {
         
         let knight = &mut board.get_white_pieces()[1][1];
 
-        let mv = board.make_move(knight, 3, 6);
+        let mv = board.make_move(knight, sq("f3"));
         assert!(mv);
 
-        let res = board.find_piece_by_coords(3, 6).unwrap();
+        let res = board.find_piece(sq("f3")).unwrap();
 
         assert_eq!(res.get_position(), board.get_white_pieces()[1][1].get_position());
 
@@ -169,13 +173,110 @@ mod tests {
 
         //make moves 1. e4 f5 2. Qh5+
         let w_pawn = &mut board.get_white_pieces()[0][4];
-        board.make_move(w_pawn, 4, 5);
+        board.make_move(w_pawn, sq("e4"));
         let b_pawn = &mut board.get_black_pieces()[0][5];
-        board.make_move(b_pawn, 5, 6);
+        board.make_move(b_pawn, sq("f5"));
         let w_queen = &mut board.get_white_pieces()[4][0];
-        board.make_move(w_queen, 5, 8);
+        board.make_move(w_queen, sq("h5"));
 
         //check if board state includes checks
-        //assert!(board.in_check());
+        assert!(board.in_check());
+    }
+
+    #[test]
+    fn test_castling_from_the_king_square_only() {
+        init();
+
+        let castles = |fen: &str| {
+            let moves: Vec<String> = Board::new(fen.to_string()).get_legal_moves().iter().map(|mv| mv.to_string()).collect();
+            (moves.contains(&"e1g1".to_string()), moves.contains(&"e1c1".to_string()), moves.contains(&"d1g1".to_string()))
+        };
+
+        assert_eq!(castles("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1"), (true, true, false));
+
+        // rights that do not fit the position never move a king from anywhere else
+        assert_eq!(castles("4k3/8/8/8/8/8/8/R2K3R w KQ - 0 1"), (false, false, false));
+        let board = Board::new("4k3/8/8/8/8/8/8/R2K3R w KQ - 0 1".to_string());
+        assert!(board.get_legal_moves().iter().all(|mv| !mv.is_castle()));
+    }
+
+    #[test]
+    fn test_from_fen() {
+        init();
+
+        let board = Board::from_fen("  4k3/8/8/8/4P3/8/8/4K3   b - e3 ").unwrap();
+        assert_eq!(board.get_fen(), "4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1");
+
+        assert_eq!(Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w").err(), Some(ParseFenError::Fields));
+        assert_eq!(Board::from_fen("4k3/8/8/8/8/8/4P4/4K3 w - - 0 1").err(), Some(ParseFenError::Placement));
+        assert_eq!(Board::from_fen("4k3/8/8/8/8/8/4X3/4K3 w - - 0 1").err(), Some(ParseFenError::Placement));
+        assert_eq!(Board::from_fen("4k3/8/8/8/8/8/4P3/8 w - - 0 1").err(), Some(ParseFenError::Kings));
+        assert_eq!(Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 x - - 0 1").err(), Some(ParseFenError::SideToMove));
+        assert_eq!(Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w KX - 0 1").err(), Some(ParseFenError::Castling));
+        assert_eq!(Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - e9 0 1").err(), Some(ParseFenError::EnPassant));
+        assert_eq!(Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - x 1").err(), Some(ParseFenError::MoveCounters));
+    }
+
+    #[test]
+    fn test_from_fen_drops_rights_that_do_not_fit() {
+        init();
+
+        let fen = |fen: &str| Board::from_fen(fen).unwrap().get_fen();
+
+        // a king off its square has no castling rights, and a missing rook loses its side only
+        assert_eq!(fen("r3k2r/8/8/8/8/8/8/R2K3R w KQkq - 0 1"), "r3k2r/8/8/8/8/8/8/R2K3R w kq - 0 1");
+        assert_eq!(fen("r3k3/8/8/8/8/8/8/R3K2R w KQkq - 0 1"), "r3k3/8/8/8/8/8/8/R3K2R w KQq - 0 1");
+        assert_eq!(fen("4k3/8/8/8/8/8/8/3K4 w KQkq - 0 1"), "4k3/8/8/8/8/8/8/3K4 w - - 0 1");
+
+        // so the king cannot castle from the wrong square
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R2K3R w KQ - 0 1").unwrap();
+        assert!(board.get_legal_moves().iter().all(|mv| !mv.is_castle()));
+
+        // an en passant square needs the pawn that just went past it
+        assert_eq!(fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1"), "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+        assert_eq!(fen("4k3/8/8/4P3/8/8/8/4K3 w - d6 0 1"), "4k3/8/8/4P3/8/8/8/4K3 w - - 0 1");
+        assert_eq!(fen("4k3/3p4/8/3pP3/8/8/8/4K3 w - d6 0 1"), "4k3/3p4/8/3pP3/8/8/8/4K3 w - - 0 1");
+        assert_eq!(fen("4k3/8/8/3pP3/8/8/8/4K3 w - d3 0 1"), "4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1");
+        assert_eq!(fen("4k3/8/8/8/3Pp3/8/8/4K3 b - d3 0 1"), "4k3/8/8/8/3Pp3/8/8/4K3 b - d3 0 1");
+
+        // so there is no capture onto an empty square
+        let board = Board::from_fen("4k3/8/8/4P3/8/8/8/4K3 w - d6 0 1").unwrap();
+        assert!(board.get_legal_moves().iter().all(|mv| !mv.is_en_passant()));
+        assert!(Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap().get_legal_moves().iter().any(|mv| mv.is_en_passant()));
+    }
+
+    #[test]
+    fn test_parse_and_pieces() {
+        init();
+
+        let board: Board = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1".parse().unwrap();
+        assert_eq!(board.pieces(Color::White).map(|p| p.get_kind()).collect::<Vec<_>>(), vec![PieceType::Pawn, PieceType::King]);
+        assert_eq!(board.pieces(Color::Black).count(), 1);
+
+        assert_eq!("not a fen".parse::<Board>().err(), Some(ParseFenError::Fields));
+        assert_eq!(Board::default().get_fen(), Board::new("".to_string()).get_fen());
+    }
+
+    #[test]
+    fn test_hash() {
+        init();
+
+        // the same position by two move orders
+        let mut one = Board::default();
+        let mut two = Board::default();
+        for text in ["g1f3", "g8f6", "b1c3"].iter() { assert!(one.play_move(text.parse().unwrap())); }
+        for text in ["b1c3", "g8f6", "g1f3"].iter() { assert!(two.play_move(text.parse().unwrap())); }
+        assert_eq!(one.hash(), two.hash());
+
+        // the side to move, the castling rights and the en passant square all count, the move
+        // clocks do not
+        let hash = |fen: &str| Board::from_fen(fen).unwrap().hash();
+        let base = hash("4k3/8/8/8/4Pp2/8/8/R3K3 b Q e3 0 1");
+
+        assert_ne!(base, hash("4k3/8/8/8/4Pp2/8/8/R3K3 w Q e3 0 1"));
+        assert_ne!(base, hash("4k3/8/8/8/4Pp2/8/8/R3K3 b - e3 0 1"));
+        assert_ne!(base, hash("4k3/8/8/8/4Pp2/8/8/R3K3 b Q - 0 1"));
+        assert_ne!(base, hash("4k3/8/8/8/4Pp2/8/8/R2K4 b - e3 0 1"));
+        assert_eq!(base, hash("4k3/8/8/8/4Pp2/8/8/R3K3 b Q e3 7 30"));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,69 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::Board;
+
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    #[test]
+    fn test_ascii_diagram() {
+        init();
+
+        let board = Board::new("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".to_string());
+        let expected = "\
+8  r n b q k b n r
+7  p p p p p p p p
+6  . . . . . . . .
+5  . . . . . . . .
+4  . . . . P . . .
+3  . . . . . . . .
+2  P P P P . P P P
+1  R N B Q K B N R
+   a b c d e f g h
+Black to move";
+
+        assert_eq!(board.to_string(), expected);
+    }
+
+    #[test]
+    fn test_unicode_flipped_diagram() {
+        init();
+
+        // black king in check from the rook on e1
+        let board = Board::new("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1".to_string());
+        let expected = "\
+1  · ♔ · ♖ · · · ·
+2  · · · · · · · ·
+3  · · · · · · · ·
+4  · · · · · · · ·
+5  · · · · · · · ·
+6  · · · · · · · ·
+7  · · · · · · · ·
+8  · · · ♚ · · · ·
+   h g f e d c b a
+Black to move, in check";
+
+        assert_eq!(format!("{:#}", board.display().flipped(true)), expected);
+        assert_eq!(format!("{}", board.display().flipped(false)), board.to_string());
+    }
+
+    #[test]
+    fn test_debug_shows_fen() {
+        init();
+
+        let board = Board::new("".to_string());
+        let debug = format!("{:?}", board);
+
+        assert!(debug.starts_with("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\n8  r n b q k b n r\n"));
+        assert!(debug.ends_with("White to move"));
+    }
+}
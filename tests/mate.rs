@@ -0,0 +1,119 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::{Board, GameStatus, Move, PuzzleError, Stipulation};
+
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    fn mv(text: &str) -> Move {
+        text.parse().unwrap()
+    }
+
+    // plays the line and returns where it ends
+    fn play(board: &Board, line: &[Move]) -> Board {
+        let mut board = board.clone();
+        for mv in line.iter() {
+            assert!(board.play_move(*mv), "{} in {:?}", mv, line);
+        }
+        board
+    }
+
+    #[test]
+    fn test_mate_in_one() {
+        init();
+
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/4R1K1 w - - 0 1").unwrap();
+        assert_eq!(board.find_mate(3), Some(vec![mv("e1e8")]));
+
+        // nothing mates from the start
+        assert_eq!(Board::default().find_mate(1), None);
+    }
+
+    #[test]
+    fn test_mate_in_two() {
+        init();
+
+        // Morphy: 1. Ra6 and mate next move whatever black does
+        let board = Board::from_fen("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1").unwrap();
+        assert_eq!(board.find_mate(1), None);
+
+        let line = board.find_mate(2).unwrap();
+        assert_eq!(line.len(), 3);
+        assert_eq!(line[0], mv("a1a6"));
+        assert_eq!(play(&board, &line).status(), GameStatus::Checkmate);
+
+        assert_eq!(board.check_puzzle(Stipulation::Mate, 2, mv("a1a6")), Ok(()));
+    }
+
+    #[test]
+    fn test_cooks() {
+        init();
+
+        // either rook mates on the back rank
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R3R1K1 w - - 0 1").unwrap();
+
+        assert_eq!(board.key_moves(Stipulation::Mate, 1), vec![mv("a1a8"), mv("e1e8")]);
+        assert_eq!(board.check_puzzle(Stipulation::Mate, 1, mv("e1e8")), Err(PuzzleError::Cooked(vec![mv("a1a8")])));
+        assert_eq!(board.check_puzzle(Stipulation::Mate, 1, mv("g1f1")), Err(PuzzleError::WrongKey(vec![mv("a1a8"), mv("e1e8")])));
+        assert_eq!(Board::default().check_puzzle(Stipulation::Mate, 1, mv("e2e4")), Err(PuzzleError::NoSolution));
+        assert_eq!(PuzzleError::Cooked(vec![mv("a1a8")]).to_string(), "cooked by a1a8");
+    }
+
+    #[test]
+    fn test_helpmate() {
+        init();
+
+        // black in check has one way out, and walks into the queen's mate on g7
+        let board = Board::from_fen("7k/8/7K/8/8/8/8/Q7 b - - 0 1").unwrap();
+
+        let line = board.solve(Stipulation::Helpmate, 1).unwrap();
+        assert_eq!(line.len(), 2);
+        assert_eq!(line[1], mv("a1g7"));
+        assert_eq!(play(&board, &line).status(), GameStatus::Checkmate);
+
+        assert_eq!(line[0], mv("h8g8"));
+        assert_eq!(board.check_puzzle(Stipulation::Helpmate, 1, mv("h8g8")), Ok(()));
+
+        // as a plain mate black has nothing
+        assert_eq!(board.find_mate(1), None);
+    }
+
+    #[test]
+    fn test_selfmate() {
+        init();
+
+        // 1. b8=Q+ and the only way out is Qxb8, which mates white
+        let board = Board::from_fen("2K5/kP5r/8/2N5/1q3R2/6p1/8/8 w - - 0 1").unwrap();
+
+        let line = board.solve(Stipulation::Selfmate, 1).unwrap();
+        assert_eq!(line.iter().map(|mv| mv.to_string()).collect::<Vec<_>>(), vec!["b7b8q", "b4b8"]);
+        assert_eq!(play(&board, &line).status(), GameStatus::Checkmate);
+
+        // every reply to the key mates
+        let after = play(&board, &line[..1]);
+        for reply in after.get_legal_moves() {
+            assert_eq!(play(&after, &[reply]).status(), GameStatus::Checkmate);
+        }
+
+        assert_eq!(board.check_puzzle(Stipulation::Selfmate, 1, mv("b7b8q")), Ok(()));
+        assert_eq!(board.find_mate(1), None);
+    }
+
+    #[test]
+    fn test_key_given_without_flags() {
+        init();
+
+        // a key parsed from text has no capture flag, but is still the capture the solver found
+        let board = Board::from_fen("3r2k1/5ppp/8/8/8/8/8/3R2K1 w - - 0 1").unwrap();
+        assert_eq!(board.check_puzzle(Stipulation::Mate, 1, mv("d1d8")), Ok(()));
+    }
+}
@@ -0,0 +1,74 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::{Board, Color, PieceType};
+
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    // plays a list of moves in UCI notation
+    fn play(board: &mut Board, moves: &[&str]) {
+        for uci in moves.iter() {
+            assert!(board.play_move(uci.parse().unwrap()), "{} is not legal", uci);
+        }
+    }
+
+    #[test]
+    fn test_captures_are_recorded() {
+        init();
+
+        let mut board = Board::new("".to_string());
+        assert!(board.captured(Color::White).is_empty());
+        assert!(board.captured(Color::Black).is_empty());
+
+        // 1. e4 d5 2. exd5 Qxd5 3. Nc3 Qxa2 4. Rxa2
+        play(&mut board, &["e2e4", "d7d5", "e4d5", "d8d5", "b1c3", "d5a2", "a1a2"]);
+
+        assert_eq!(board.captured(Color::White), vec![PieceType::Pawn, PieceType::Pawn]);
+        assert_eq!(board.captured(Color::Black), vec![PieceType::Pawn, PieceType::Queen]);
+        assert_eq!(board.piece_count(Color::White, PieceType::Pawn), 6);
+        assert_eq!(board.piece_count(Color::Black, PieceType::Queen), 0);
+        assert_eq!(board.get_black_pieces()[4].len(), 0);
+        assert_eq!(board.material_difference(), 9 + 1 - 2);
+    }
+
+    #[test]
+    fn test_captured_from_fen() {
+        init();
+
+        // white is missing a rook and two pawns, black a knight and a pawn
+        let board = Board::new("r1bqkbnr/ppp1pppp/8/8/8/8/PPPP2PP/RNBQKBN1 w Qkq - 0 1".to_string());
+
+        assert_eq!(board.captured(Color::White), vec![PieceType::Pawn, PieceType::Pawn, PieceType::Rook]);
+        assert_eq!(board.captured(Color::Black), vec![PieceType::Pawn, PieceType::Knight]);
+        assert_eq!(board.material(Color::White), 39 - 7);
+        assert_eq!(board.material_difference(), -3);
+    }
+
+    #[test]
+    fn test_promotion_is_not_a_capture() {
+        init();
+
+        // a second white queen means one of the missing pawns promoted rather than being taken
+        let board = Board::new("4k3/8/8/8/8/8/PPPPPP2/QQ2K3 w - - 0 1".to_string());
+        assert_eq!(board.piece_count(Color::White, PieceType::Queen), 2);
+        assert_eq!(board.captured(Color::White).iter().filter(|k| **k == PieceType::Pawn).count(), 1);
+
+        let mut board = Board::new("4k3/P7/8/8/8/8/8/4K3 w - - 0 1".to_string());
+        let before = board.captured(Color::White);
+        play(&mut board, &["a7a8q"]);
+
+        assert_eq!(board.captured(Color::White), before);
+        assert_eq!(board.piece_count(Color::White, PieceType::Pawn), 0);
+        assert_eq!(board.piece_count(Color::White, PieceType::Queen), 1);
+        assert_eq!(board.material_difference(), 9);
+    }
+}
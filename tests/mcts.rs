@@ -0,0 +1,237 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::mcts::{Evaluation, Evaluator, HeuristicEvaluator, Mcts, MctsConfig};
+    use alpha_rust::search::{SearchLimits, MATE};
+    use alpha_rust::{Board, Move};
+
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    fn playouts(n: u64) -> SearchLimits {
+        SearchLimits { nodes: Some(n), ..SearchLimits::default() }
+    }
+
+    fn tree(fen: &str, config: MctsConfig) -> Mcts<HeuristicEvaluator> {
+        Mcts::new(&Board::from_fen(fen).unwrap(), HeuristicEvaluator, config)
+    }
+
+    // every move alike and every position a draw, counting the batches it is asked for
+    struct Uniform {
+        batches: Vec<usize>
+    }
+
+    impl Evaluator for Uniform {
+        fn evaluate(&mut self, _board: &Board, _repetitions: u32, moves: &[Move]) -> Evaluation {
+            Evaluation { priors: vec![1.0 / moves.len() as f32; moves.len()], value: 0.0 }
+        }
+
+        fn evaluate_batch(&mut self, positions: &[(Board, u32, Vec<Move>)]) -> Vec<Evaluation> {
+            self.batches.push(positions.len());
+            positions.iter().map(|(board, repetitions, moves)| self.evaluate(board, *repetitions, moves)).collect()
+        }
+    }
+
+    // every move alike, noting each position it is asked about and the repetitions it is told of
+    struct Recording {
+        seen: Vec<(u64, u32)>
+    }
+
+    impl Evaluator for Recording {
+        fn evaluate(&mut self, board: &Board, repetitions: u32, moves: &[Move]) -> Evaluation {
+            self.seen.push((board.hash(), repetitions));
+            Evaluation { priors: vec![1.0 / moves.len() as f32; moves.len()], value: 0.0 }
+        }
+    }
+
+    #[test]
+    fn test_mate_in_one() {
+        init();
+
+        let mut mcts = tree("6k1/5ppp/8/8/8/8/8/4R1K1 w - - 0 1", MctsConfig::default());
+        let result = mcts.search(&playouts(400), &|| false, &mut |_| {}).unwrap();
+
+        assert_eq!(result.best_move.to_string(), "e1e8");
+        assert_eq!(result.score, MATE - 1);
+        assert_eq!(result.mate_in(), Some(1));
+    }
+
+    #[test]
+    fn test_takes_hanging_queen() {
+        init();
+
+        let mut mcts = tree("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", MctsConfig::default());
+        let result = mcts.search(&playouts(400), &|| false, &mut |_| {}).unwrap();
+
+        assert_eq!(result.best_move.to_string(), "d2d5");
+        assert!(result.score > 300, "{}", result.score);
+        assert_eq!(result.nodes, 400);
+    }
+
+    #[test]
+    fn test_info() {
+        init();
+
+        let mut infos = Vec::new();
+        let mut mcts = tree("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", MctsConfig::default());
+        let result = mcts.search(&playouts(300), &|| false, &mut |info| infos.push(info.clone())).unwrap();
+
+        // reported as the average depth goes up, and once more at the end
+        assert!(infos.len() >= 2);
+        assert!(infos.windows(2).all(|pair| pair[0].depth <= pair[1].depth));
+
+        let last = infos.last().unwrap();
+        assert_eq!(last.pv, result.pv);
+        assert_eq!(last.nodes, 300);
+        assert!(last.seldepth >= last.depth);
+
+        // the visits at the root add up to the playouts, and the best move has the most
+        let visits = mcts.root_visits();
+        assert_eq!(visits.len(), 20);
+        assert_eq!(visits.iter().map(|(_, n)| n).sum::<u32>(), 300);
+
+        let most = visits.iter().map(|(_, n)| *n).max().unwrap();
+        assert!(visits.contains(&(result.best_move, most)));
+    }
+
+    #[test]
+    fn test_tree_reuse() {
+        init();
+
+        let board = Board::default();
+        let searched = || {
+            let mut mcts = Mcts::new(&board, HeuristicEvaluator, MctsConfig::default());
+            let best = mcts.search(&playouts(500), &|| false, &mut |_| {}).unwrap().best_move;
+            (mcts, best)
+        };
+
+        // the best move's subtree is kept, with every playout that went through it
+        let (mut mcts, best) = searched();
+        let visits = mcts.root_visits().into_iter().find(|(mv, _)| *mv == best).unwrap().1;
+        mcts.play(best);
+
+        let mut next = board.clone();
+        assert!(next.play_move(best));
+        assert_eq!(mcts.board().get_fen(), next.get_fen());
+        assert_eq!(mcts.playouts(), visits);
+
+        // the search is the same every time, so a second tree has the same reply two plies down,
+        // where set_position finds it as a GUI would send it
+        let (reply, reply_visits) = mcts.root_visits().into_iter().max_by_key(|(_, n)| *n).unwrap();
+        assert!(next.play_move(reply));
+
+        let (mut mcts, _) = searched();
+        mcts.set_position(&next, &[]);
+        assert_eq!(mcts.board().get_fen(), next.get_fen());
+        assert_eq!(mcts.playouts(), reply_visits);
+
+        mcts.search(&playouts(100), &|| false, &mut |_| {}).unwrap();
+        assert_eq!(mcts.playouts(), reply_visits + 100);
+
+        // a position that is not in the tree starts over
+        mcts.set_position(&board, &[]);
+        assert_eq!(mcts.playouts(), 0);
+        assert!(mcts.root_visits().is_empty());
+    }
+
+    #[test]
+    fn test_repetitions() {
+        init();
+
+        let board = Board::from_fen("7k/8/8/8/8/8/8/R6K w - - 0 1").unwrap();
+        let mut repeated = board.clone();
+        assert!(repeated.play_move("a1a2".parse().unwrap()));
+
+        let search = |history: &[u64]| {
+            let mut mcts = Mcts::new(&board, Recording { seen: Vec::new() }, MctsConfig::default());
+            mcts.set_position(&board, history);
+            mcts.search(&playouts(2000), &|| false, &mut |_| {}).unwrap();
+            mcts
+        };
+        let times_seen = |mcts: &Mcts<Recording>, hash: u64| {
+            mcts.evaluator().seen.iter().filter(|(h, _)| *h == hash).map(|(_, n)| *n).collect::<Vec<u32>>()
+        };
+
+        // without a history nothing has been seen before, and coming back to the root is a draw
+        // rather than a position to evaluate again
+        let mcts = search(&[]);
+        assert!(mcts.evaluator().seen.iter().all(|(_, n)| *n == 0));
+        assert_eq!(times_seen(&mcts, board.hash()).len(), 1);
+
+        // a position seen once before in the game is evaluated as one seen once
+        let mcts = search(&[repeated.hash()]);
+        assert!(!times_seen(&mcts, repeated.hash()).is_empty());
+        assert!(times_seen(&mcts, repeated.hash()).iter().all(|n| *n == 1));
+
+        // and one seen twice is a draw by threefold repetition
+        let mcts = search(&[repeated.hash(), board.hash(), repeated.hash()]);
+        assert!(times_seen(&mcts, repeated.hash()).is_empty());
+        assert_eq!(times_seen(&mcts, board.hash()), vec![1]);
+    }
+
+    #[test]
+    fn test_noise() {
+        init();
+
+        let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4";
+        let noisy = |seed: u64| {
+            let config = MctsConfig { noise_fraction: 0.25, seed, ..MctsConfig::default() };
+            let mut mcts = tree(fen, config);
+            mcts.search(&playouts(200), &|| false, &mut |_| {}).unwrap();
+            mcts.root_visits()
+        };
+        let quiet = {
+            let mut mcts = tree(fen, MctsConfig::default());
+            mcts.search(&playouts(200), &|| false, &mut |_| {}).unwrap();
+            mcts.root_visits()
+        };
+
+        // the same seed gives the same search, and the noise changes where the playouts go
+        assert_eq!(noisy(7), noisy(7));
+        assert_ne!(noisy(7), quiet);
+        assert_ne!(noisy(7), noisy(8));
+    }
+
+    #[test]
+    fn test_batches() {
+        init();
+
+        let evaluator = Uniform { batches: Vec::new() };
+        let config = MctsConfig { batch_size: 8, ..MctsConfig::default() };
+        let mut mcts = Mcts::new(&Board::default(), evaluator, config);
+
+        let result = mcts.search(&playouts(100), &|| false, &mut |_| {}).unwrap();
+        assert_eq!(result.nodes, 100);
+        assert_eq!(result.score, 0);
+
+        // virtual losses spread a batch over different moves, so batches stay full; the root's
+        // own evaluation is not part of one
+        let visits = mcts.root_visits();
+        assert!(visits.iter().all(|(_, n)| *n >= 1), "{:?}", visits);
+    }
+
+    #[test]
+    fn test_stop_and_game_over() {
+        init();
+
+        // stopped before any playout, the move the policy likes best
+        let mut mcts = tree("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", MctsConfig::default());
+        let result = mcts.search(&SearchLimits::default(), &|| true, &mut |_| {}).unwrap();
+        assert_eq!(result.best_move.to_string(), "d2d5");
+        assert_eq!(result.nodes, 0);
+
+        // checkmated or stalemated, there is nothing to search
+        let mut mcts = tree("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", MctsConfig::default());
+        assert_eq!(mcts.search(&playouts(10), &|| false, &mut |_| {}), None);
+        let mut mcts = tree("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", MctsConfig::default());
+        assert_eq!(mcts.search(&playouts(10), &|| false, &mut |_| {}), None);
+    }
+}
@@ -0,0 +1,98 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::Board;
+
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    // counts the leaf nodes of the legal move tree, which matches the published counts only when
+    // every special move is generated and nothing illegal slips through
+    fn perft(board: &Board, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = board.get_legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        moves.into_iter().map(|mv| {
+            let mut child = board.clone();
+            assert!(child.play_move(mv), "{}", board.get_fen());
+            perft(&child, depth - 1)
+        }).sum()
+    }
+
+    fn assert_perft(fen: &str, counts: &[u64]) {
+        let board = Board::new(fen.to_string());
+
+        for (depth, nodes) in counts.iter().enumerate() {
+            assert_eq!(perft(&board, depth as u32 + 1), *nodes, "depth {} of {}", depth + 1, fen);
+        }
+    }
+
+    #[test]
+    fn test_perft_start_position() {
+        init();
+
+        assert_perft("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &[20, 400, 8902, 197281]);
+    }
+
+    #[test]
+    fn test_perft_kiwipete() {
+        init();
+
+        // castling both ways, pins, en passant and promotions all in one position
+        assert_perft("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2039, 97862]);
+    }
+
+    #[test]
+    fn test_perft_tricky_positions() {
+        init();
+
+        // positions 3 to 6 from the chess programming wiki: en passant out of a pin along the
+        // rank, promotions with and without captures, castling rights lost to captures
+        assert_perft("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812]);
+        assert_perft("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9467]);
+        assert_perft("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", &[44, 1486, 62379]);
+        assert_perft("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", &[46, 2079, 89890]);
+    }
+
+    #[test]
+    fn test_fen_after_moves() {
+        init();
+
+        // the FEN is written out again from the pieces after every move, so each of these is
+        // reached by exactly one legal move
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 2",
+            "rnbqkb1r/pppppppp/5n2/4P3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2",
+            "rnbqkb1r/ppp1pppp/5n2/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+            "rnbqkb1r/ppp1pppp/3P1n2/8/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3"
+        ];
+        let mut board = Board::new("".to_string());
+
+        for fen in fens.iter() {
+            let children = board.get_legal_moves().into_iter().map(|mv| {
+                let mut child = board.clone();
+                assert!(child.play_move(mv));
+                child
+            });
+
+            let matching = children.filter(|child| child.get_fen() == *fen).collect::<Vec<Board>>();
+            assert_eq!(matching.len(), 1, "{}", fen);
+            board = matching[0].clone();
+        }
+    }
+}
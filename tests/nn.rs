@@ -0,0 +1,189 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::mcts::{Evaluator, Mcts, MctsConfig};
+    use alpha_rust::nn::{encode, encode_game, move_index, LoadError, Network, NetworkEvaluator, INPUTS, POLICY_SIZE};
+    use alpha_rust::pgn::Game;
+    use alpha_rust::search::SearchLimits;
+    use alpha_rust::{Board, Color, Move};
+
+    use std::collections::HashSet;
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    fn mv(text: &str) -> Move {
+        text.parse().unwrap()
+    }
+
+    fn plane(input: &[f32], n: usize) -> &[f32] {
+        &input[n * 64..(n + 1) * 64]
+    }
+
+    // a network without trunk layers whose value is tanh of the inputs summed with the given
+    // weight, and whose policy is flat
+    fn summing_network(weight: f32) -> Vec<u8> {
+        let mut bytes = b"ARNN".to_vec();
+        for n in [1, INPUTS as u32, POLICY_SIZE as u32, 0, 1].iter() {
+            bytes.extend_from_slice(&n.to_le_bytes());
+        }
+
+        let mut floats = |values: Vec<f32>| values.iter().for_each(|x| bytes.extend_from_slice(&x.to_le_bytes()));
+        floats(vec![0.0; POLICY_SIZE * (INPUTS + 1)]);
+        floats(vec![weight; INPUTS]);
+        floats(vec![0.0]);
+        floats(vec![1.0, 0.0]);
+
+        bytes
+    }
+
+    #[test]
+    fn test_encode() {
+        init();
+
+        let start = encode(&Board::default(), 0);
+        assert_eq!(start.len(), INPUTS);
+
+        // own pawns on the second rank, the other side's on the seventh, all four castling rights
+        assert!(plane(&start, 0)[8..16].iter().all(|x| *x == 1.0));
+        assert!(plane(&start, 6)[48..56].iter().all(|x| *x == 1.0));
+        assert_eq!(plane(&start, 5)[4], 1.0);
+        assert!((12..15).all(|n| plane(&start, n).iter().all(|x| *x == 0.0)));
+        assert!((15..19).all(|n| plane(&start, n).iter().all(|x| *x == 1.0)));
+
+        // black to move sees the board from its own side, so its pieces look just like white's did
+        let mut board = Board::default();
+        assert!(board.play_move(mv("e2e4")));
+        let black = encode(&board, 0);
+        assert_eq!(&black[..6 * 64], &start[..6 * 64]);
+        // white's pawn went from e2 to e4, which black sees as e7 to e5
+        assert_eq!(plane(&black, 6)[52], 0.0);
+        assert_eq!(plane(&black, 6)[36], 1.0);
+        assert!(plane(&black, 14).iter().all(|x| *x == 1.0));
+
+        // only the other side can still castle
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQ - 10 30").unwrap();
+        let input = encode(&board, 2);
+        assert!((15..17).all(|n| plane(&input, n).iter().all(|x| *x == 0.0)));
+        assert!((17..19).all(|n| plane(&input, n).iter().all(|x| *x == 1.0)));
+        assert!((12..14).all(|n| plane(&input, n).iter().all(|x| *x == 1.0)));
+        assert!(plane(&input, 19).iter().all(|x| *x == 0.1));
+    }
+
+    #[test]
+    fn test_encode_game() {
+        init();
+
+        let mut game = Game::new();
+        assert_eq!(encode_game(&game), encode(&Board::default(), 0));
+
+        for text in ["g1f3", "g8f6", "f3g1", "f6g8"].iter() {
+            assert!(game.play(mv(text)));
+        }
+
+        // back where it started, the position has been seen once before
+        let input = encode_game(&game);
+        assert!(plane(&input, 12).iter().all(|x| *x == 1.0));
+        assert!(plane(&input, 13).iter().all(|x| *x == 0.0));
+    }
+
+    #[test]
+    fn test_move_index() {
+        init();
+
+        // e2e4 is a queen move two squares north from e2, and e7e5 the same for black
+        assert_eq!(move_index(mv("e2e4"), Color::White), 12 * 73 + 1);
+        assert_eq!(move_index(mv("e7e5"), Color::Black), 12 * 73 + 1);
+        assert_eq!(move_index(mv("g1f3"), Color::White), 6 * 73 + 56 + 7);
+        assert_eq!(move_index(mv("a7a8q"), Color::White), 48 * 73);
+        assert_eq!(move_index(mv("a7b8n"), Color::White), 48 * 73 + 64 + 2);
+        assert_eq!(move_index(mv("h2h1r"), Color::Black), 55 * 73 + 64 + 6 + 1);
+
+        // every legal move of a busy position has its own index
+        for fen in ["r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1"].iter() {
+            let board = Board::from_fen(fen).unwrap();
+            let moves = board.get_legal_moves();
+            let indices: HashSet<usize> = moves.iter().map(|mv| move_index(*mv, board.get_side_to_move())).collect();
+
+            assert_eq!(indices.len(), moves.len());
+            assert!(indices.iter().all(|i| *i < POLICY_SIZE));
+        }
+    }
+
+    #[test]
+    fn test_weights() {
+        init();
+
+        let network = Network::new(&[64, 64, 32], 16, 3);
+        assert_eq!(network.trunk(), vec![64, 64, 32]);
+
+        let bytes = network.to_bytes();
+        assert!(Network::from_bytes(&bytes).unwrap() == network);
+
+        let (policy, value) = network.forward(&encode(&Board::default(), 0));
+        assert_eq!(policy.len(), POLICY_SIZE);
+        assert!(value > -1.0 && value < 1.0);
+
+        let mut bad = bytes.clone();
+        bad[0] = b'X';
+        assert_eq!(Network::from_bytes(&bad).err(), Some(LoadError::Magic));
+
+        let mut bad = bytes.clone();
+        bad[4] = 2;
+        assert_eq!(Network::from_bytes(&bad).err(), Some(LoadError::Version(2)));
+
+        let mut bad = bytes.clone();
+        bad[9] = 0;
+        assert_eq!(Network::from_bytes(&bad).err(), Some(LoadError::Shape));
+
+        assert_eq!(Network::from_bytes(&bytes[..bytes.len() - 1]).err(), Some(LoadError::Truncated));
+        assert_eq!(Network::from_bytes(&bytes[..30]).err(), Some(LoadError::Truncated));
+
+        let mut long = bytes.clone();
+        long.push(0);
+        assert_eq!(Network::from_bytes(&long).err(), Some(LoadError::TrailingBytes));
+    }
+
+    #[test]
+    fn test_forward() {
+        init();
+
+        // 32 pieces and 4 castling planes make 288 ones at the start
+        let network = Network::from_bytes(&summing_network(0.001)).unwrap();
+        let (policy, value) = network.forward(&encode(&Board::default(), 0));
+
+        assert!(policy.iter().all(|x| *x == 0.0));
+        assert!((value - 0.288f32.tanh()).abs() < 1e-5, "{}", value);
+    }
+
+    #[test]
+    fn test_evaluator() {
+        init();
+
+        let mut evaluator = NetworkEvaluator::new(Network::new(&[32], 16, 5));
+        let board = Board::default();
+        let moves = board.get_legal_moves();
+
+        let evaluation = evaluator.evaluate(&board, 0, &moves);
+        assert_eq!(evaluation.priors.len(), 20);
+        assert!((evaluation.priors.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+
+        // the repetitions go into the network's input
+        assert_ne!(evaluator.evaluate(&board, 1, &moves), evaluation);
+
+        // and it guides the tree search like any other evaluator
+        let mut mcts = Mcts::new(&board, evaluator, MctsConfig::default());
+        let limits = SearchLimits { nodes: Some(50), ..SearchLimits::default() };
+        let result = mcts.search(&limits, &|| false, &mut |_| {}).unwrap();
+
+        assert_eq!(result.nodes, 50);
+        assert!(moves.contains(&result.best_move));
+    }
+}
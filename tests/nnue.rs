@@ -0,0 +1,195 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::nnue::{Accumulators, LoadError, Network, FEATURES};
+    use alpha_rust::search::{search_with, SearchLimits, SearchOptions, MATE};
+    use alpha_rust::tt::TranspositionTable;
+    use alpha_rust::{Board, Color, Move};
+
+    use std::sync::{Arc, Once};
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    fn mv(text: &str) -> Move {
+        text.parse().unwrap()
+    }
+
+    fn after(board: &Board, mv: Move) -> Board {
+        let mut child = board.clone();
+        assert!(child.play_move(mv), "{} in {}", mv, board.get_fen());
+        child
+    }
+
+    // the accumulators on top match the ones worked out from scratch
+    fn assert_fresh(accumulators: &Accumulators, network: &Network, board: &Board) {
+        let fresh = Accumulators::new(network, board);
+
+        for color in [Color::White, Color::Black].iter() {
+            assert_eq!(accumulators.values(*color), fresh.values(*color), "{}", board.get_fen());
+        }
+        assert_eq!(accumulators.evaluate(board.get_side_to_move()), network.evaluate(board));
+    }
+
+    #[test]
+    fn test_incremental_updates() {
+        init();
+
+        let network = Network::new(32, 8, 8, 1);
+
+        // castling both ways, en passant, promotions with and without a capture, king moves
+        let cases = [
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", vec!["e1g1", "e8c8", "a1b1", "h8e8"]),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", vec!["e1c1", "e8g8", "e2a6", "b4c3"]),
+            ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", vec!["e5f6", "g8f6", "e1e2", "d5d4", "c2c4", "d4c3"]),
+            ("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1", vec!["g2h1q", "b7a8n", "h1f1", "e2f1", "d7e6", "f1f2", "h2h1r"])
+        ];
+
+        for (fen, moves) in cases.iter() {
+            let root = Board::from_fen(fen).unwrap();
+            let mut accumulators = Accumulators::new(&network, &root);
+            let mut boards = vec![root];
+
+            for text in moves.iter() {
+                let board = boards.last().unwrap().clone();
+                let child = after(&board, mv(text));
+
+                accumulators.push(&board, mv(text), &child);
+                assert_fresh(&accumulators, &network, &child);
+                boards.push(child);
+            }
+
+            // taking the moves back gives the accumulators from before
+            while boards.len() > 1 {
+                boards.pop();
+                accumulators.pop();
+                assert_fresh(&accumulators, &network, boards.last().unwrap());
+            }
+        }
+
+        // a long game of whatever moves come to hand, a pass now and then
+        let mut board = Board::default();
+        let mut accumulators = Accumulators::new(&network, &board);
+
+        for ply in 0..200 {
+            let moves = board.get_legal_moves();
+            if moves.is_empty() { break; }
+
+            let chosen = moves[(ply * 7 + 3) % moves.len()];
+            let child = after(&board, chosen);
+            accumulators.push(&board, chosen, &child);
+
+            if ply % 10 == 0 {
+                accumulators.push_null();
+                assert_fresh(&accumulators, &network, &child);
+                accumulators.pop();
+            }
+
+            assert_fresh(&accumulators, &network, &child);
+            board = child;
+        }
+    }
+
+    #[test]
+    fn test_evaluation() {
+        init();
+
+        let network = Network::new(64, 16, 16, 2);
+        assert_eq!(network.shape(), (64, 16, 16));
+
+        // each side sees the board from its own end, so a position and its mirror image with the
+        // colours swapped are the same to the side to move
+        let white = Board::from_fen("rnbqkbnr/pppp1ppp/8/4p3/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let black = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_eq!(network.evaluate(&white), network.evaluate(&black));
+
+        // and the side to move matters
+        let passed = Board::from_fen("rnbqkbnr/pppp1ppp/8/4p3/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        let accumulators = Accumulators::new(&network, &white);
+        assert_eq!(accumulators.evaluate(Color::Black), network.evaluate(&passed));
+    }
+
+    #[test]
+    fn test_network_format() {
+        init();
+
+        let network = Network::new(16, 8, 4, 3);
+        let bytes = network.to_bytes();
+        assert_eq!(bytes.len(), 24 + 2 * 16 * (FEATURES + 1) + 8 * (4 + 32) + 4 * (4 + 8) + 4 + 4);
+        assert!(Network::from_bytes(&bytes).unwrap() == network);
+
+        // bytes built into the program load just the same
+        let embedded: &'static [u8] = Box::leak(bytes.clone().into_boxed_slice());
+        assert!(Network::from_bytes(embedded).unwrap() == network);
+
+        let mut bad = bytes.clone();
+        bad[0] = b'X';
+        assert_eq!(Network::from_bytes(&bad).err(), Some(LoadError::Magic));
+
+        let mut bad = bytes.clone();
+        bad[4] = 2;
+        assert_eq!(Network::from_bytes(&bad).err(), Some(LoadError::Version(2)));
+
+        let mut bad = bytes.clone();
+        bad[9] = 0;
+        assert_eq!(Network::from_bytes(&bad).err(), Some(LoadError::Shape));
+
+        // widths that could never fit in a file
+        let mut bad = bytes.clone();
+        bad[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(Network::from_bytes(&bad).err(), Some(LoadError::Truncated));
+
+        assert_eq!(Network::from_bytes(&bytes[..bytes.len() - 1]).err(), Some(LoadError::Truncated));
+        assert_eq!(Network::from_bytes(&bytes[..20]).err(), Some(LoadError::Truncated));
+
+        let mut long = bytes.clone();
+        long.push(0);
+        assert_eq!(Network::from_bytes(&long).err(), Some(LoadError::TrailingBytes));
+
+        // and from a file
+        let path = std::env::temp_dir().join(format!("alpha-rust-{}.nnue", std::process::id()));
+        std::fs::write(&path, &bytes).unwrap();
+        let loaded = Network::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(loaded.unwrap() == network);
+        assert!(Network::load("/no/such/file.nnue").is_err());
+    }
+
+    #[test]
+    fn test_search() {
+        init();
+
+        let options = SearchOptions { nnue: Some(Arc::new(Network::new(32, 8, 8, 4))), ..SearchOptions::default() };
+        let limits = SearchLimits { depth: Some(3), ..SearchLimits::default() };
+        let search = |fen: &str, options: &SearchOptions| {
+            let board = Board::from_fen(fen).unwrap();
+            search_with(&board, &[], &limits, &TranspositionTable::new(1), options, &|| false, &mut |_| {}).unwrap()
+        };
+
+        // a mate is a mate whatever the evaluation
+        let result = search("6k1/5ppp/8/8/8/8/8/4R1K1 w - - 0 1", &options);
+        assert_eq!(result.best_move.to_string(), "e1e8");
+        assert_eq!(result.score, MATE - 1);
+
+        // a different evaluation, a different search, and one that is the same every time
+        let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4";
+        let summary = |options: &SearchOptions| {
+            let result = search(fen, options);
+            (result.score, result.nodes, result.pv)
+        };
+        assert_eq!(summary(&options), summary(&options));
+        assert_ne!(summary(&options), summary(&SearchOptions::default()));
+
+        // with helper threads too
+        let threads = SearchOptions { threads: 3, ..options.clone() };
+        let result = search(fen, &threads);
+        assert!(Board::from_fen(fen).unwrap().get_legal_moves().contains(&result.best_move));
+    }
+}
@@ -0,0 +1,56 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::Board;
+
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    #[test]
+    fn test_perft_start_position() {
+        init();
+
+        let board = Board::new("".to_string());
+
+        assert_eq!(board.perft(0), 1);
+        assert_eq!(board.perft(1), 20);
+        assert_eq!(board.perft(2), 400);
+        assert_eq!(board.perft(3), 8902);
+    }
+
+    #[test]
+    fn test_perft_tricky_positions() {
+        init();
+
+        // "kiwipete", and positions 3 to 5 from the chess programming wiki
+        let positions = [
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 2039),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 191),
+            ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", 264),
+            ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", 1486)
+        ];
+
+        for (fen, nodes) in positions.iter() {
+            assert_eq!(Board::new(fen.to_string()).perft(2), *nodes, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_divide_adds_up() {
+        init();
+
+        let board = Board::new("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".to_string());
+        let divide = board.divide(2);
+
+        assert_eq!(divide.len(), 48);
+        assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), 2039);
+    }
+}
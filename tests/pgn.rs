@@ -0,0 +1,100 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::pgn::{Game, ParsePgnError};
+
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for uci in moves.iter() {
+            assert!(game.play(uci.parse().unwrap()), "{} is not legal", uci);
+        }
+    }
+
+    #[test]
+    fn test_write_pgn() {
+        init();
+
+        let mut game = Game::new();
+        game.set_tag("White", "Alice");
+        game.set_tag("Event", "Casual \"blitz\" game");
+
+        // fool's mate
+        play(&mut game, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+
+        let pgn = game.to_pgn();
+        assert!(pgn.starts_with("[Event \"Casual \\\"blitz\\\" game\"]\n[Site \"?\"]\n"));
+        assert!(pgn.contains("[White \"Alice\"]\n"));
+        assert!(pgn.contains("[Result \"0-1\"]\n"));
+        assert!(pgn.ends_with("\n\n1. f3 e5 2. g4 Qh4# 0-1\n"));
+    }
+
+    #[test]
+    fn test_write_from_position() {
+        init();
+
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 12");
+        play(&mut game, &["e8d7", "e2e4"]);
+
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 12\"]\n"));
+        assert!(pgn.ends_with("\n\n12... Kd7 13. e4 *\n"));
+    }
+
+    #[test]
+    fn test_long_games_wrap() {
+        init();
+
+        let mut game = Game::new();
+        for _ in 0..10 {
+            play(&mut game, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+        }
+
+        let pgn = game.to_pgn();
+        assert!(pgn.lines().all(|line| line.len() <= 80));
+        assert_eq!(Game::from_pgn(&pgn).unwrap(), game);
+    }
+
+    #[test]
+    fn test_read_pgn() {
+        init();
+
+        let pgn = "[Event \"Test\"]
+[White \"Bob\"]
+[Result \"1-0\"]
+
+1. e4 {best by test} e5 (1... c5 2. Nf3 (2. c3) d6) 2. Nf3 $1 Nc6 3.Bb5 a6; the Morphy
+4. Ba4 Nf6 5. O-O 1-0
+
+[Event \"Next game\"]
+
+1. d4 *
+";
+
+        let game: Game = pgn.parse().unwrap();
+        let moves: Vec<String> = game.moves().iter().map(|mv| mv.to_string()).collect();
+
+        assert_eq!(moves, vec!["e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6", "b5a4", "g8f6", "e1g1"]);
+        assert_eq!(game.tag("Event"), Some("Test"));
+        assert_eq!(game.tag("White"), Some("Bob"));
+        assert_eq!(game.tag("Black"), Some("?"));
+        assert_eq!(game.result(), "1-0");
+    }
+
+    #[test]
+    fn test_read_errors() {
+        init();
+
+        assert_eq!(Game::from_pgn("[Event Test]\n\n1. e4 *"), Err(ParsePgnError::Tag("[Event Test]".to_string())));
+        assert_eq!(Game::from_pgn("1. e4 e5 2. Ke3 *"), Err(ParsePgnError::Move { ply: 3, san: "Ke3".to_string() }));
+    }
+}
@@ -0,0 +1,103 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::{Board, Move, ParseSanError};
+
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    fn mv(uci: &str) -> Move {
+        uci.parse().unwrap()
+    }
+
+    #[test]
+    fn test_to_san() {
+        init();
+
+        let board = Board::new("".to_string());
+        assert_eq!(board.to_san(mv("e2e4")), "e4");
+        assert_eq!(board.to_san(mv("g1f3")), "Nf3");
+
+        // castling, captures, en passant and promotion with check
+        let board = Board::new("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1".to_string());
+        assert_eq!(board.to_san(mv("e1g1")), "O-O");
+        assert_eq!(board.to_san(mv("e1c1")), "O-O-O");
+        assert_eq!(board.to_san(mv("e5d6")), "exd6");
+        assert_eq!(board.to_san(mv("b7a8q")), "bxa8=Q+");
+        assert_eq!(board.to_san(mv("b7b8n")), "b8=N");
+
+        // mate
+        let board = Board::new("6k1/5ppp/8/8/8/8/8/4R1K1 w - - 0 1".to_string());
+        assert_eq!(board.to_san(mv("e1e8")), "Re8#");
+    }
+
+    #[test]
+    fn test_disambiguation() {
+        init();
+
+        // knights on b1 and f3 both reach d2, rooks on a1 and a5 both reach a3, and queens on
+        // h4, e4 and h1 all reach e1, h1 only by rank and e4 only by file
+        let board = Board::new("2k5/8/8/R7/4Q2Q/5N2/8/RN5Q w - - 0 1".to_string());
+
+        assert_eq!(board.to_san(mv("b1d2")), "Nbd2");
+        assert_eq!(board.to_san(mv("f3d2")), "Nfd2");
+        assert_eq!(board.to_san(mv("a1a3")), "R1a3");
+        assert_eq!(board.to_san(mv("a5a3")), "R5a3");
+        assert_eq!(board.to_san(mv("h4e1")), "Qh4e1");
+        assert_eq!(board.to_san(mv("e4e1")), "Qee1");
+    }
+
+    #[test]
+    fn test_parse_san() {
+        init();
+
+        let board = Board::new("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1".to_string());
+
+        assert_eq!(board.parse_san("O-O").unwrap().to_string(), "e1g1");
+        assert_eq!(board.parse_san("0-0-0").unwrap().to_string(), "e1c1");
+        assert_eq!(board.parse_san("exd6").unwrap().to_string(), "e5d6");
+        assert_eq!(board.parse_san("bxa8=Q+").unwrap().to_string(), "b7a8q");
+        assert_eq!(board.parse_san("b8N").unwrap().to_string(), "b7b8n");
+        assert_eq!(board.parse_san("Kf1!?").unwrap().to_string(), "e1f1");
+
+        assert_eq!(board.parse_san("Nf3"), Err(ParseSanError::Illegal));
+        assert_eq!(board.parse_san("b8"), Err(ParseSanError::Illegal));
+        assert_eq!(board.parse_san("hello"), Err(ParseSanError::Invalid));
+
+        let board = Board::new("2k5/8/8/R7/4Q2Q/5N2/8/RN5Q w - - 0 1".to_string());
+        assert_eq!(board.parse_san("Nd2"), Err(ParseSanError::Ambiguous));
+        assert_eq!(board.parse_san("Nbd2").unwrap().to_string(), "b1d2");
+        assert_eq!(board.parse_san("Nb1d2").unwrap().to_string(), "b1d2");
+    }
+
+    #[test]
+    fn test_san_round_trip() {
+        init();
+
+        let board = Board::new("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".to_string());
+
+        for legal in board.get_legal_moves() {
+            assert_eq!(board.parse_san(&board.to_san(legal)), Ok(legal));
+        }
+    }
+
+    #[test]
+    fn test_parse_move() {
+        init();
+
+        let board = Board::default();
+
+        assert_eq!(board.parse_move("g1f3"), Ok(mv("g1f3")));
+        assert_eq!(board.parse_move("Nf3"), Ok(mv("g1f3")));
+        assert_eq!(board.parse_move("e2e5"), Err(ParseSanError::Illegal));
+        assert_eq!(board.parse_move("xyz"), Err(ParseSanError::Invalid));
+    }
+}
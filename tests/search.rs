@@ -0,0 +1,270 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::search::{search, search_with, SearchInfo, SearchLimits, SearchOptions, BENCH_POSITIONS, MATE};
+    use alpha_rust::tt::TranspositionTable;
+    use alpha_rust::Board;
+
+    use std::cell::Cell;
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    fn depth(depth: u32) -> SearchLimits {
+        SearchLimits { depth: Some(depth), ..SearchLimits::default() }
+    }
+
+    #[test]
+    fn test_evaluate_is_symmetric() {
+        init();
+
+        let board = Board::new("".to_string());
+        assert_eq!(board.evaluate(), 0);
+
+        // a knight up for white, seen from both sides
+        let white = Board::new("rnbqkb1r/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string());
+        let black = Board::new("rnbqkb1r/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1".to_string());
+        assert!(white.evaluate() > 250);
+        assert_eq!(black.evaluate(), -white.evaluate());
+    }
+
+    #[test]
+    fn test_finds_mate_in_one() {
+        init();
+
+        // back rank mate with Re8#
+        let board = Board::new("6k1/5ppp/8/8/8/8/8/4R1K1 w - - 0 1".to_string());
+        let info = search(&board, &[], &depth(3), &|| false, &mut |_| {}).unwrap();
+
+        assert_eq!(info.pv[0].to_string(), "e1e8");
+        assert_eq!(info.score, MATE - 1);
+        assert_eq!(info.mate_in(), Some(1));
+    }
+
+    #[test]
+    fn test_takes_hanging_queen() {
+        init();
+
+        let board = Board::new("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1".to_string());
+        let info = search(&board, &[], &depth(2), &|| false, &mut |_| {}).unwrap();
+
+        assert_eq!(info.pv[0].to_string(), "d2d5");
+        assert!(info.score > 400);
+    }
+
+    #[test]
+    fn test_reports_every_depth() {
+        init();
+
+        let board = Board::new("".to_string());
+        let mut reports: Vec<SearchInfo> = Vec::new();
+        let info = search(&board, &[], &depth(3), &|| false, &mut |info| reports.push(info.clone())).unwrap();
+
+        assert_eq!(reports.iter().map(|info| info.depth).collect::<Vec<u32>>(), vec![1, 2, 3]);
+        assert_eq!(reports.last().map(|last| (&last.pv, last.score)), Some((&info.pv, info.score)));
+        assert_eq!(info.best_move, info.pv[0]);
+        assert_eq!(info.pv.len(), 3);
+        assert!(reports.windows(2).all(|pair| pair[0].nodes < pair[1].nodes));
+    }
+
+    #[test]
+    fn test_stops_when_asked() {
+        init();
+
+        let board = Board::new("".to_string());
+        let polls = Cell::new(0);
+
+        // stopping on the second poll still leaves the first iteration's move
+        let stop = || {
+            polls.set(polls.get() + 1);
+            polls.get() > 1
+        };
+        let info = search(&board, &[], &SearchLimits::default(), &stop, &mut |_| {}).unwrap();
+
+        assert!(!info.pv.is_empty());
+        assert!(info.depth < 10);
+
+        // a node limit stops it too
+        let limits = SearchLimits { nodes: Some(500), ..SearchLimits::default() };
+        let info = search(&board, &[], &limits, &|| false, &mut |_| {}).unwrap();
+
+        assert!(info.nodes <= 500);
+    }
+
+    #[test]
+    fn test_no_moves_means_no_result() {
+        init();
+
+        // black is checkmated
+        let board = Board::new("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1".to_string());
+
+        assert_eq!(search(&board, &[], &depth(2), &|| false, &mut |_| {}), None);
+    }
+
+    #[test]
+    fn test_finds_quiet_sacrifice() {
+        init();
+
+        // Morphy's mate in two, 1. Ra6 bxa6 2. b7#: the first move is a quiet rook sacrifice, which
+        // gets reduced at first but has to come through once the depth is enough for the mate
+        let board = Board::new("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1".to_string());
+        let info = search(&board, &[], &depth(5), &|| false, &mut |_| {}).unwrap();
+
+        assert_eq!(info.pv[0], "a1a6".parse().unwrap());
+        assert_eq!(info.mate_in(), Some(2));
+    }
+
+    #[test]
+    fn test_repetitions() {
+        init();
+
+        // a queen down, with the king's two moves as the only way on
+        let board = Board::new("k7/8/8/8/8/4q3/8/7K w - - 0 1".to_string());
+        let after = |mv: &str| {
+            let mut child = board.clone();
+            assert!(child.play_move(mv.parse().unwrap()));
+            child.hash()
+        };
+        let (g2, h2) = (after("h1g2"), after("h1h2"));
+
+        let lost = search(&board, &[], &depth(3), &|| false, &mut |_| {}).unwrap();
+        assert!(lost.score < -500);
+
+        // both moves going back to positions seen once before in the game is not enough yet
+        let once = search(&board, &[g2, h2], &depth(3), &|| false, &mut |_| {}).unwrap();
+        assert_eq!(once.score, lost.score);
+
+        // seen twice before, either move is a draw by threefold repetition
+        let twice = search(&board, &[g2, h2, g2, h2], &depth(3), &|| false, &mut |_| {}).unwrap();
+        assert_eq!(twice.score, 0);
+
+        // and with only one of them, that is the move
+        let one = search(&board, &[h2, h2], &depth(3), &|| false, &mut |_| {}).unwrap();
+        assert_eq!(one.score, 0);
+        assert_eq!(one.best_move, "h1h2".parse().unwrap());
+    }
+
+    #[test]
+    fn test_bench_positions() {
+        init();
+
+        for fen in BENCH_POSITIONS.iter() {
+            let board = Board::from_fen(fen).unwrap();
+            let first = search(&board, &[], &depth(2), &|| false, &mut |_| {}).unwrap();
+            let second = search(&board, &[], &depth(2), &|| false, &mut |_| {}).unwrap();
+
+            // the node counts are what bench compares, so they have to be the same every run
+            assert_eq!(first.nodes, second.nodes, "{}", fen);
+            assert_eq!(first.pv, second.pv, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_pv_is_legal() {
+        init();
+
+        let board = Board::new(BENCH_POSITIONS[1].to_string());
+        let result = search(&board, &[], &depth(4), &|| false, &mut |_| {}).unwrap();
+
+        // every move of the principal variation can be played in turn
+        let mut position = board.clone();
+        for mv in result.pv.iter() {
+            assert!(position.play_move(*mv), "{} in {:?}", mv, result.pv);
+        }
+
+        assert_eq!(result.ponder(), result.pv.get(1).copied());
+        assert_eq!(result.to_string(), format!("bestmove {} ponder {}", result.pv[0], result.pv[1]));
+    }
+
+    #[test]
+    fn test_table_is_kept() {
+        init();
+
+        // a search that starts from a table filled by the last one has less to do
+        let board = Board::new(BENCH_POSITIONS[2].to_string());
+        let tt = TranspositionTable::new(1);
+
+        let first = search_with(&board, &[], &depth(4), &tt, &SearchOptions::default(), &|| false, &mut |_| {}).unwrap();
+        let second = search_with(&board, &[], &depth(4), &tt, &SearchOptions::default(), &|| false, &mut |_| {}).unwrap();
+
+        assert!(second.nodes < first.nodes, "{} {}", first.nodes, second.nodes);
+        assert_eq!(second.best_move, first.best_move);
+    }
+
+    #[test]
+    fn test_threads() {
+        init();
+
+        let board = Board::new("6k1/5ppp/8/8/8/8/8/4R1K1 w - - 0 1".to_string());
+        let tt = TranspositionTable::new(1);
+        let threads = SearchOptions { threads: 4, ..SearchOptions::default() };
+        let result = search_with(&board, &[], &depth(4), &tt, &threads, &|| false, &mut |_| {}).unwrap();
+
+        assert_eq!(result.best_move.to_string(), "e1e8");
+        assert_eq!(result.mate_in(), Some(1));
+
+        // the helpers search a line from the middlegame too, and the pv still holds together
+        let board = Board::new(BENCH_POSITIONS[1].to_string());
+        let single = search(&board, &[], &depth(3), &|| false, &mut |_| {}).unwrap();
+        let result = search_with(&board, &[], &depth(3), &TranspositionTable::new(1), &threads, &|| false, &mut |_| {}).unwrap();
+
+        let mut position = board.clone();
+        for mv in result.pv.iter() {
+            assert!(position.play_move(*mv), "{} in {:?}", mv, result.pv);
+        }
+        assert_eq!(result.depth, single.depth);
+    }
+
+    #[test]
+    fn test_multipv() {
+        init();
+
+        let board = Board::default();
+        let options = SearchOptions { multipv: 3, ..SearchOptions::default() };
+        let mut reports: Vec<SearchInfo> = Vec::new();
+        let result = search_with(&board, &[], &depth(3), &TranspositionTable::new(1), &options, &|| false, &mut |info| reports.push(info.clone())).unwrap();
+
+        // three lines every depth, ranked, each with its own first move
+        assert_eq!(reports.iter().map(|info| (info.depth, info.multipv)).collect::<Vec<_>>(),
+            vec![(1, 1), (1, 2), (1, 3), (2, 1), (2, 2), (2, 3), (3, 1), (3, 2), (3, 3)]);
+        assert_eq!(&result.lines[..], &reports[6..]);
+        assert_eq!(result.pv, result.lines[0].pv);
+        assert!(result.lines.windows(2).all(|pair| pair[0].score >= pair[1].score));
+
+        let mut first: Vec<_> = result.lines.iter().map(|line| line.pv[0]).collect();
+        first.dedup();
+        assert_eq!(first.len(), 3);
+
+        // the lines run out with the moves: the king has two ways out of check
+        let board = Board::new("k7/8/8/8/8/8/8/6rK w - - 0 1".to_string());
+        let result = search_with(&board, &[], &depth(2), &TranspositionTable::new(1), &options, &|| false, &mut |_| {}).unwrap();
+        assert_eq!(result.lines.len(), 2);
+
+        // one line is the plain search
+        assert_eq!(search(&board, &[], &depth(2), &|| false, &mut |_| {}).unwrap().lines.len(), 1);
+    }
+
+    #[test]
+    fn test_info_line() {
+        init();
+
+        let info = SearchInfo {
+            depth: 3,
+            multipv: 2,
+            seldepth: 5,
+            score: MATE - 3,
+            nodes: 2000,
+            time: 100,
+            pv: vec!["e1e8".parse().unwrap()]
+        };
+
+        assert_eq!(info.to_string(), "depth 3 seldepth 5 multipv 2 score mate 2 nodes 2000 nps 20000 time 100 pv e1e8");
+    }
+}
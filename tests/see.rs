@@ -0,0 +1,80 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::{Board, Move, PieceType};
+
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    // builds a move from UCI notation
+    fn mv(uci: &str) -> Move {
+        uci.parse().unwrap()
+    }
+
+    #[test]
+    fn test_see_undefended_capture() {
+        init();
+
+        // Rxe5 wins a free pawn
+        let board = Board::new("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1".to_string());
+        assert_eq!(board.see(mv("e1e5")), 100);
+    }
+
+    #[test]
+    fn test_see_exchange_with_xrays() {
+        init();
+
+        // after Nxe5 Nxe5 white is better off stopping than going on into the recaptures by the
+        // queens lined up behind the rook and bishop
+        let board = Board::new("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1".to_string());
+        assert_eq!(board.see(mv("d3e5")), 100 - PieceType::Knight.value());
+
+        // the doubled rook keeps the queen after Rxd5 Rxd5 Rxd5
+        let board = Board::new("4k3/3r4/8/3q4/8/8/3R4/3RK3 w - - 0 1".to_string());
+        assert_eq!(board.see(mv("d2d5")), 900);
+
+        // without it, the rook is lost for the queen
+        let board = Board::new("4k3/3r4/8/3q4/8/8/3R4/4K3 w - - 0 1".to_string());
+        assert_eq!(board.see(mv("d2d5")), 400);
+    }
+
+    #[test]
+    fn test_see_king_recapture() {
+        init();
+
+        // the king cannot take back on a square covered by the bishop
+        let board = Board::new("8/8/3k4/4p3/8/8/1B6/4Q1K1 w - - 0 1".to_string());
+        assert_eq!(board.see(mv("e1e5")), 100);
+
+        let board = Board::new("8/8/3k4/4p3/8/8/8/4Q1K1 w - - 0 1".to_string());
+        assert_eq!(board.see(mv("e1e5")), -800);
+    }
+
+    #[test]
+    fn test_see_quiet_and_special_moves() {
+        init();
+
+        // Nb2 walks into the pawn's capture
+        let board = Board::new("4k3/8/8/8/8/2p5/8/3NK3 w - - 0 1".to_string());
+        assert_eq!(board.see(mv("d1b2")), -320);
+        assert!(!board.see_ge(mv("d1b2"), 0));
+        assert!(board.see_ge(mv("d1c3"), 0));
+
+        // en passant takes the pawn beside the capturing one
+        let board = Board::new("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2".to_string());
+        assert_eq!(board.see(mv("e5d6")), 100);
+
+        // capturing into the last rank counts the promotion
+        let board = Board::new("3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1".to_string());
+        let promotion = mv("e7d8q");
+        assert_eq!(board.see(promotion), 500 + 800);
+    }
+}
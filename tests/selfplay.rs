@@ -0,0 +1,156 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::mcts::{HeuristicEvaluator, MctsConfig};
+    use alpha_rust::search::SearchLimits;
+    use alpha_rust::selfplay::{Ending, Record, SelfPlay, SelfPlayConfig};
+    use alpha_rust::{Board, Color, GameStatus};
+
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    fn depth(depth: u32) -> SearchLimits {
+        SearchLimits { depth: Some(depth), ..SearchLimits::default() }
+    }
+
+    #[test]
+    fn test_checkmate() {
+        init();
+
+        let config = SelfPlayConfig { limits: depth(2), temperature: 0.0, ..SelfPlayConfig::default() };
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/4R1K1 w - - 0 1").unwrap();
+        let played = SelfPlay::alpha_beta(config).play_game(&board, &|| false).unwrap();
+
+        assert_eq!(played.ending, Ending::Status(GameStatus::Checkmate));
+        assert_eq!(played.result, 1);
+        assert_eq!(played.game.result(), "1-0");
+        assert_eq!(played.records.len(), 1);
+
+        let record = &played.records[0];
+        assert_eq!(record.best_move.to_string(), "e1e8");
+        assert_eq!(record.result, 1);
+        assert_eq!(played.to_jsonl(), format!(
+            "{{\"fen\":\"6k1/5ppp/8/8/8/8/8/4R1K1 w - - 0 1\",\"score\":{},\"best\":\"e1e8\",\"visits\":{{}},\"result\":1}}\n",
+            record.score
+        ));
+    }
+
+    #[test]
+    fn test_resignation() {
+        init();
+
+        // black is a queen down and gives up straight away
+        let config = SelfPlayConfig { limits: depth(2), resign_score: Some(500), resign_moves: 1, ..SelfPlayConfig::default() };
+        let board = Board::from_fen("4k3/8/8/8/8/8/3Q4/4K3 b - - 0 1").unwrap();
+        let played = SelfPlay::alpha_beta(config).play_game(&board, &|| false).unwrap();
+
+        assert_eq!(played.ending, Ending::Resigned(Color::Black));
+        assert_eq!(played.result, 1);
+        assert_eq!(played.game.result(), "1-0");
+        assert_eq!(played.game.tag("Termination"), Some("resignation"));
+        assert_eq!(played.records.len(), 1);
+        assert_eq!(played.records[0].result, -1);
+
+        // without resigning it plays on
+        let config = SelfPlayConfig { resign_score: None, max_plies: 6, ..config };
+        let played = SelfPlay::alpha_beta(config).play_game(&board, &|| false).unwrap();
+        assert_eq!(played.ending, Ending::Adjudicated);
+    }
+
+    #[test]
+    fn test_adjudication_and_temperature() {
+        init();
+
+        let config = SelfPlayConfig { limits: depth(1), max_plies: 8, temperature_plies: 8, ..SelfPlayConfig::default() };
+        let play = |seed: u64| {
+            SelfPlay::alpha_beta(SelfPlayConfig { seed, ..config }).play_game(&Board::default(), &|| false).unwrap()
+        };
+
+        let played = play(1);
+        assert_eq!(played.ending, Ending::Adjudicated);
+        assert_eq!(played.result, 0);
+        assert_eq!(played.game.result(), "1/2-1/2");
+        assert_eq!(played.records.len(), 8);
+        assert!(played.records.iter().all(|record| record.result == 0 && record.visits.is_empty()));
+
+        // the same seed plays the same game, a different one another game, and the moves played
+        // are not always the best ones
+        assert_eq!(play(1).game.moves(), played.game.moves());
+        assert!((2..6).any(|seed| play(seed).game.moves() != played.game.moves()));
+        assert!((1..6).map(play).any(|other| {
+            other.records.iter().zip(other.game.moves()).any(|(record, mv)| record.best_move != *mv)
+        }));
+    }
+
+    #[test]
+    fn test_low_temperature() {
+        init();
+
+        // nearly no temperature at all still plays the one good move, the rook taking the queen
+        let board = Board::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+        let config = SelfPlayConfig { limits: depth(2), max_plies: 1, temperature: 0.001, ..SelfPlayConfig::default() };
+
+        for seed in 1..6 {
+            let played = SelfPlay::alpha_beta(SelfPlayConfig { seed, ..config }).play_game(&board, &|| false).unwrap();
+            assert_eq!(played.game.moves()[0].to_string(), "d1d5");
+        }
+    }
+
+    #[test]
+    fn test_tree_search() {
+        init();
+
+        let config = SelfPlayConfig {
+            limits: SearchLimits { nodes: Some(40), ..SearchLimits::default() },
+            max_plies: 6,
+            ..SelfPlayConfig::default()
+        };
+        let noise = MctsConfig { noise_fraction: 0.25, ..MctsConfig::default() };
+        let mut selfplay = SelfPlay::tree_search(config, HeuristicEvaluator, noise);
+
+        // games one after the other start from scratch
+        for _ in 0..2 {
+            let played = selfplay.play_game(&Board::default(), &|| false).unwrap();
+            assert_eq!(played.records.len(), 6);
+            assert_eq!(played.records[0].visits.iter().map(|(_, n)| n).sum::<u32>(), 40);
+
+            // later moves keep the visits of the subtree they came from
+            for (record, mv) in played.records.iter().zip(played.game.moves()) {
+                assert!(record.visits.iter().map(|(_, n)| n).sum::<u32>() >= 40);
+                assert!(record.visits.iter().any(|(m, n)| m == mv && *n > 0));
+            }
+        }
+
+        // stopped, there is no game
+        assert!(selfplay.play_game(&Board::default(), &|| true).is_none());
+    }
+
+    #[test]
+    fn test_json() {
+        init();
+
+        let record = Record {
+            fen: "4k3/8/8/8/8/8/3Q4/4K3 b - - 0 1".to_string(),
+            score: -950,
+            best_move: "e8f7".parse().unwrap(),
+            visits: vec![("e8f7".parse().unwrap(), 30), ("e8e7".parse().unwrap(), 12)],
+            result: -1
+        };
+
+        let value: serde_json::Value = serde_json::from_str(&record.to_json()).unwrap();
+        assert_eq!(value["fen"], "4k3/8/8/8/8/8/3Q4/4K3 b - - 0 1");
+        assert_eq!(value["score"], -950);
+        assert_eq!(value["best"], "e8f7");
+        assert_eq!(value["visits"]["e8f7"], 30);
+        assert_eq!(value["visits"]["e8e7"], 12);
+        assert_eq!(value["result"], -1);
+    }
+}
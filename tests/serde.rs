@@ -0,0 +1,89 @@
+#![cfg(feature = "serde")]
+
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::pgn::Game;
+    use alpha_rust::{Board, Color, GameStatus, Move, PieceType, Square};
+
+    use serde_json::json;
+
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    #[test]
+    fn test_simple_types() {
+        init();
+
+        let square: Square = "e4".parse().unwrap();
+        assert_eq!(serde_json::to_value(square).unwrap(), json!("e4"));
+        assert_eq!(serde_json::from_value::<Square>(json!("e4")).unwrap(), square);
+        assert!(serde_json::from_value::<Square>(json!("i9")).is_err());
+
+        assert_eq!(serde_json::to_value(square.file()).unwrap(), json!("e"));
+        assert_eq!(serde_json::to_value(square.rank()).unwrap(), json!("4"));
+
+        let mv: Move = "e7e8q".parse().unwrap();
+        assert_eq!(serde_json::to_value(mv).unwrap(), json!("e7e8q"));
+        assert_eq!(serde_json::from_value::<Move>(json!("e7e8q")).unwrap(), mv);
+
+        assert_eq!(serde_json::to_value(PieceType::Knight).unwrap(), json!("Knight"));
+        assert_eq!(serde_json::to_value(Color::Black).unwrap(), json!("Black"));
+        assert_eq!(serde_json::to_value(GameStatus::Checkmate).unwrap(), json!("Checkmate"));
+        assert_eq!(serde_json::from_value::<PieceType>(json!("Queen")).unwrap(), PieceType::Queen);
+    }
+
+    #[test]
+    fn test_board_and_pieces() {
+        init();
+
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+        let board = Board::new(fen.to_string());
+
+        assert_eq!(serde_json::to_value(&board).unwrap(), json!(fen));
+
+        let back: Board = serde_json::from_value(json!(fen)).unwrap();
+        assert_eq!(back.get_fen(), fen);
+        assert_eq!(back.get_white_pieces(), board.get_white_pieces());
+
+        assert!(serde_json::from_value::<Board>(json!("not a fen")).is_err());
+
+        let pawn = board.find_piece("e4".parse().unwrap()).unwrap();
+        let value = serde_json::to_value(pawn).unwrap();
+        assert_eq!(value, json!({"kind": "Pawn", "color": "White", "square": "e4"}));
+        assert_eq!(&serde_json::from_value::<alpha_rust::Piece>(value).unwrap(), pawn);
+    }
+
+    #[test]
+    fn test_game() {
+        init();
+
+        let mut game = Game::new();
+        game.set_tag("White", "Alice");
+        for uci in ["e2e4", "e7e5", "e1e2"].iter() {
+            assert!(game.play(uci.parse().unwrap()));
+        }
+
+        let value = serde_json::to_value(&game).unwrap();
+        assert_eq!(value["start"], json!("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+        assert_eq!(value["moves"], json!(["e2e4", "e7e5", "e1e2"]));
+        assert_eq!(value["tags"][4], json!(["White", "Alice"]));
+
+        let back: Game = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(back, game);
+        assert_eq!(serde_json::to_value(&back).unwrap(), value);
+
+        // the moves are checked when read back
+        let mut illegal = value;
+        illegal["moves"] = json!(["e2e4", "e2e4"]);
+        assert!(serde_json::from_value::<Game>(illegal).is_err());
+    }
+}
@@ -0,0 +1,162 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::wasm::{BoardSingleton, Color, GameStatus, PieceType};
+    use alpha_rust::Board;
+
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    #[test]
+    fn test_legal_moves_from() {
+        init();
+
+        let board = BoardSingleton::new();
+        assert_eq!(board.legal_moves().len(), 20);
+
+        let mut moves = board.legal_moves_from("g1");
+        moves.sort();
+        assert_eq!(moves, vec!["g1f3", "g1h3"]);
+
+        assert!(board.legal_moves_from("e4").is_empty());
+        assert!(board.legal_moves_from("e7").is_empty());
+        assert!(board.legal_moves_from("z9").is_empty());
+    }
+
+    #[test]
+    fn test_play_and_undo() {
+        init();
+
+        let mut board = BoardSingleton::new();
+        let start = board.get_fen();
+
+        assert!(board.play("e2e4").is_ok());
+        assert_eq!(board.side_to_move(), Color::Black);
+        assert!(board.play("e7e5").is_ok());
+        assert_eq!(board.get_fen(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2");
+
+        assert!(board.undo());
+        assert!(board.undo());
+        assert_eq!(board.get_fen(), start);
+        assert!(!board.undo());
+
+        // a new position clears the history
+        assert!(board.play("d2d4").is_ok());
+        assert!(board.set_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").is_ok());
+        assert!(!board.undo());
+    }
+
+    #[test]
+    fn test_status() {
+        init();
+
+        let mut board = BoardSingleton::new();
+        assert_eq!(board.status(), GameStatus::Ongoing);
+
+        // fool's mate
+        for uci in ["f2f3", "e7e5", "g2g4", "d8h4"].iter() {
+            assert!(board.play(uci).is_ok());
+        }
+        assert_eq!(board.status(), GameStatus::Checkmate);
+
+        // shuffling the knights brings the starting position back a third time
+        assert!(board.set_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_ok());
+        for uci in ["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1"].iter() {
+            assert!(board.play(uci).is_ok());
+            assert_eq!(board.status(), GameStatus::Ongoing);
+        }
+        assert!(board.play("f6g8").is_ok());
+        assert_eq!(board.status(), GameStatus::Repetition);
+    }
+
+    #[test]
+    fn test_board_status() {
+        init();
+
+        let status = |fen: &str| GameStatus::from(Board::new(fen.to_string()).status());
+
+        assert_eq!(status("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"), GameStatus::Stalemate);
+        assert_eq!(status("4k3/8/8/8/8/8/4P3/4K3 w - - 100 80"), GameStatus::FiftyMoveRule);
+        assert_eq!(status("4k3/8/8/8/8/8/8/4K3 w - - 0 1"), GameStatus::InsufficientMaterial);
+        assert_eq!(status("4k3/8/8/8/8/8/8/2N1K3 w - - 0 1"), GameStatus::InsufficientMaterial);
+        // bishops on squares of the same color cannot mate, on opposite colors they can
+        assert_eq!(status("2b1k3/8/8/8/8/8/8/4KB2 w - - 0 1"), GameStatus::InsufficientMaterial);
+        assert_eq!(status("1b2k3/8/8/8/8/8/8/4KB2 w - - 0 1"), GameStatus::Ongoing);
+        assert_eq!(status("4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1"), GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn test_play_san() {
+        init();
+
+        // errors are JsErrors, which only exist on wasm, so bad input is covered by tests/web.rs
+        let mut board = BoardSingleton::new();
+
+        assert!(board.play("Nf3").is_ok());
+        assert!(board.play("d5").is_ok());
+        assert_eq!(board.get_last_move(), Some("d7d5".to_string()));
+    }
+
+    #[test]
+    fn test_pieces() {
+        init();
+
+        let board = BoardSingleton::new();
+        let pieces = board.pieces();
+
+        assert_eq!(pieces.len(), 32);
+        assert_eq!(pieces.iter().filter(|p| p.color() == Color::White).count(), 16);
+
+        let king = pieces.iter().find(|p| p.kind() == PieceType::King && p.color() == Color::Black).unwrap();
+        assert_eq!(king.square(), "e8");
+    }
+
+    #[test]
+    fn test_get_squares() {
+        init();
+
+        let board = BoardSingleton::new();
+        let squares = board.get_squares();
+
+        assert_eq!(squares.len(), 64);
+        // white back rank, pawns, empty middle and the black pieces
+        assert_eq!(squares[0..8], [4, 2, 3, 5, 6, 3, 2, 4]);
+        assert!(squares[8..16].iter().all(|c| *c == 1));
+        assert!(squares[16..48].iter().all(|c| *c == 0));
+        assert!(squares[48..56].iter().all(|c| *c == 7));
+        assert_eq!(squares[56..64], [10, 8, 9, 11, 12, 9, 8, 10]);
+    }
+
+    #[test]
+    fn test_snapshot_highlights() {
+        init();
+
+        let mut board = BoardSingleton::new();
+        assert_eq!(board.get_last_move(), None);
+        assert_eq!(board.get_check_square(), None);
+
+        for uci in ["e2e4", "f7f5", "d1h5"].iter() {
+            assert!(board.play(uci).is_ok());
+        }
+
+        assert_eq!(board.get_last_move(), Some("d1h5".to_string()));
+        // the black king on e8 is in check
+        assert_eq!(board.get_check_square(), Some(60));
+        // g6 is the only way out of check
+        assert_eq!(board.get_targets(54), vec![46]);
+        assert!(board.get_targets(63).is_empty());
+        assert!(board.get_targets(64).is_empty());
+
+        // promotions show up once per target square
+        assert!(board.set_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").is_ok());
+        assert_eq!(board.get_targets(48), vec![56]);
+    }
+}
@@ -0,0 +1,104 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::{Board, File, Move, PieceType, PromotionError, Rank, Square};
+
+    use std::collections::HashSet;
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    #[test]
+    fn test_square_round_trip() {
+        init();
+
+        let e4: Square = "e4".parse().unwrap();
+        assert_eq!(e4, Square::new(File::E, Rank::FOURTH));
+        assert_eq!(e4.index(), 28);
+        assert_eq!(e4.to_string(), "e4");
+        assert_eq!(e4.offset(1, 1), "f5".parse().ok());
+        assert_eq!(Square::from_index(63).unwrap().to_string(), "h8");
+
+        // every square survives a trip through algebraic notation
+        for square in Square::all() {
+            assert_eq!(square.to_string().parse::<Square>(), Ok(square));
+        }
+        assert_eq!(Square::all().count(), 64);
+    }
+
+    #[test]
+    fn test_invalid_coordinates() {
+        init();
+
+        for bad in ["", "e", "i1", "a0", "a9", "e44", "E4"].iter() {
+            assert!(bad.parse::<Square>().is_err(), "{} parsed as a square", bad);
+        }
+
+        assert_eq!(Square::from_index(64), None);
+        assert_eq!(File::new(8), None);
+        assert_eq!(Rank::new(8), None);
+        assert_eq!(Square::new(File::H, Rank::EIGHTH).offset(1, 0), None);
+        assert_eq!(Square::new(File::A, Rank::FIRST).offset(0, -1), None);
+    }
+
+    #[test]
+    fn test_move_packing() {
+        init();
+
+        let mv: Move = "e7e8q".parse().unwrap();
+        assert_eq!(mv.from().to_string(), "e7");
+        assert_eq!(mv.to().to_string(), "e8");
+        assert_eq!(mv.promotion(), Some(PieceType::Queen));
+        assert_eq!(mv.to_string(), "e7e8q");
+        assert_eq!(Move::from_bits(mv.bits()), Some(mv));
+        assert_eq!(std::mem::size_of::<Move>(), 2);
+
+        assert!("e7e8k".parse::<Move>().is_err());
+        assert!("e7e8p".parse::<Move>().is_err());
+        assert!("e7e8x".parse::<Move>().is_err());
+        assert!("e2e9".parse::<Move>().is_err());
+        assert_eq!(Move::from_bits(0x6000), None);
+
+        // only knights, bishops, rooks and queens can be promoted to
+        let (e7, e8) = (Square::new(File::E, Rank::SEVENTH), Square::new(File::E, Rank::EIGHTH));
+        assert_eq!(Move::new(e7, e8, Some(PieceType::Rook)).unwrap().to_string(), "e7e8r");
+        assert_eq!(Move::new(e7, e8, None).unwrap().to_string(), "e7e8");
+        assert_eq!(Move::new(e7, e8, Some(PieceType::King)), Err(PromotionError(PieceType::King)));
+        assert_eq!(Move::new(e7, e8, Some(PieceType::Pawn)), Err(PromotionError(PieceType::Pawn)));
+    }
+
+    #[test]
+    fn test_generated_move_flags() {
+        init();
+
+        let board = Board::new("r3k3/8/8/3pP3/8/8/8/R3K2R w KQq d6 0 1".to_string());
+        let moves = board.get_legal_moves();
+        let find = |uci: &str| *moves.iter().find(|m| m.to_string() == uci).unwrap();
+
+        assert!(find("e1g1").is_castle());
+        assert!(find("e1c1").is_castle());
+        assert!(find("e5d6").is_en_passant() && find("e5d6").is_capture());
+        assert!(find("a1a8").is_capture() && !find("a1a8").is_castle());
+        assert!(!find("e5e6").is_capture());
+
+        // moves built by hand equal the generated ones whatever the flags, and only need the
+        // right squares to be played
+        let parsed: Move = "e5d6".parse().unwrap();
+        assert_eq!(parsed, find("e5d6"));
+        assert!(!parsed.identical(&find("e5d6")) && find("e5d6").identical(&find("e5d6")));
+        assert!(moves.iter().copied().collect::<HashSet<Move>>().contains(&parsed));
+        assert_ne!(parsed, "e5d6q".parse().unwrap());
+
+        let mut board = Board::new("".to_string());
+        assert!(board.get_legal_moves().contains(&"e2e4".parse().unwrap()));
+        assert!(board.play_move("e2e4".parse().unwrap()));
+        assert_eq!(board.get_fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    }
+}
@@ -0,0 +1,93 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::svg::{render_svg, Arrow, Brush, Circle, RenderOptions, Theme};
+    use alpha_rust::{Board, Color, Square};
+
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    fn sq(square: &str) -> Square {
+        square.parse().unwrap()
+    }
+
+    #[test]
+    fn test_render_start_position() {
+        init();
+
+        let svg = render_svg(&Board::new("".to_string()), &RenderOptions::default());
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" viewBox=\"0 0 360 360\""));
+        assert!(svg.ends_with("</svg>"));
+        assert_eq!(svg.matches("<rect").count(), 64);
+        assert_eq!(svg.matches("<use").count(), 32);
+        // every piece is drawn once, with paths rather than text
+        assert_eq!(svg.matches("<g id=\"").count(), 12);
+        assert!(svg.contains(r##"<g id="black-knight""##));
+        assert!(!svg.contains('♜'));
+        // a1 is a dark square in the bottom left corner with the white rook on it
+        assert!(svg.contains(r##"<rect x="0" y="315" width="45" height="45" fill="#b58863"/>"##));
+        assert!(svg.contains(r##"<use xlink:href="#white-rook" x="0" y="315"/>"##));
+        // eight file letters and eight rank numbers
+        assert_eq!(svg.matches("font-weight=\"bold\"").count(), 16);
+    }
+
+    #[test]
+    fn test_flipped_without_coordinates() {
+        init();
+
+        let options = RenderOptions {
+            orientation: Color::Black,
+            coordinates: false,
+            theme: Theme::blue(),
+            ..RenderOptions::default()
+        };
+        let svg = render_svg(&Board::new("".to_string()), &options);
+
+        // from black's side a1 is in the top right corner
+        assert!(svg.contains(r##"<rect x="315" y="0" width="45" height="45" fill="#8ca2ad"/>"##));
+        assert!(svg.contains(r##"<use xlink:href="#white-rook" x="315" y="0"/>"##));
+        assert!(!svg.contains("font-weight"));
+
+        // only the pieces on the board are drawn
+        let kings = render_svg(&Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap(), &options);
+        assert_eq!(kings.matches("<g id=\"").count(), 2);
+        assert!(kings.contains(r##"<use xlink:href="#black-king" x="135" y="315"/>"##));
+    }
+
+    #[test]
+    fn test_highlights_and_annotations() {
+        init();
+
+        // 1. e4 f5 2. Qh5+
+        let mut board = Board::new("".to_string());
+        for uci in ["e2e4", "f7f5", "d1h5"].iter() {
+            assert!(board.play_move(uci.parse().unwrap()));
+        }
+
+        let options = RenderOptions {
+            last_move: Some("d1h5".parse().unwrap()),
+            arrows: vec![Arrow { from: sq("g7"), to: sq("g6"), brush: Brush::Green }, Arrow { from: sq("a1"), to: sq("a1"), brush: Brush::Red }],
+            circles: vec![Circle { square: sq("e8"), brush: Brush::Red }],
+            ..RenderOptions::default()
+        };
+        let svg = render_svg(&board, &options);
+
+        // two last move squares and the checked king on e8
+        assert_eq!(svg.matches("rgba(155, 199, 0, 0.41)").count(), 2);
+        assert!(svg.contains(r##"<rect x="180" y="0" width="45" height="45" fill="rgba(255, 0, 0, 0.6)"/>"##));
+        assert!(svg.contains(r##"<circle cx="202.5" cy="22.5" r="20.25" fill="none" stroke="#882020""##));
+        // arrows from a square to itself are left out
+        assert_eq!(svg.matches("<polygon").count(), 1);
+        assert!(svg.contains(r##"<g fill="#15781b" stroke="#15781b" opacity="0.8">"##));
+        assert!(svg.contains(r##"<polygon points="292.5,108 282.6,90 302.4,90" stroke="none"/>"##));
+    }
+}
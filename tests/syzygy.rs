@@ -0,0 +1,195 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::syzygy::{Tablebase, Wdl};
+    use alpha_rust::Board;
+
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    const FIXTURES: &str = "tests/fixtures/syzygy";
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    // writes a KQvK table pair where every position stores the same value, which is enough to
+    // exercise table lookup, color flipping and capture resolution without real tablebase files
+    //
+    // white to move always wins, black to move always loses, and wins are 9 moves from zeroing
+    fn single_value_tables(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("alpha-rust-syzygy-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        // header flags (split), piece order byte, pieces (side 0 in the low nibble, side 1 in the
+        // high one) and a padding byte, followed by one single value table per side
+        let wdl = [
+            0x71, 0xe8, 0x23, 0x5d,
+            0x01, 0x00, 0x66, 0x55, 0xee, 0x00,
+            0x80, 4,
+            0x80, 0
+        ];
+        let dtz = [
+            0xd7, 0x66, 0x0c, 0xa5,
+            0x01, 0x00, 0x06, 0x05, 0x0e, 0x00,
+            0x80, 9
+        ];
+
+        fs::write(dir.join("KQvK.rtbw"), &wdl[..]).unwrap();
+        fs::write(dir.join("KQvK.rtbz"), &dtz[..]).unwrap();
+
+        dir
+    }
+
+    // opens the fixture tables, failing the test when one it needs is missing
+    fn fixtures(files: &[&str]) -> Tablebase {
+        for file in files.iter() {
+            assert!(Path::new(FIXTURES).join(file).exists(), "{} is missing from {}", file, FIXTURES);
+        }
+
+        Tablebase::open(FIXTURES).unwrap()
+    }
+
+    #[test]
+    fn test_probe_wdl_single_value() {
+        init();
+
+        let tb = Tablebase::open(single_value_tables("wdl")).unwrap();
+        assert_eq!(tb.max_pieces(), 3);
+
+        let board = Board::new("4k3/8/8/8/8/8/8/4K2Q w - - 0 1".to_string());
+        assert_eq!(board.probe_wdl(&tb), Some(Wdl::Win));
+
+        let board = Board::new("4k3/8/8/8/8/8/8/4KQ2 b - - 0 1".to_string());
+        assert_eq!(board.probe_wdl(&tb), Some(Wdl::Loss));
+
+        // black can take the hanging queen, which leaves a bare king draw
+        let board = Board::new("4k3/4Q3/8/8/8/8/8/4K3 b - - 0 1".to_string());
+        assert_eq!(board.probe_wdl(&tb), Some(Wdl::Draw));
+
+        // black holding the queen uses the same table with colors swapped
+        let board = Board::new("4K3/8/8/8/8/8/8/4kq2 b - - 0 1".to_string());
+        assert_eq!(board.probe_wdl(&tb), Some(Wdl::Win));
+
+        // no table for this material, and castling rights are never in the tables
+        let board = Board::new("4k3/8/8/8/8/8/8/4K2R w - - 0 1".to_string());
+        assert_eq!(board.probe_wdl(&tb), None);
+        let board = Board::new("4k3/8/8/8/8/8/8/4K2Q w K - 0 1".to_string());
+        assert_eq!(board.probe_wdl(&tb), None);
+    }
+
+    #[test]
+    fn test_probe_dtz_single_value() {
+        init();
+
+        let tb = Tablebase::open(single_value_tables("dtz")).unwrap();
+
+        // 9 moves stored without the plies flag are 18 plies, plus the move being made
+        let board = Board::new("4k3/8/8/8/8/8/8/4K2Q w - - 0 1".to_string());
+        assert_eq!(board.probe_dtz(&tb), Some(19));
+
+        let board = Board::new("4K3/8/8/8/8/8/8/4kq2 b - - 0 1".to_string());
+        assert_eq!(board.probe_dtz(&tb), Some(19));
+
+        // only white to move is stored, so black to move is resolved by looking one ply ahead
+        let board = Board::new("4k3/8/8/8/8/8/8/4KQ2 b - - 0 1".to_string());
+        assert_eq!(board.probe_dtz(&tb), Some(-20));
+
+        let board = Board::new("4k3/4Q3/8/8/8/8/8/4K3 b - - 0 1".to_string());
+        assert_eq!(board.probe_dtz(&tb), Some(0));
+    }
+
+    #[test]
+    fn test_best_tablebase_move_prefers_mate() {
+        init();
+
+        let tb = Tablebase::open(single_value_tables("best")).unwrap();
+        let board = Board::new("k7/8/1K6/8/8/8/8/7Q w - - 0 1".to_string());
+
+        let mv = board.best_tablebase_move(&tb).unwrap();
+        let mut after = board.clone();
+        assert!(after.play_move(mv));
+        assert!(after.in_check());
+        assert!(after.get_legal_moves().is_empty());
+    }
+
+    #[test]
+    fn test_probe_real_tables() {
+        init();
+
+        let tb = fixtures(&["KQvK.rtbw", "KQvK.rtbz", "KPvK.rtbw"]);
+
+        let board = Board::new("4k3/8/8/8/8/8/8/4K2Q w - - 0 1".to_string());
+        assert_eq!(board.probe_wdl(&tb), Some(Wdl::Win));
+
+        let board = Board::new("4k3/4Q3/8/8/8/8/8/4K3 b - - 0 1".to_string());
+        assert_eq!(board.probe_wdl(&tb), Some(Wdl::Draw));
+
+        // one of the longest KQvK wins, mate in 10
+        let board = Board::new("8/8/8/5k2/8/8/1Q6/K7 w - - 0 1".to_string());
+        assert_eq!(board.probe_dtz(&tb), Some(19));
+
+        // king in front of the pawn on the sixth rank wins, opposition in front of it draws
+        let board = Board::new("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1".to_string());
+        assert_eq!(board.probe_wdl(&tb), Some(Wdl::Loss));
+
+        let board = Board::new("8/8/8/8/8/4k3/4P3/4K3 w - - 0 1".to_string());
+        assert_eq!(board.probe_wdl(&tb), Some(Wdl::Draw));
+    }
+
+    #[test]
+    fn test_probe_four_pieces() {
+        init();
+
+        let tb = fixtures(&["KQvKR.rtbw", "KQvKR.rtbz", "KQvK.rtbw", "KQvK.rtbz", "KRvK.rtbw", "KRvK.rtbz"]);
+        assert_eq!(tb.max_pieces(), 4);
+
+        // the queen takes the rook straight away
+        let board = Board::new("8/8/8/8/8/2k5/8/K2Q3r w - - 0 1".to_string());
+        assert_eq!(board.probe_wdl(&tb), Some(Wdl::Win));
+        assert_eq!(board.probe_dtz(&tb), Some(1));
+
+        // the rook skewers the king and takes the queen behind it on the next move
+        let board = Board::new("8/8/1k6/r7/8/8/8/3K3Q b - - 0 1".to_string());
+        assert_eq!(board.probe_wdl(&tb), Some(Wdl::Win));
+        assert_eq!(board.probe_dtz(&tb), Some(3));
+
+        // the same with the colors swapped looks the table up from the other side
+        let board = Board::new("3k3q/8/8/8/R7/1K6/8/8 w - - 0 1".to_string());
+        assert_eq!(board.probe_wdl(&tb), Some(Wdl::Win));
+        assert_eq!(board.probe_dtz(&tb), Some(3));
+
+        // one of the longest wins, 31 moves before the rook falls
+        let board = Board::new("8/8/8/8/7Q/2k5/8/3r3K w - - 0 1".to_string());
+        assert_eq!(board.probe_wdl(&tb), Some(Wdl::Win));
+        assert_eq!(board.probe_dtz(&tb), Some(61));
+
+        let board = Board::new("8/8/2k4K/7Q/8/8/8/3r4 b - - 0 1".to_string());
+        assert_eq!(board.probe_dtz(&tb), Some(-62));
+    }
+
+    #[test]
+    fn test_best_tablebase_move_real_tables() {
+        init();
+
+        let tb = fixtures(&["KRvK.rtbw", "KRvK.rtbz"]);
+
+        // white wins and every tablebase move keeps the win
+        let board = Board::new("8/8/8/4k3/8/8/8/R3K3 w - - 0 1".to_string());
+        let dtz = board.probe_dtz(&tb).unwrap();
+        assert!(dtz > 0);
+
+        let mv = board.best_tablebase_move(&tb).unwrap();
+        let mut after = board.clone();
+        assert!(after.play_move(mv));
+        assert!(after.probe_dtz(&tb).unwrap() < 0);
+    }
+}
@@ -0,0 +1,108 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::search::{search, SearchLimits};
+    use alpha_rust::time::{TimeControl, TimeManager};
+    use alpha_rust::{Board, Move};
+
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    fn clock(remaining: u64, increment: u64) -> TimeControl {
+        TimeControl { remaining, increment, ..TimeControl::default() }
+    }
+
+    fn mv(text: &str) -> Move {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_limits() {
+        init();
+
+        // a minute spread over 30 moves, plus three quarters of the increment
+        let manager = TimeManager::new(&clock(60000, 1000));
+        assert_eq!(manager.soft_limit(), 2750);
+        assert_eq!(manager.hard_limit(), 11000);
+
+        // the moves to go replace the default, and the overhead comes off the top
+        let manager = TimeManager::new(&TimeControl { remaining: 10100, increment: 0, movestogo: Some(10), overhead: 100 });
+        assert_eq!(manager.soft_limit(), 1000);
+        assert_eq!(manager.hard_limit(), 4000);
+
+        // the last move before the time control may not use up the clock
+        let manager = TimeManager::new(&TimeControl { movestogo: Some(1), ..clock(1000, 0) });
+        assert_eq!(manager.soft_limit(), 500);
+        assert_eq!(manager.hard_limit(), 800);
+
+        // nor may a big increment with little time left
+        let manager = TimeManager::new(&clock(1000, 5000));
+        assert_eq!(manager.soft_limit(), 500);
+        assert_eq!(manager.hard_limit(), 800);
+
+        assert_eq!(TimeManager::new(&TimeControl { overhead: 50, ..clock(30, 0) }).hard_limit(), 0);
+    }
+
+    #[test]
+    fn test_stable_best_move_saves_time() {
+        init();
+
+        let mut manager = TimeManager::new(&clock(60000, 0));
+        let normal = manager.soft_limit();
+
+        for _ in 0..6 {
+            manager.update(mv("e2e4"), 30);
+        }
+
+        assert!(manager.soft_limit() < normal);
+        assert!(manager.out_of_time(normal * 3 / 4));
+    }
+
+    #[test]
+    fn test_unsettled_search_takes_longer() {
+        init();
+
+        // a best move that keeps changing
+        let mut manager = TimeManager::new(&clock(60000, 0));
+        let normal = manager.soft_limit();
+
+        for text in ["e2e4", "d2d4", "e2e4", "g1f3"].iter() {
+            manager.update(mv(text), 30);
+        }
+        assert!(manager.soft_limit() > normal);
+        assert!(manager.soft_limit() <= manager.hard_limit());
+
+        // a score that drops
+        let mut manager = TimeManager::new(&clock(60000, 0));
+        manager.update(mv("e2e4"), 50);
+        manager.update(mv("e2e4"), -50);
+        assert!(manager.soft_limit() > normal);
+        assert!(!manager.out_of_time(normal));
+    }
+
+    #[test]
+    fn test_search_on_the_clock() {
+        init();
+
+        let board = Board::default();
+        let limits = SearchLimits { clock: Some(clock(2000, 0)), ..SearchLimits::default() };
+        let hard_limit = TimeManager::new(&clock(2000, 0)).hard_limit();
+
+        let result = search(&board, &[], &limits, &|| false, &mut |_| {}).unwrap();
+        assert!(result.time <= hard_limit + 50, "{} ms", result.time);
+
+        // a forced move is played without thinking
+        let board = Board::new("k7/8/8/8/8/8/1r6/K1r5 w - - 0 1".to_string());
+        let result = search(&board, &[], &limits, &|| false, &mut |_| {}).unwrap();
+        assert_eq!(result.best_move.to_string(), "a1b2");
+        assert_eq!(result.depth, 1);
+    }
+}
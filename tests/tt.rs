@@ -0,0 +1,72 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::tt::{Bound, Entry, TranspositionTable};
+    use alpha_rust::Move;
+
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    fn entry(text: &str, score: i32, depth: u32, bound: Bound) -> Entry {
+        Entry { best_move: Some(text.parse::<Move>().unwrap()), score, depth, bound }
+    }
+
+    #[test]
+    fn test_store_and_probe() {
+        init();
+
+        let tt = TranspositionTable::new(1);
+        tt.new_search();
+
+        assert_eq!(tt.probe(42), None);
+
+        tt.store(42, entry("e2e4", -31990, 5, Bound::Lower));
+        assert_eq!(tt.probe(42), Some(entry("e2e4", -31990, 5, Bound::Lower)));
+
+        // a shallower search of the same position keeps the deeper one
+        tt.store(42, entry("d2d4", 10, 3, Bound::Exact));
+        assert_eq!(tt.probe(42).unwrap().depth, 5);
+
+        // an entry without a move keeps the move the position had
+        tt.store(42, Entry { best_move: None, score: 20, depth: 6, bound: Bound::Upper });
+        assert_eq!(tt.probe(42), Some(entry("e2e4", 20, 6, Bound::Upper)));
+
+        tt.clear();
+        assert_eq!(tt.probe(42), None);
+    }
+
+    #[test]
+    fn test_replacement() {
+        init();
+
+        // a single slot, so every hash lands on the same one
+        let tt = TranspositionTable::new(0);
+        tt.new_search();
+
+        tt.store(1, entry("e2e4", 0, 8, Bound::Exact));
+
+        // a much shallower entry does not push out a deep one from the same search
+        tt.store(2, entry("d2d4", 0, 5, Bound::Exact));
+        assert_eq!(tt.probe(1).unwrap().depth, 8);
+        assert_eq!(tt.probe(2), None);
+
+        // one nearly as deep does
+        tt.store(2, entry("d2d4", 0, 7, Bound::Exact));
+        assert_eq!(tt.probe(1), None);
+        assert_eq!(tt.probe(2).unwrap().depth, 7);
+
+        // and entries from an earlier search always make way
+        tt.new_search();
+        tt.store(1, entry("e2e4", 0, 1, Bound::Exact));
+        assert_eq!(tt.probe(2), None);
+        assert_eq!(tt.probe(1).unwrap().depth, 1);
+    }
+}
@@ -0,0 +1,271 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use alpha_rust::nn::Network;
+    use alpha_rust::nnue;
+    use alpha_rust::time::TimeControl;
+    use alpha_rust::uci::{parse_go, parse_position, parse_position_with_history, Uci};
+    use alpha_rust::{Board, Color};
+
+    use std::sync::{Arc, Mutex};
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn init() {
+        INIT.call_once(|| {
+            crate::utils::set_panic_hook();
+        });
+    }
+
+    // runs the commands, waits for the search and returns everything the engine said
+    fn session(commands: &[&str]) -> Vec<String> {
+        let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+        let mut uci = Uci::with_output(output.clone());
+
+        for command in commands.iter() {
+            assert!(uci.execute(command));
+        }
+        uci.wait();
+
+        let text = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        text.lines().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_handshake() {
+        init();
+
+        let lines = session(&["uci", "isready"]);
+
+        assert!(lines[0].starts_with("id name alpha-rust"));
+        assert!(lines.contains(&"uciok".to_string()));
+        assert_eq!(lines.last().unwrap(), "readyok");
+    }
+
+    #[test]
+    fn test_position() {
+        init();
+
+        let board = parse_position(&["startpos", "moves", "e2e4", "e7e5", "g1f3"]).unwrap();
+        assert_eq!(board.get_fen(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+        let words: Vec<&str> = std::iter::once("fen").chain(fen.split(' ')).collect();
+        assert_eq!(parse_position(&words).unwrap().get_fen(), Board::new(fen.to_string()).get_fen());
+
+        assert!(parse_position(&["startpos", "moves", "e2e5"]).is_err());
+        assert!(parse_position(&["fen", "8/8/8/8/8/8/8/8", "w", "-", "-"]).is_err());
+        assert!(parse_position(&["somewhere"]).is_err());
+
+        // the positions on the way, for the tree search's repetitions
+        let (board, history) = parse_position_with_history(&["startpos", "moves", "g1f3", "g8f6", "f3g1", "f6g8"]).unwrap();
+        assert_eq!(history.len(), 4);
+        assert_eq!(history[0], board.hash());
+        assert_eq!(parse_position_with_history(&["startpos"]).unwrap().1, Vec::<u64>::new());
+    }
+
+    #[test]
+    fn test_go_limits() {
+        init();
+
+        let limits = parse_go(&["depth", "5", "nodes", "1000", "movetime", "200"], Color::White).unwrap();
+        assert_eq!((limits.depth, limits.nodes, limits.movetime), (Some(5), Some(1000), Some(200)));
+
+        let infinite = parse_go(&["infinite"], Color::White).unwrap();
+        assert_eq!((infinite.depth, infinite.infinite), (None, true));
+        assert!(!parse_go(&["depth", "5"], Color::White).unwrap().infinite);
+        assert!(parse_go(&["depth"], Color::White).is_none());
+        assert!(parse_go(&["depth", "x"], Color::White).is_none());
+    }
+
+    #[test]
+    fn test_go_clock() {
+        init();
+
+        let args = ["wtime", "60000", "btime", "-20", "winc", "1000", "binc", "500", "movestogo", "20"];
+
+        let white = parse_go(&args, Color::White).unwrap().clock.unwrap();
+        assert_eq!(white, TimeControl { remaining: 60000, increment: 1000, movestogo: Some(20), overhead: 0 });
+
+        // a clock that has run out is read as no time left
+        let black = parse_go(&args, Color::Black).unwrap().clock.unwrap();
+        assert_eq!((black.remaining, black.increment), (0, 500));
+
+        // only the other side's clock is not a clock at all
+        assert_eq!(parse_go(&["btime", "60000"], Color::White).unwrap().clock, None);
+        assert!(parse_go(&["wtime"], Color::White).is_none());
+    }
+
+    #[test]
+    fn test_set_option() {
+        init();
+
+        let lines = session(&[
+            "setoption name Move Overhead value 100",
+            "setoption name Move Overhead value lots",
+            "setoption name Threads value 4",
+            "setoption name Threads value 0",
+            "setoption name MultiPV value 3",
+            "setoption name MultiPV value -1",
+            "setoption name Hash value 1",
+            "setoption name Search value MCTS",
+            "setoption name Search value Random",
+            "setoption name Style value Risky"
+        ]);
+
+        assert_eq!(lines, vec![
+            "info string bad value for Move Overhead: lots",
+            "info string bad value for Threads: 0",
+            "info string bad value for MultiPV: -1",
+            "info string bad value for Search: Random",
+            "info string unknown option: Style"
+        ]);
+    }
+
+    #[test]
+    fn test_go_on_the_clock() {
+        init();
+
+        // a second for the whole game leaves well under a second for this move
+        let start = std::time::Instant::now();
+        let lines = session(&["position startpos", "go wtime 1000 btime 1000"]);
+
+        assert!(start.elapsed().as_millis() < 1000);
+        assert!(lines.last().unwrap().starts_with("bestmove "));
+    }
+
+    #[test]
+    fn test_search_reports_pv_and_best_move() {
+        init();
+
+        // the rook mates on the back rank
+        let lines = session(&["position fen 6k1/5ppp/8/8/8/8/8/4R1K1 w - - 0 1", "go depth 3"]);
+
+        let info = lines.iter().rev().find(|line| line.starts_with("info depth")).unwrap();
+        assert!(info.contains("score mate 1"), "{}", info);
+        assert!(info.ends_with("pv e1e8"), "{}", info);
+        assert_eq!(lines.last().unwrap(), "bestmove e1e8");
+    }
+
+    #[test]
+    fn test_threads() {
+        init();
+
+        // four threads still find Morphy's mate in two
+        let lines = session(&[
+            "setoption name Threads value 4",
+            "position fen kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1",
+            "go depth 5"
+        ]);
+
+        let info = lines.iter().rev().find(|line| line.starts_with("info depth")).unwrap();
+        assert!(info.contains("score mate 2"), "{}", info);
+        assert!(lines.last().unwrap().starts_with("bestmove a1a6"));
+    }
+
+    #[test]
+    fn test_multipv() {
+        init();
+
+        let lines = session(&["setoption name MultiPV value 2", "position startpos", "go depth 2"]);
+        let last: Vec<&String> = lines.iter().filter(|line| line.starts_with("info depth 2 ")).collect();
+
+        assert_eq!(last.len(), 2);
+        assert!(last[0].contains(" multipv 1 "), "{}", last[0]);
+        assert!(last[1].contains(" multipv 2 "), "{}", last[1]);
+        assert!(lines.last().unwrap().starts_with("bestmove "));
+    }
+
+    #[test]
+    fn test_tree_search() {
+        init();
+
+        // nodes counts playouts, and the tree from the first go is used again for the second
+        let lines = session(&[
+            "setoption name Search value MCTS",
+            "position fen 4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1",
+            "go nodes 200",
+            "position fen 4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1 moves d2d5",
+            "go nodes 200"
+        ]);
+
+        let best: Vec<&String> = lines.iter().filter(|line| line.starts_with("bestmove ")).collect();
+        assert_eq!(best.len(), 2);
+        assert!(best[0].starts_with("bestmove d2d5"), "{}", best[0]);
+        assert!(lines.iter().any(|line| line.contains(" nodes 200 ")));
+    }
+
+    #[test]
+    fn test_weights_file() {
+        init();
+
+        let path = std::env::temp_dir().join(format!("alpha-rust-uci-{}.nn", std::process::id()));
+        std::fs::write(&path, Network::new(&[16], 8, 1).to_bytes()).unwrap();
+
+        let lines = session(&[
+            "setoption name WeightsFile value /no/such/file",
+            &format!("setoption name WeightsFile value {}", path.display()),
+            "setoption name Search value MCTS",
+            "position startpos",
+            "go nodes 20"
+        ]);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(lines[0].starts_with("info string could not load /no/such/file"), "{}", lines[0]);
+        assert!(lines.iter().any(|line| line.contains(" nodes 20 ")));
+        assert!(lines.last().unwrap().starts_with("bestmove "));
+    }
+
+    #[test]
+    fn test_eval_file() {
+        init();
+
+        let path = std::env::temp_dir().join(format!("alpha-rust-uci-{}.nnue", std::process::id()));
+        std::fs::write(&path, nnue::Network::new(8, 8, 8, 1).to_bytes()).unwrap();
+
+        let lines = session(&[
+            "setoption name EvalFile value /no/such/file",
+            &format!("setoption name EvalFile value {}", path.display()),
+            "position startpos",
+            "go depth 3",
+            "setoption name EvalFile value <empty>",
+            "go depth 3"
+        ]);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(lines[0].starts_with("info string could not load /no/such/file"), "{}", lines[0]);
+        assert_eq!(lines.iter().filter(|line| line.starts_with("bestmove ")).count(), 2);
+        assert_eq!(lines.iter().filter(|line| line.starts_with("info string")).count(), 1);
+    }
+
+    #[test]
+    fn test_go_infinite_waits_for_stop() {
+        init();
+
+        let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+        let mut uci = Uci::with_output(output.clone());
+        let said = || String::from_utf8(output.lock().unwrap().clone()).unwrap();
+
+        // a mate in one is found at once, but the answer has to wait for stop
+        assert!(uci.execute("position fen 6k1/5ppp/8/8/8/8/8/4R1K1 w - - 0 1"));
+        assert!(uci.execute("go infinite"));
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        assert!(said().contains(" mate 1 "), "{}", said());
+        assert!(!said().contains("bestmove"));
+
+        assert!(uci.execute("stop"));
+        assert_eq!(said().lines().last().unwrap(), "bestmove e1e8");
+    }
+
+    #[test]
+    fn test_stop() {
+        init();
+
+        // an infinite search only ends when it is stopped, and still names a move
+        let lines = session(&["position startpos", "go infinite", "stop"]);
+
+        assert!(lines.last().unwrap().starts_with("bestmove "));
+    }
+}
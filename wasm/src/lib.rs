@@ -0,0 +1,4 @@
+// The JavaScript bindings as a cdylib for wasm-pack. They live in alpha_rust::wasm, this only
+// links them into a library of their own.
+
+pub use alpha_rust::wasm::*;
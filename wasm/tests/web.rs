@@ -0,0 +1,48 @@
+//! Test suite for the Web and headless browsers.
+
+#![cfg(target_arch = "wasm32")]
+
+extern crate wasm_bindgen_test;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test]
+fn pass() {
+    assert_eq!(1 + 1, 2);
+}
+
+#[wasm_bindgen_test]
+fn play_rejects_bad_moves() {
+    let mut board = wasm_alpha_rust::BoardSingleton::new();
+
+    assert!(board.play("e2e5").is_err());
+    assert!(board.play("not a move").is_err());
+    assert!(board.play("e2e4").is_ok());
+}
+
+#[wasm_bindgen_test]
+fn set_fen_rejects_bad_positions() {
+    let mut board = wasm_alpha_rust::BoardSingleton::new();
+    let start = board.get_fen();
+
+    assert!(board.set_fen("8/8/8/8/8/8/8/8 w - - 0 1").is_err());
+    assert_eq!(board.get_fen(), start);
+    assert!(board.play("Qxd2").is_err());
+}
+
+#[wasm_bindgen_test]
+fn top_moves_are_ranked() {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    let mut board = wasm_alpha_rust::BoardSingleton::new();
+    assert!(board.set_fen("6k1/5ppp/8/8/8/8/8/4R1K1 w - - 0 1").is_ok());
+
+    let lines: js_sys::Array = board.top_moves(3, 2).unchecked_into();
+    let field = |i: u32, name: &str| js_sys::Reflect::get(&lines.get(i), &JsValue::from(name)).unwrap();
+
+    assert_eq!(lines.length(), 3);
+    assert_eq!(field(0, "pv").as_string().unwrap(), "e1e8");
+    assert_eq!(field(0, "mate").as_f64(), Some(1.0));
+    assert_eq!(field(2, "multipv").as_f64(), Some(3.0));
+}
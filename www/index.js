@@ -2,11 +2,88 @@ import * as wasm from "wasm-alpha-rust";
 
 let board = new wasm.BoardSingleton();
 
-let str = board.get_board_string();
+// index in the piece codes from get_squares, 0 is an empty square
+const GLYPHS = ["", "♙", "♘", "♗", "♖", "♕", "♔", "♟", "♞", "♝", "♜", "♛", "♚"];
+const FILES = "abcdefgh";
 
-let div = document.getElementById( 'root' );
+// search limits for each strength level, the time keeps the higher levels from stalling the game
+const STRENGTH = {
+    1: { depth: 1, movetime: 0 },
+    2: { depth: 2, movetime: 0 },
+    3: { depth: 3, movetime: 2000 },
+    4: { depth: 4, movetime: 4000 },
+    5: { depth: 0, movetime: 8000 },
+};
 
-div.insertAdjacentHTML( 'beforeend', str );
+// milliseconds lost on every engine move to the messages between the page and the worker
+const ENGINE_OVERHEAD = 50;
+
+const STATUS_MESSAGES = {
+    [wasm.GameStatus.Stalemate]: "Draw by stalemate",
+    [wasm.GameStatus.FiftyMoveRule]: "Draw by the fifty-move rule",
+    [wasm.GameStatus.InsufficientMaterial]: "Draw by insufficient material",
+    [wasm.GameStatus.Repetition]: "Draw by threefold repetition",
+};
+
+let selected = null;
+let flipped = false;
+
+// color the engine plays, null when both sides are moved by hand
+let engineColor = null;
+let thinking = false;
+
+// the search runs in a web worker, see worker.js; every search gets an id so replies to searches
+// that have since been abandoned can be told apart and ignored
+let worker = null;
+let searchId = 0;
+let analysisId = null;
+let engineId = null;
+
+// shared memory lets the page stop a search early, but only when it is cross-origin isolated
+const canStop = typeof SharedArrayBuffer === "function" && self.crossOriginIsolated;
+
+// flag of the search running in the worker, set to 1 to stop it
+let stopFlag = null;
+
+// the analysis shown, one line per candidate move
+let analysisLines = [];
+
+// why the last search failed in the worker, shown until the position changes
+let searchError = null;
+
+// the game clock, null when the game is not played on one; the side to move's time is counted
+// down from when the last move was made
+let clock = null;
+let flagged = null;
+
+render();
+
+setInterval(tickClock, 100);
+
+document.getElementById("analyze").addEventListener("click", () => {
+    if (analysisId !== null) {
+        stopAnalysis();
+    } else {
+        startAnalysis();
+    }
+});
+
+document.getElementById("new-game").addEventListener("click", () => {
+    const color = document.getElementById("color").value;
+
+    engineColor = color === "white" ? wasm.Color.Black : wasm.Color.White;
+    flipped = color === "black";
+
+    startClock(document.getElementById("time-control").value);
+    setFen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+});
+
+document.getElementById("takeback").addEventListener("click", takeback);
+
+document.getElementById("flip").addEventListener("click", () => {
+    flipped = !flipped;
+    render();
+});
 
 const form = document.getElementById('fen-form');
 form.addEventListener('submit', event => {
@@ -14,19 +91,377 @@ form.addEventListener('submit', event => {
 
     let input = document.getElementById("fen")
 
-    if(input.value.match(/\s*([rnbqkpRNBQKP1-8]+\/){7}([rnbqkpRNBQKP1-8]+)\s[bw-]\s(([a-hkqA-HKQ]{1,4})|(-))\s(([a-h][36])|(-))\s\d+\s\d+\s*/)) {
-        setFen(input.value);
+    // a position typed in is set up for moving both sides by hand
+    const previous = engineColor;
+    engineColor = null;
+
+    if (setFen(input.value)) {
+        clock = null;
+        flagged = null;
         input.value = "";
     } else {
+        engineColor = previous;
         input.value = "invalid FEN string"
     }
 });
 
+// sets up a position, returns false and leaves the board alone if the FEN is not valid
 function setFen(fen) {
-    board.set_fen(fen);
+    try {
+        board.set_fen(fen);
+    } catch (error) {
+        return false;
+    }
+
+    cancelEngine();
+    positionChanged();
+    return true;
+}
+
+// index of a square name like "e4", as used by get_squares
+function squareIndex(name) {
+    return (Number(name[1]) - 1) * 8 + FILES.indexOf(name[0]);
+}
+
+function squareName(index) {
+    return FILES[index % 8] + (Math.floor(index / 8) + 1);
+}
+
+// whether the player may move the pieces of the side to move
+function playerToMove() {
+    return !thinking && gameOngoing() && board.side_to_move() !== engineColor;
+}
+
+// plays the player's move from one square to another if it is legal, returns whether it was played
+function tryMove(from, to) {
+    if (!playerToMove()) return false;
+
+    const prefix = squareName(from) + squareName(to);
+    const moves = Array.from(board.legal_moves_from(squareName(from))).filter(mv => mv.startsWith(prefix));
+
+    if (moves.length === 0) return false;
+
+    // promotions come as one move per piece, the choice of piece is up to the player
+    const promotion = document.getElementById("promotion").value;
+    const move = moves.length === 1 ? moves[0] : moves.find(mv => mv.endsWith(promotion));
+
+    if (move === undefined) return false;
+
+    punchClock();
+    board.play(move);
+    positionChanged();
+
+    return true;
+}
+
+// takes back the last move of each side in a game, or the last move when moving both sides by hand
+function takeback() {
+    cancelEngine();
+
+    if (board.undo() && engineColor !== null && board.side_to_move() === engineColor) {
+        board.undo();
+    }
+
+    positionChanged();
+}
+
+function positionChanged() {
+    selected = null;
+    searchError = null;
+    render();
+
+    if (analysisId !== null) {
+        stopAnalysis();
+        startAnalysis();
+    }
+
+    if (engineColor !== null && board.side_to_move() === engineColor && gameOngoing()) {
+        startEngine();
+    }
+}
+
+function startWorker() {
+    worker = new Worker("./worker.js");
+
+    worker.onmessage = event => {
+        const message = event.data;
+
+        if (message.type === "info" && message.id === analysisId) {
+            showInfo(message);
+        } else if (message.type === "bestmove" && message.id === analysisId) {
+            analysisId = null;
+            document.getElementById("analyze").textContent = "Analyze";
+        } else if (message.type === "bestmove" && message.id === engineId) {
+            engineId = null;
+            thinking = false;
+
+            if (message.move !== null) {
+                punchClock();
+                board.play(message.move);
+            }
+            positionChanged();
+        } else if (message.type === "error" && (message.id === analysisId || message.id === engineId)) {
+            // no best move is coming, so stop waiting for one
+            if (message.id === analysisId) {
+                analysisId = null;
+                document.getElementById("analyze").textContent = "Analyze";
+            } else {
+                engineId = null;
+                thinking = false;
+            }
+
+            searchError = message.message;
+            render();
+        }
+    };
+}
+
+// stops whatever search the worker is running; without shared memory there is no way to reach
+// into a running search, so the worker goes instead
+function stopSearch() {
+    if (stopFlag !== null) {
+        Atomics.store(stopFlag, 0, 1);
+    } else if (worker !== null) {
+        worker.terminate();
+        worker = null;
+    }
+}
+
+// starts a search in the worker, returning its id; each search gets its own stop flag, so
+// stopping one never stops the next
+function search(limits) {
+    if (analysisId !== null || engineId !== null) stopSearch();
+    if (worker === null) startWorker();
+
+    searchError = null;
+    stopFlag = canStop ? new Int32Array(new SharedArrayBuffer(4)) : null;
+    searchId += 1;
+    worker.postMessage(Object.assign({ type: "go", id: searchId, fen: board.get_fen(), stop: stopFlag }, limits));
+
+    return searchId;
+}
+
+function startEngine() {
+    const strength = STRENGTH[document.getElementById("strength").value];
+
+    // on the clock the engine decides how long to think, the strength only limits the depth
+    const limits = clock === null ? strength : {
+        depth: strength.depth,
+        remaining: timeLeft(engineColor),
+        increment: clock.increment,
+        overhead: ENGINE_OVERHEAD,
+    };
+
+    // the engine and the analysis share the worker, so analysis is switched off while the engine plays
+    stopAnalysis();
+
+    thinking = true;
+    engineId = search(limits);
+    render();
+}
+
+function cancelEngine() {
+    if (engineId === null) return;
+
+    stopSearch();
+    engineId = null;
+    thinking = false;
+}
+
+function startAnalysis() {
+    if (thinking) return;
+
+    analysisId = search({ multipv: Number(document.getElementById("lines").value) });
+    analysisLines = [];
+    document.getElementById("analyze").textContent = "Stop";
+    document.getElementById("analysis-lines").replaceChildren();
+}
+
+function stopAnalysis() {
+    if (analysisId === null) return;
+
+    // with shared memory the worker finishes the search and reports a best move, which is ignored
+    stopSearch();
+    analysisId = null;
+    document.getElementById("analyze").textContent = "Analyze";
+}
+
+// one line per candidate move, each like "depth 6  +0.35  12345 nodes  80 kN/s  e2e4 e7e5 g1f3",
+// replaced as every depth finishes; the lines of a depth come in best first
+function showInfo(info) {
+    // scores are from the side to move's point of view, shown from white's
+    const sign = board.side_to_move() === wasm.Color.White ? 1 : -1;
+
+    let score;
+    if (info.mate !== null) {
+        score = "#" + sign * info.mate;
+    } else {
+        const white = sign * info.score;
+        score = (white >= 0 ? "+" : "") + (white / 100).toFixed(2);
+    }
+
+    if (info.multipv === 1) analysisLines = [];
+    analysisLines[info.multipv - 1] =
+        `depth ${info.depth}  ${score}  ${info.nodes} nodes  ${Math.round(info.nps / 1000)} kN/s  ${info.pv}`;
+
+    document.getElementById("analysis-lines").replaceChildren(...analysisLines.map(line => {
+        const div = document.createElement("div");
+        div.textContent = line;
+        return div;
+    }));
+}
+
+// starts the clock for a new game from a time control like "3+2", minutes and seconds of
+// increment, or stops it for an empty one
+function startClock(timeControl) {
+    flagged = null;
+
+    if (timeControl === "") {
+        clock = null;
+        return;
+    }
+
+    const [minutes, increment] = timeControl.split("+").map(Number);
+    clock = {
+        [wasm.Color.White]: minutes * 60000,
+        [wasm.Color.Black]: minutes * 60000,
+        increment: increment * 1000,
+        // time of the last move; the clock of the side to move runs from here
+        since: performance.now(),
+    };
+}
+
+// stops the clock of the side about to move and adds its increment
+function punchClock() {
+    if (clock === null) return;
+
+    const side = board.side_to_move();
+    const now = performance.now();
+
+    clock[side] += clock.increment - (now - clock.since);
+    clock.since = now;
+}
+
+// milliseconds left on a side's clock
+function timeLeft(color) {
+    const running = color === board.side_to_move() && gameOngoing();
+    return Math.max(0, clock[color] - (running ? performance.now() - clock.since : 0));
+}
+
+// a game on the clock is lost by the side whose time runs out
+function gameOngoing() {
+    return flagged === null && board.status() === wasm.GameStatus.Ongoing;
+}
+
+function tickClock() {
+    if (clock === null) {
+        document.getElementById("clock").textContent = "";
+        return;
+    }
+
+    if (gameOngoing() && timeLeft(board.side_to_move()) === 0) {
+        flagged = board.side_to_move();
+        cancelEngine();
+        render();
+    }
+
+    const format = ms => {
+        const seconds = Math.ceil(ms / 1000);
+        return Math.floor(seconds / 60) + ":" + String(seconds % 60).padStart(2, "0");
+    };
+
+    document.getElementById("clock").textContent =
+        `White ${format(timeLeft(wasm.Color.White))}  Black ${format(timeLeft(wasm.Color.Black))}`;
+}
+
+function statusText() {
+    const status = board.status();
+    const side = board.side_to_move() === wasm.Color.White ? "White" : "Black";
+
+    if (flagged !== null) {
+        return `${flagged === wasm.Color.White ? "Black" : "White"} wins on time`;
+    }
+    if (status === wasm.GameStatus.Checkmate) {
+        return `Checkmate, ${side === "White" ? "Black" : "White"} wins`;
+    }
+    if (status !== wasm.GameStatus.Ongoing) {
+        return STATUS_MESSAGES[status];
+    }
+    if (searchError !== null) {
+        return "The engine failed: " + searchError;
+    }
+
+    let text = thinking ? "Engine is thinking..." : `${side} to move`;
+    if (board.get_check_square() !== undefined) text = "Check! " + text;
+
+    return text;
+}
+
+function render() {
+    const squares = board.get_squares();
+    const check = board.get_check_square();
+    const lastMove = board.get_last_move();
+    const targets = selected === null ? [] : Array.from(board.get_targets(selected));
+
+    const highlighted = lastMove ? [squareIndex(lastMove.slice(0, 2)), squareIndex(lastMove.slice(2, 4))] : [];
+
+    const table = document.createElement("table");
+    const body = document.createElement("tbody");
+
+    for (let row = 0; row < 8; row++) {
+        const tr = document.createElement("tr");
+        const rank = flipped ? row : 7 - row;
+
+        for (let column = 0; column < 8; column++) {
+            const file = flipped ? 7 - column : column;
+            const index = rank * 8 + file;
+            const cell = document.createElement("td");
+
+            cell.textContent = GLYPHS[squares[index]];
+            cell.classList.add((rank + file) % 2 === 0 ? "dark" : "light");
+
+            if (highlighted.includes(index)) cell.classList.add("last-move");
+            if (index === check) cell.classList.add("check");
+            if (index === selected) cell.classList.add("selected");
+            if (targets.includes(index)) cell.classList.add("target");
+
+            // a click on a target moves the selected piece there, any other click selects
+            cell.addEventListener("click", () => {
+                if (selected !== null && tryMove(selected, index)) return;
+
+                selected = squares[index] !== 0 && selected !== index ? index : null;
+                render();
+            });
+
+            if (squares[index] !== 0) {
+                cell.draggable = true;
+                cell.addEventListener("dragstart", event => {
+                    event.dataTransfer.setData("text/plain", String(index));
+                    selected = index;
+                });
+            }
+
+            cell.addEventListener("dragover", event => event.preventDefault());
+            cell.addEventListener("drop", event => {
+                event.preventDefault();
+
+                const from = Number(event.dataTransfer.getData("text/plain"));
+                if (!tryMove(from, index)) {
+                    selected = null;
+                    render();
+                }
+            });
+
+            tr.appendChild(cell);
+        }
+
+        body.appendChild(tr);
+    }
+
+    table.appendChild(body);
 
-    let str = board.get_board_string();
-    let div = document.getElementById( 'root' );
+    const root = document.getElementById('root');
+    root.replaceChildren(table);
 
-    div.innerHTML = str;
-}
\ No newline at end of file
+    document.getElementById("status").textContent = statusText();
+}
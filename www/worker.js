@@ -0,0 +1,55 @@
+// Runs searches off the main thread so the page stays responsive while the engine thinks.
+//
+// Messages in:
+//   {type: "go", id, fen, depth, movetime, stop}  stop is an optional Int32Array on a SharedArrayBuffer
+// Messages out, with the id of the search they belong to:
+//   {type: "ready"}                               once the wasm module has loaded
+//   {type: "info", id, depth, seldepth, score, mate, nodes, nps, time, pv}
+//   {type: "bestmove", id, move, ponder}          move is null when there are no legal moves
+//   {type: "error", id, message}                  the search failed, no bestmove follows
+
+// like bootstrap.js, the wasm module has to be imported asynchronously
+const engine = import("wasm-alpha-rust");
+
+engine.then(() => postMessage({ type: "ready" }));
+
+onmessage = event => {
+    const message = event.data;
+
+    if (message.type === "go") {
+        engine.then(wasm => {
+            // anything left out of the message is no limit
+            const limits = new wasm.Limits();
+            limits.depth = message.depth || 0;
+            limits.movetime = message.movetime || 0;
+            limits.remaining = message.remaining || 0;
+            limits.increment = message.increment || 0;
+            limits.movestogo = message.movestogo || 0;
+            limits.overhead = message.overhead || 0;
+            limits.multipv = message.multipv || 0;
+
+            let result;
+            try {
+                result = wasm.analyze(
+                    message.fen,
+                    limits,
+                    info => postMessage(Object.assign({ type: "info", id: message.id }, info)),
+                    message.stop
+                );
+            } catch (error) {
+                // the page is waiting for a bestmove, so it has to hear about the failure instead
+                postMessage({ type: "error", id: message.id, message: String(error) });
+                return;
+            } finally {
+                limits.free();
+            }
+
+            postMessage({
+                type: "bestmove",
+                id: message.id,
+                move: result === undefined ? null : result.bestmove,
+                ponder: result === undefined ? null : result.ponder
+            });
+        });
+    }
+};